@@ -0,0 +1,412 @@
+//! Feeds mutated class files to every reader, checking that they fail with errors instead of panicking.
+//! Also checks that [`verify`] finds no problem in the original classes,
+//! and finds one in every mutated class that [`ClassFile::parse`] rejects.
+//!
+//! Usage: `cargo run --release --example fuzz <dir or .class>...`, with `MUTATIONS` per class (100 by default)
+//! and a `SEED`. Without paths, it fuzzes generated classes nesting element values, array dimensions,
+//! generic signatures and `Code` attributes around the nesting limits, which mutations cannot reach. Inputs that panic are saved as `fuzz-crash-<n>.class` in the current directory,
+//! and the last input is kept in `fuzz-current.class` if the process aborts.
+
+use std::{panic::AssertUnwindSafe, path::Path};
+
+use cm_jclass::{
+    attr::{Annotation, AttrMatch, Data, ElemValue, JAttr, MAX_NESTING, StackMapFrame, VerificationType},
+    jtype,
+    kotlin::{KotlinMetadata, METADATA_DESC},
+    pool::ClassPool,
+    verify::verify,
+    write::ClassFile,
+    AttrInfo, JClassBytesReader, JClassReader,
+};
+
+/// xorshift64, enough to spread mutations without a dependency
+struct Rng(u64);
+impl Rng {
+    const fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n.max(1) as u64) as usize
+    }
+}
+
+/// File holding the input being checked, to reproduce crashes that abort the process
+const CURRENT: &str = "fuzz-current.class";
+
+/// Values likely to hit bounds checks when written over counts, lengths and indices
+const INTERESTING: &[u32] = &[0, 1, 2, 0x7f, 0x80, 0xff, 0x7fff, 0x8000, 0xffff, 0x7fff_ffff, 0xffff_ffff];
+
+fn mutate(data: &[u8], rng: &mut Rng) -> Vec<u8> {
+    let mut m = data.to_vec();
+    for _ in 0..=rng.below(3) {
+        let at = rng.below(m.len());
+        match rng.below(7) {
+            0 => if let Some(b) = m.get_mut(at) {
+                *b = rng.next() as u8;
+            },
+            1 => if let Some(b) = m.get_mut(at) {
+                *b ^= 1 << rng.below(8);
+            },
+            2 => if at + 2 <= m.len() {
+                let x = INTERESTING[rng.below(INTERESTING.len())] as u16;
+                m[at..at + 2].copy_from_slice(&x.to_be_bytes());
+            },
+            3 => if at + 4 <= m.len() {
+                let x = INTERESTING[rng.below(INTERESTING.len())];
+                m[at..at + 4].copy_from_slice(&x.to_be_bytes());
+            },
+            4 => m.truncate(at),
+            5 => {
+                let end = (at + rng.below(16)).min(m.len());
+                m.drain(at..end);
+            }
+            _ => {
+                let end = (at + rng.below(16)).min(m.len());
+                let copy = m[at..end].to_vec();
+                let to = rng.below(m.len());
+                m.splice(to..to, copy);
+            }
+        }
+    }
+    m
+}
+
+fn utf8(s: &str) -> Vec<u8> {
+    [&[1][..], &(s.len() as u16).to_be_bytes(), s.as_bytes()].concat()
+}
+fn attr(name: u16, body: &[u8]) -> Vec<u8> {
+    [&name.to_be_bytes()[..], &(body.len() as u32).to_be_bytes(), body].concat()
+}
+
+/// Class `T` extending `java/lang/Object`, with `extra` pool entries from index 5.
+/// Members and attributes are given with their counts.
+fn class_file(extra: &[Vec<u8>], fields: &[u8], methods: &[u8], attrs: &[u8]) -> Vec<u8> {
+    let mut out = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 52];
+    out.extend_from_slice(&(5 + extra.len() as u16).to_be_bytes());
+    out.extend(utf8("T"));
+    out.extend([7, 0, 1]);
+    out.extend(utf8("java/lang/Object"));
+    out.extend([7, 0, 3]);
+    out.extend(extra.concat());
+    // Public super, this class, superclass, no interfaces
+    out.extend([0, 0x21, 0, 2, 0, 4, 0, 0]);
+    out.extend([fields, methods, attrs].concat());
+    out
+}
+
+/// Classes nesting each structure `depth` levels deep
+fn deep_classes(depth: usize) -> Vec<(String, Vec<u8>)> {
+    let annotations = |level: &[u8]| {
+        let pool = [utf8("RuntimeVisibleAnnotations"), utf8("LA;"), utf8("v"), vec![3, 0, 0, 0, 0]];
+        // One annotation of type `A` with `v` set to the nested value, ending with an int
+        let value = [level.repeat(depth), vec![b'I', 0, 8]].concat();
+        let body = [&[0, 1, 0, 6, 0, 1, 0, 7][..], &value].concat();
+        class_file(&pool, &[0, 0], &[0, 0], &[&[0, 1][..], &attr(5, &body)].concat())
+    };
+    // Strings of the pool are at most 65535 bytes long
+    let dims = "[".repeat(depth.min(65_000));
+    let descriptors = {
+        let pool = [utf8("f"), utf8(&format!("{dims}I")), utf8("m"), utf8(&format!("({dims}I)V"))];
+        // A field and an abstract method
+        class_file(&pool, &[0, 1, 0, 0, 0, 5, 0, 6, 0, 0], &[0, 1, 0x04, 0x01, 0, 7, 0, 8, 0, 0], &[0, 0])
+    };
+    let signature = {
+        let pool = [utf8("Signature"), utf8(&format!("{}LA;{}", "LA<".repeat(depth.min(13_000)), ">;".repeat(depth.min(13_000))))];
+        class_file(&pool, &[0, 0], &[0, 0], &[&[0, 1][..], &attr(5, &[0, 6])].concat())
+    };
+    let code = {
+        // Each `Code` attribute holds a `return` and the next one
+        let mut code = vec![0, 0];
+        for _ in 0..depth {
+            let body = [&[0, 0, 0, 0, 0, 0, 0, 1, 0xB1, 0, 0][..], &code].concat();
+            code = [&[0, 1][..], &attr(5, &body)].concat();
+        }
+        let pool = [utf8("Code"), utf8("m"), utf8("()V")];
+        class_file(&pool, &[0, 0], &[&[0, 1, 0, 0x09, 0, 6, 0, 7][..], &code].concat(), &[0, 0])
+    };
+    vec![
+        (format!("nested arrays {depth}"), annotations(&[b'[', 0, 1])),
+        (format!("nested annotations {depth}"), annotations(&[b'@', 0, 6, 0, 1, 0, 7])),
+        (format!("array descriptors {depth}"), descriptors),
+        (format!("generic signature {depth}"), signature),
+        (format!("nested code {depth}"), code),
+    ]
+}
+
+fn annotation(an: &Data<Annotation>) -> anyhow::Result<()> {
+    an.type_name()?;
+    for e in an.elems() {
+        let e = e?;
+        e.name()?;
+        elem_value(e.value()?)?;
+    }
+    Ok(())
+}
+fn elem_value(v: ElemValue) -> anyhow::Result<()> {
+    match v {
+        ElemValue::Annotation(an) => annotation(&an),
+        ElemValue::Array(mut it) => it.try_for_each(|v| elem_value(v?.value()?)),
+        _ => Ok(()),
+    }
+}
+
+/// Walks attributes, `depth` being the number of `Code` and `Record` attributes around them
+fn attrs<T: AttrMatch>(it: impl Iterator<Item = anyhow::Result<AttrInfo<T>>>, pool: &ClassPool, depth: usize) -> anyhow::Result<()> {
+    if depth > MAX_NESTING {
+        anyhow::bail!("Attributes nested deeper than {MAX_NESTING} levels");
+    }
+    for ai in it {
+        let ai = ai?;
+        match ai.kind()? {
+            JAttr::AnnotationDefault(k) => elem_value(ai.read(&k)?.value()?)?,
+            JAttr::BootstrapMethods(k) => for bm in ai.read(&k)? {
+                let bm = bm?;
+                bm.owner()?;
+                for &a in bm.args() {
+                    pool.display_at(a)?;
+                }
+            },
+            JAttr::ConstantValue(k) => {
+                ai.read(&k)?;
+            }
+            JAttr::Code(k) => {
+                let code = ai.read(&k)?;
+                for insn in code.insns() {
+                    insn?.resolve(pool)?;
+                }
+                for h in code.handlers() {
+                    code.catch_type(h)?;
+                }
+                attrs(code.attrs(), pool, depth + 1)?;
+            }
+            JAttr::EnclosingMethod(k) => {
+                ai.read(&k)?;
+            }
+            JAttr::Exceptions(k) => {
+                ai.read(&k)?;
+            }
+            JAttr::NestMembers(k) => {
+                ai.read(&k)?;
+            }
+            JAttr::PermittedSubclasses(k) => {
+                ai.read(&k)?;
+            }
+            JAttr::InnerClasses(k) => for ic in ai.read(&k)? {
+                let ic = ic?;
+                ic.inner_name()?;
+                ic.outer_name()?;
+                ic.simple_name()?;
+            },
+            JAttr::LineNumberTable(k) => for l in ai.read(&k)? {
+                l?;
+            },
+            JAttr::LocalVariableTable(k) | JAttr::LocalVariableTypeTable(k) => for l in ai.read(&k)? {
+                let l = l?;
+                l.name()?;
+                l.descriptor()?;
+            },
+            JAttr::MethodParameters(k) => for p in ai.read(&k)? {
+                p.name()?;
+            },
+            JAttr::Module(k) => {
+                ai.read(&k)?;
+            }
+            JAttr::ModuleMainClass(k) => {
+                ai.read(&k)?;
+            }
+            JAttr::NestHost(k) => {
+                ai.read(&k)?;
+            }
+            JAttr::ModulePackages(k) => {
+                ai.read(&k)?;
+            }
+            JAttr::Record(k) => for rc in ai.read(&k)? {
+                let rc = rc?;
+                rc.name()?;
+                rc.descriptor()?;
+                attrs(rc.attrs(), pool, depth + 1)?;
+            },
+            JAttr::RuntimeVisibleAnnotations(k) | JAttr::RuntimeInvisibleAnnotations(k) => for an in ai.read(&k)? {
+                let an = an?;
+                annotation(&an)?;
+                if *an.type_name()? == *METADATA_DESC {
+                    if let Some(km) = KotlinMetadata::from_annotation(&an)? {
+                        km.describe();
+                    }
+                }
+            },
+            JAttr::RuntimeVisibleParameterAnnotations(k) | JAttr::RuntimeInvisibleParameterAnnotations(k) => for param in ai.read(&k)? {
+                for an in param {
+                    annotation(&an?)?;
+                }
+            },
+            JAttr::RuntimeVisibleTypeAnnotations(k) | JAttr::RuntimeInvisibleTypeAnnotations(k) => for ta in ai.read(&k)? {
+                annotation(&ta?.annotation())?;
+            },
+            JAttr::Signature(k) => {
+                ai.read(&k)?;
+            }
+            JAttr::SourceFile(k) => {
+                ai.read(&k)?;
+            }
+            JAttr::SourceDebugExtension(k) => {
+                ai.read(&k)?;
+            }
+            JAttr::StackMapTable(k) => for f in ai.read(&k)? {
+                let types = match f? {
+                    StackMapFrame::SameLocals1StackItem(_, v) => vec![v],
+                    StackMapFrame::Append(_, l) => l,
+                    StackMapFrame::Full(_, l, s) => l.into_iter().chain(s).collect(),
+                    _ => vec![],
+                };
+                for v in types {
+                    if let VerificationType::Object(i) = v {
+                        pool.get(pool.get(i)?)?;
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn read_stream(data: &[u8]) -> anyhow::Result<()> {
+    let jcr = JClassReader::new(data)?;
+    let pool = jcr.pool().clone();
+    for i in 1..pool.len() as u16 {
+        let _ = pool.display_at(i);
+    }
+    jcr.class_name()?;
+    jcr.super_class()?;
+    let (jcr, interfaces) = jcr.interfaces()?;
+    for i in interfaces {
+        i?;
+    }
+    let (jcr, fields) = jcr.fields()?;
+    for f in fields {
+        let mut f = f?;
+        jtype::render_field(&f.name()?.to_str_lossy(), &f.descriptor()?.to_str_lossy(), None)?;
+        attrs(f.attrs(), &pool, 0)?;
+    }
+    let (jcr, methods) = jcr.methods()?;
+    for m in methods {
+        let mut m = m?;
+        jtype::render_method(&m.name()?.to_str_lossy(), &m.descriptor()?.to_str_lossy(), None)?;
+        attrs(m.attrs(), &pool, 0)?;
+    }
+    let (_, class_attrs) = jcr.attributes()?;
+    attrs(class_attrs, &pool, 0)
+}
+
+fn read_bytes(data: &[u8]) -> anyhow::Result<()> {
+    let jcr = JClassBytesReader::new(data.to_vec())?;
+    let pool = jcr.pool();
+    for i in 0..=pool.len() as u16 {
+        let _ = pool.item(i);
+    }
+    pool.utf8s().count();
+    pool.items()?;
+    jcr.class_name()?;
+    jcr.super_class()?;
+    for i in jcr.interfaces()? {
+        i?;
+    }
+    jcr.reader().skip_interfaces()?.skip_fields()?.skip_methods()?.skip_attributes()?;
+    Ok(())
+}
+
+fn edit(data: &[u8]) -> anyhow::Result<()> {
+    let mut class = ClassFile::parse(data)?;
+    class.to_bytes()?;
+    class.strip_debug()?;
+    class.compact_pool()?;
+    ClassFile::parse(&class.to_bytes()?)?;
+    Ok(())
+}
+
+/// Runs every reader on the input, returning whether [`ClassFile::parse`] accepts it
+/// and the problems found by [`verify`].
+fn exercise(data: &[u8]) -> (bool, usize) {
+    let _ = read_stream(data);
+    let _ = read_bytes(data);
+    let _ = edit(data);
+    (ClassFile::parse(data).is_ok(), verify(data).len())
+}
+
+#[derive(Default)]
+struct Stats {
+    classes: usize,
+    /// Original classes with problems found by `verify`
+    flagged: usize,
+    mutants: usize,
+    panics: usize,
+    /// Mutants rejected by `ClassFile::parse` without a problem found by `verify`
+    missed: usize,
+}
+
+fn check(name: &str, data: Vec<u8>, rng: &mut Rng, mutations: usize, stats: &mut Stats) -> std::io::Result<()> {
+    stats.classes += 1;
+    let problems = verify(&data);
+    if !problems.is_empty() {
+        stats.flagged += 1;
+        eprintln!("{name}: {} problems, first: {}", problems.len(), problems[0]);
+    }
+    // The original comes first, so that saved inputs can be checked again with `MUTATIONS=0`
+    for i in 0..=mutations {
+        let m = if i == 0 { data.clone() } else { mutate(&data, rng) };
+        stats.mutants += usize::from(i > 0);
+        // Stack overflows abort the process without unwinding
+        std::fs::write(CURRENT, &m)?;
+        match std::panic::catch_unwind(AssertUnwindSafe(|| exercise(&m))) {
+            Ok((false, 0)) => {
+                stats.missed += 1;
+                eprintln!("{name}: rejected by the parser but not by verify");
+            }
+            Ok(_) => {}
+            Err(_) => {
+                let out = format!("fuzz-crash-{}.class", stats.panics);
+                eprintln!("{name}: panic, input saved as {out}");
+                std::fs::write(out, &m)?;
+                stats.panics += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn walk(path: &Path, rng: &mut Rng, mutations: usize, stats: &mut Stats) -> std::io::Result<()> {
+    if path.is_dir() {
+        for e in std::fs::read_dir(path)? {
+            walk(&e?.path(), rng, mutations, stats)?;
+        }
+    } else if path.extension().is_some_and(|x| x == "class") {
+        check(&path.display().to_string(), std::fs::read(path)?, rng, mutations, stats)?;
+    }
+    Ok(())
+}
+
+fn main() -> std::io::Result<()> {
+    let env = |k: &str, default: u64| std::env::var(k).ok().and_then(|x| x.parse().ok()).unwrap_or(default);
+    let mutations = env("MUTATIONS", 100) as usize;
+    let mut rng = Rng(env("SEED", 0x9E37_79B9_7F4A_7C15) | 1);
+    let mut stats = Stats::default();
+    let paths = std::env::args_os().skip(1).collect::<Vec<_>>();
+    for arg in &paths {
+        walk(Path::new(arg), &mut rng, mutations, &mut stats)?;
+    }
+    if paths.is_empty() {
+        for depth in [1, 2, MAX_NESTING - 1, MAX_NESTING, MAX_NESTING + 1, 511, 512, 513, 100_000] {
+            for (name, data) in deep_classes(depth) {
+                check(&name, data, &mut rng, mutations, &mut stats)?;
+            }
+        }
+    }
+    std::fs::remove_file(CURRENT).ok();
+    println!("{} classes, {} with problems", stats.classes, stats.flagged);
+    println!("{} mutants, {} panics, {} rejected without problems", stats.mutants, stats.panics, stats.missed);
+    Ok(())
+}
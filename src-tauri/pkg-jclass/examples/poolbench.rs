@@ -0,0 +1,231 @@
+//! Compares the throughput of the original streaming reader, [`JClassReader`] and [`JClassBytesReader`]
+//! on class files held in memory.
+//!
+//! Usage: `cargo run --release --example poolbench <dir or .class>...`
+
+use std::{path::Path, time::{Duration, Instant}};
+
+use bytes::Bytes;
+use cm_jclass::{idx::Utf8, pool::PoolIter, JClassBytesReader, JClassReader};
+
+const ROUNDS: usize = 5;
+
+/// The reader before the lazy pool: every `Utf8` item copied into its own `Arc<[u8]>`,
+/// fields skipped byte by byte and methods copied out before they are iterated.
+mod baseline {
+    use std::{io::Read, sync::Arc};
+
+    use byteorder::{ReadBytesExt, BE};
+    use bytes::{Buf, BufMut, BytesMut};
+    use cm_jclass::pool::PoolItem;
+
+    enum Item {
+        Utf8(Arc<[u8]>),
+        Class(u16),
+        Other,
+    }
+
+    pub struct Reader<'a> {
+        r: &'a [u8],
+        pool: Arc<[Item]>,
+        class_ref: u16,
+        super_ref: u16,
+    }
+    impl<'a> Reader<'a> {
+        pub fn new(mut r: &'a [u8]) -> anyhow::Result<Self> {
+            r.read_u32::<BE>()?;
+            r.read_u16::<BE>()?;
+            let major = r.read_u16::<BE>()?;
+            let count = r.read_u16::<BE>()? as usize;
+            let mut pool = Vec::with_capacity(count);
+            pool.push(Item::Other);
+            while pool.len() < count {
+                let tag = r.read_u8()?;
+                pool.push(if tag == 1 {
+                    let mut b = vec![0; r.read_u16::<BE>()? as usize];
+                    r.read_exact(&mut b)?;
+                    Item::Utf8(Arc::from(b.into_boxed_slice()))
+                } else {
+                    match PoolItem::read_from(tag, major, &mut r)? {
+                        PoolItem::Class(i) => Item::Class(i.get()),
+                        _ => Item::Other,
+                    }
+                });
+                if tag == 5 || tag == 6 {
+                    pool.push(Item::Other);
+                }
+            }
+            r.read_u16::<BE>()?;
+            let class_ref = r.read_u16::<BE>()?;
+            let super_ref = r.read_u16::<BE>()?;
+            Ok(Self { r, pool: Arc::from(pool.into_boxed_slice()), class_ref, super_ref })
+        }
+        fn class(&self, idx: u16) -> anyhow::Result<Arc<[u8]>> {
+            match self.pool.get(idx as usize) {
+                Some(Item::Class(i)) => match self.pool.get(*i as usize) {
+                    Some(Item::Utf8(s)) => Ok(Arc::clone(s)),
+                    _ => anyhow::bail!("Invalid pool item"),
+                },
+                _ => anyhow::bail!("Invalid pool item"),
+            }
+        }
+        pub fn class_name(&self) -> anyhow::Result<Arc<[u8]>> {
+            self.class(self.class_ref)
+        }
+        pub fn super_class(&self) -> anyhow::Result<Option<Arc<[u8]>>> {
+            if self.super_ref == 0 { Ok(None) } else { self.class(self.super_ref).map(Some) }
+        }
+        pub fn utf8s(&self) -> impl Iterator<Item = &[u8]> {
+            self.pool.iter().filter_map(|pi| match pi {
+                Item::Utf8(s) => Some(&s[..]),
+                _ => None,
+            })
+        }
+        pub fn interfaces(&mut self) -> anyhow::Result<Vec<Arc<[u8]>>> {
+            let mut v = vec![0; self.r.read_u16::<BE>()? as usize];
+            self.r.read_u16_into::<BE>(&mut v)?;
+            v.into_iter().map(|i| self.class(i)).collect()
+        }
+        pub fn method_count(mut self) -> anyhow::Result<usize> {
+            let n = self.r.read_u16::<BE>()?;
+            self.r.read_exact(&mut vec![0; n as usize * 2])?;
+            // Fields
+            for _ in 0..self.r.read_u16::<BE>()? {
+                self.r.read_exact(&mut [0; 6])?;
+                for _ in 0..self.r.read_u16::<BE>()? {
+                    self.r.read_exact(&mut [0; 2])?;
+                    let len = self.r.read_u32::<BE>()? as usize;
+                    if (&mut self.r).bytes().take(len).count() != len {
+                        anyhow::bail!("Invalid length");
+                    }
+                }
+            }
+            // Methods, copied and then walked one by one
+            let mut bmut = BytesMut::new();
+            let count = self.r.read_u16::<BE>()?;
+            for _ in 0..count {
+                let mut n = [0; 6];
+                self.r.read_exact(&mut n)?;
+                bmut.put_slice(&n);
+                let attrs = self.r.read_u16::<BE>()?;
+                bmut.put_u16(attrs);
+                for _ in 0..attrs {
+                    bmut.put_u16(self.r.read_u16::<BE>()?);
+                    let len = self.r.read_u32::<BE>()?;
+                    bmut.put_u32(len);
+                    std::io::copy(&mut Read::take(&mut self.r, len.into()), &mut (&mut bmut).writer())?;
+                }
+            }
+            let mut b = bmut.freeze();
+            let mut n = 0;
+            for _ in 0..count {
+                let mut len = 8;
+                let mut at = b.clone();
+                at.advance(6);
+                for _ in 0..at.get_u16() {
+                    at.advance(2);
+                    let l = at.get_u32() as usize;
+                    at.advance(l);
+                    len += 6 + l;
+                }
+                let _member = (b.split_to(len), Arc::clone(&self.pool));
+                n += 1;
+            }
+            Ok(n)
+        }
+    }
+}
+
+fn walk(path: &Path, out: &mut Vec<Bytes>) -> std::io::Result<()> {
+    if path.is_dir() {
+        for e in std::fs::read_dir(path)? {
+            walk(&e?.path(), out)?;
+        }
+    } else if path.extension().is_some_and(|x| x == "class") {
+        out.push(std::fs::read(path)?.into());
+    }
+    Ok(())
+}
+
+/// Best time of [`ROUNDS`] runs of `f` over all classes, with a checksum to compare the readers
+fn bench(classes: &[Bytes], f: impl Fn(&Bytes) -> anyhow::Result<usize>) -> anyhow::Result<(Duration, usize)> {
+    let mut best = Duration::MAX;
+    let mut sum = 0;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        sum = 0;
+        for c in classes {
+            sum += f(c)?;
+        }
+        best = best.min(start.elapsed());
+    }
+    Ok((best, sum))
+}
+
+fn report(name: &str, bytes: usize, runs: [(Duration, usize); 3]) {
+    let mbs = |d: Duration| bytes as f64 / d.as_secs_f64() / 1e6;
+    let [old, reader, bytes_reader] = runs;
+    let col = |(d, _): (Duration, usize)| format!("{d:>8.1?} ({:>5.0} MB/s) x{:.1}", mbs(d), old.0.as_secs_f64() / d.as_secs_f64());
+    println!(
+        "{name:<8} baseline {:>8.1?} ({:>5.0} MB/s)  reader {}  bytes {}{}",
+        old.0, mbs(old.0), col(reader), col(bytes_reader),
+        if runs.iter().all(|r| r.1 == old.1) { "" } else { "  MISMATCH" },
+    );
+}
+
+fn main() -> anyhow::Result<()> {
+    let mut classes = vec![];
+    for arg in std::env::args_os().skip(1) {
+        walk(Path::new(&arg), &mut classes)?;
+    }
+    let bytes = classes.iter().map(Bytes::len).sum();
+    println!("{} classes, {bytes} bytes, best of {ROUNDS}", classes.len());
+
+    // Class name and all strings, as for the string index
+    let old = bench(&classes, |b| {
+        let jcr = baseline::Reader::new(b)?;
+        Ok(jcr.class_name()?.len() + jcr.utf8s().map(<[u8]>::len).sum::<usize>())
+    })?;
+    let reader = bench(&classes, |b| {
+        let jcr = JClassReader::new(&b[..])?;
+        Ok(jcr.class_name()?.len() + jcr.iter_pool().by_type::<Utf8>().map(|s| s.len()).sum::<usize>())
+    })?;
+    let new = bench(&classes, |b| {
+        let jcr = JClassBytesReader::new(b.clone())?;
+        Ok(jcr.class_name()?.len() + jcr.pool().utf8s().map(|s| s.len()).sum::<usize>())
+    })?;
+    report("strings", bytes, [old, reader, new]);
+
+    // Class name and supertypes, as for the class hierarchy
+    let old = bench(&classes, |b| {
+        let mut jcr = baseline::Reader::new(b)?;
+        let n = jcr.class_name()?.len() + jcr.super_class()?.map_or(0, |s| s.len());
+        Ok(n + jcr.interfaces()?.iter().map(|i| i.len()).sum::<usize>())
+    })?;
+    let reader = bench(&classes, |b| {
+        let jcr = JClassReader::new(&b[..])?;
+        let n = jcr.class_name()?.len() + jcr.super_class()?.map_or(0, |s| s.len());
+        let (_, ii) = jcr.interfaces()?;
+        ii.map(|i| Ok(i?.len())).sum::<anyhow::Result<usize>>().map(|x| x + n)
+    })?;
+    let new = bench(&classes, |b| {
+        let jcr = JClassBytesReader::new(b.clone())?;
+        let n = jcr.class_name()?.len() + jcr.super_class()?.map_or(0, |s| s.len());
+        let ii = jcr.interfaces()?.map(|i| Ok(i?.len())).sum::<anyhow::Result<usize>>()?;
+        Ok(n + ii)
+    })?;
+    report("header", bytes, [old, reader, new]);
+
+    // Method count, going through the streaming reader
+    let old = bench(&classes, |b| baseline::Reader::new(b)?.method_count())?;
+    let reader = bench(&classes, |b| {
+        let (_, mi) = JClassReader::new(&b[..])?.skip_interfaces()?.skip_fields()?.methods()?;
+        Ok(mi.count())
+    })?;
+    let new = bench(&classes, |b| {
+        let (_, mi) = JClassBytesReader::new(b.clone())?.reader().skip_interfaces()?.skip_fields()?.methods()?;
+        Ok(mi.count())
+    })?;
+    report("methods", bytes, [old, reader, new]);
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! Checks that class files are written back unchanged, and that they can be compacted and stripped.
+//! Also checks that every pool string decodes and encodes back to the same modified UTF-8,
+//! and that member descriptors and signatures parse and write back unchanged.
+//!
+//! Usage: `cargo run --release --example roundtrip <dir or .class>...`
+
+use std::path::Path;
+
+use cm_jclass::{jtype::{ClassSig, JavaType, MethodSig}, mutf8, pool::PoolItem, write::{ClassFile, RawAttr}};
+
+#[derive(Default)]
+struct Stats {
+    classes: usize,
+    failed: usize,
+    bytes: usize,
+    compacted: usize,
+    stripped: usize,
+    /// Strings which are not valid modified UTF-8, or contain unpaired surrogates
+    invalid_strings: usize,
+}
+
+fn check(path: &Path, stats: &mut Stats) -> anyhow::Result<()> {
+    let data = std::fs::read(path)?;
+    let class = ClassFile::parse(&data)?;
+    if class.to_bytes()? != data {
+        anyhow::bail!("Output differs");
+    }
+    for item in &class.pool {
+        let PoolItem::Utf8(s) = item else { continue };
+        match s.to_str() {
+            Ok(d) if *mutf8::encode(&d) != **s => anyhow::bail!("String differs after decoding: {s:?}"),
+            Ok(_) => {}
+            Err(_) => stats.invalid_strings += 1,
+        }
+    }
+    check_sigs(&class)?;
+    let mut compacted = class;
+    compacted.compact_pool()?;
+    let out = compacted.to_bytes()?;
+    ClassFile::parse(&out)?;
+    let mut stripped = compacted;
+    stripped.strip_debug()?;
+    stripped.compact_pool()?;
+    let stripped = stripped.to_bytes()?;
+    ClassFile::parse(&stripped)?;
+    stats.bytes += data.len();
+    stats.compacted += out.len();
+    stats.stripped += stripped.len();
+    Ok(())
+}
+
+/// Parses a descriptor or signature and checks that it is written back unchanged.
+fn check_sig(s: &str, to_sig: anyhow::Result<String>) -> anyhow::Result<()> {
+    match to_sig {
+        Ok(x) if x == s => Ok(()),
+        Ok(x) => anyhow::bail!("Signature {s} written as {x}"),
+        Err(e) => anyhow::bail!("Signature {s}: {e}"),
+    }
+}
+
+fn check_sigs(class: &ClassFile) -> anyhow::Result<()> {
+    let signature = |attrs: &[RawAttr]| -> anyhow::Result<Option<String>> {
+        for a in attrs {
+            if **class.utf8_at(a.name)? == *b"Signature" {
+                let i = u16::from_be_bytes([a.data[0], a.data[1]]);
+                return Ok(Some(class.utf8_at(i)?.to_str()?.into_owned()));
+            }
+        }
+        Ok(None)
+    };
+    if let Some(s) = signature(&class.attrs)? {
+        check_sig(&s, ClassSig::from_sig(&s).map(|x| x.to_sig()))?;
+    }
+    for f in &class.fields {
+        let d = class.utf8_at(f.descriptor)?.to_str()?;
+        check_sig(&d, JavaType::from_desc(&d).map(|x| x.to_sig()))?;
+        if let Some(s) = signature(&f.attrs)? {
+            check_sig(&s, JavaType::from_sig(&s).map(|x| x.to_sig()))?;
+        }
+    }
+    for m in &class.methods {
+        let d = class.utf8_at(m.descriptor)?.to_str()?;
+        check_sig(&d, MethodSig::from_desc(&d).map(|x| x.to_sig()))?;
+        if let Some(s) = signature(&m.attrs)? {
+            check_sig(&s, MethodSig::from_sig(&s).map(|x| x.to_sig()))?;
+        }
+    }
+    Ok(())
+}
+
+fn walk(path: &Path, stats: &mut Stats) -> std::io::Result<()> {
+    if path.is_dir() {
+        for e in std::fs::read_dir(path)? {
+            walk(&e?.path(), stats)?;
+        }
+    } else if path.extension().is_some_and(|x| x == "class") {
+        stats.classes += 1;
+        if let Err(e) = check(path, stats) {
+            stats.failed += 1;
+            eprintln!("{}: {e}", path.display());
+        }
+    }
+    Ok(())
+}
+
+fn main() -> std::io::Result<()> {
+    let mut stats = Stats::default();
+    for arg in std::env::args_os().skip(1) {
+        walk(Path::new(&arg), &mut stats)?;
+    }
+    println!("{} classes, {} failed", stats.classes, stats.failed);
+    println!("{} bytes, {} compacted, {} stripped", stats.bytes, stats.compacted, stats.stripped);
+    println!("{} strings with unpaired surrogates or invalid", stats.invalid_strings);
+    Ok(())
+}
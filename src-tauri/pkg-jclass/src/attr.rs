@@ -2,28 +2,32 @@ use std::{marker::PhantomData, ops::Deref};
 
 use bytes::{Buf, Bytes};
 
-use super::{idx::{ClassInfo, ConstVal, Index, NameAndType, Utf8}, jtype::{OfClass, OfField, OfMethod}, pool::{ClassPool, JVal}, JStr};
+use super::{code::Insns, idx::{ClassInfo, ConstVal, Index, MethodHandle, ModuleInfo, NameAndType, PackageInfo, Utf8}, iter::Attrs, jtype::{OfClass, OfCode, OfField, OfMethod, OfRecordComponent}, mutf8, pool::{ClassPool, JVal, PoolItem}, AttrInfo, JStr};
+
+/// Nesting limit of annotations and arrays in element values. The JVM sets none,
+/// but readers walking deeper values would run out of stack.
+pub const MAX_NESTING: usize = 256;
 
 pub enum JAttr<T> {
-    AnnotationDefault,
-    BootstrapMethods,
-    ConstantValue,
+    AnnotationDefault(AttrKey<KeyAnnotationDefault>),
+    BootstrapMethods(AttrKey<KeyBootstrapMethods>),
+    ConstantValue(AttrKey<KeyConstantValue>),
     Code(AttrKey<KeyCode>),
     Deprecated,
     EnclosingMethod(AttrKey<KeyEnclosingMethod>),
-    Exceptions,
+    Exceptions(AttrKey<KeyExceptions>),
     InnerClasses(AttrKey<KeyInnerClasses>),
-    LineNumberTable,
-    LocalVariableTable,
-    LocalVariableTypeTable,
-    MethodParameters,
-    Module,
-    ModuleMainClass,
-    ModulePackages,
-    NestHost,
-    NestMembers,
-    PermittedSubclasses,
-    Record,
+    LineNumberTable(AttrKey<KeyLineNumberTable>),
+    LocalVariableTable(AttrKey<KeyLocalVariableTable>),
+    LocalVariableTypeTable(AttrKey<KeyLocalVariableTable>),
+    MethodParameters(AttrKey<KeyMethodParameters>),
+    Module(AttrKey<KeyModule>),
+    ModuleMainClass(AttrKey<KeyModuleMainClass>),
+    ModulePackages(AttrKey<KeyModulePackages>),
+    NestHost(AttrKey<KeyNestHost>),
+    NestMembers(AttrKey<KeyNestMembers>),
+    PermittedSubclasses(AttrKey<KeyPermittedSubclasses>),
+    Record(AttrKey<KeyRecord>),
     RuntimeInvisibleAnnotations(AttrKey<KeyAnnotations>),
     RuntimeInvisibleParameterAnnotations(AttrKey<KeyParamAnnotations>),
     RuntimeInvisibleTypeAnnotations(AttrKey<KeyTypeAnnotations>),
@@ -33,32 +37,32 @@ pub enum JAttr<T> {
     Signature(AttrKey<KeySignature>),
     SourceDebugExtension(AttrKey<KeySourceDebugExtension>),
     SourceFile(AttrKey<KeySourceFile>),
-    StackMapTable,
+    StackMapTable(AttrKey<KeyStackMapTable>),
     Synthetic,
     Unsupported(PhantomData<fn() -> T>)
 }
 impl<T> JAttr<T> {
     const fn from_name(value: &[u8]) -> Self {
         match value {
-            b"AnnotationDefault" => Self::AnnotationDefault,
-            b"BootstrapMethods" => Self::BootstrapMethods,
-            b"ConstantValue" => Self::ConstantValue,
+            b"AnnotationDefault" => Self::AnnotationDefault(ak()),
+            b"BootstrapMethods" => Self::BootstrapMethods(ak()),
+            b"ConstantValue" => Self::ConstantValue(ak()),
             b"Code" => Self::Code(ak()),
             b"Deprecated" => Self::Deprecated,
             b"EnclosingMethod" => Self::EnclosingMethod(ak()),
-            b"Exceptions" => Self::Exceptions,
+            b"Exceptions" => Self::Exceptions(ak()),
             b"InnerClasses" => Self::InnerClasses(ak()),
-            b"LineNumberTable" => Self::LineNumberTable,
-            b"LocalVariableTable" => Self::LocalVariableTable,
-            b"LocalVariableTypeTable" => Self::LocalVariableTypeTable,
-            b"MethodParameters" => Self::MethodParameters,
-            b"Module" => Self::Module,
-            b"ModuleMainClass" => Self::ModuleMainClass,
-            b"ModulePackages" => Self::ModulePackages,
-            b"NestHost" => Self::NestHost,
-            b"NestMembers" => Self::NestMembers,
-            b"PermittedSubclasses" => Self::PermittedSubclasses,
-            b"Record" => Self::Record,
+            b"LineNumberTable" => Self::LineNumberTable(ak()),
+            b"LocalVariableTable" => Self::LocalVariableTable(ak()),
+            b"LocalVariableTypeTable" => Self::LocalVariableTypeTable(ak()),
+            b"MethodParameters" => Self::MethodParameters(ak()),
+            b"Module" => Self::Module(ak()),
+            b"ModuleMainClass" => Self::ModuleMainClass(ak()),
+            b"ModulePackages" => Self::ModulePackages(ak()),
+            b"NestHost" => Self::NestHost(ak()),
+            b"NestMembers" => Self::NestMembers(ak()),
+            b"PermittedSubclasses" => Self::PermittedSubclasses(ak()),
+            b"Record" => Self::Record(ak()),
             b"RuntimeInvisibleAnnotations" => Self::RuntimeInvisibleAnnotations(ak()),
             b"RuntimeInvisibleParameterAnnotations" => Self::RuntimeInvisibleParameterAnnotations(ak()),
             b"RuntimeInvisibleTypeAnnotations" => Self::RuntimeInvisibleTypeAnnotations(ak()),
@@ -68,7 +72,7 @@ impl<T> JAttr<T> {
             b"Signature" => Self::Signature(ak()),
             b"SourceDebugExtension" => Self::SourceDebugExtension(ak()),
             b"SourceFile" => Self::SourceFile(ak()),
-            b"StackMapTable" => Self::StackMapTable,
+            b"StackMapTable" => Self::StackMapTable(ak()),
             b"Synthetic" => Self::Synthetic,
             _ => Self::Unsupported(PhantomData),
         }
@@ -80,13 +84,13 @@ const fn ak<T: UseAttr>() -> AttrKey<T> {
     AttrKey(PhantomData)
 }
 
-trait AttrMatch {
+pub trait AttrMatch {
     fn matches(a: &JAttr<Self>) -> bool where Self: std::marker::Sized;
 }
 impl AttrMatch for OfField {
     fn matches(a: &JAttr<Self>) -> bool {
         matches!(a,
-            JAttr::ConstantValue |
+            JAttr::ConstantValue(_) |
             JAttr::Deprecated |
             JAttr::RuntimeInvisibleAnnotations(_) |
             JAttr::RuntimeInvisibleTypeAnnotations(_) |
@@ -100,11 +104,11 @@ impl AttrMatch for OfField {
 impl AttrMatch for OfMethod {
     fn matches(a: &JAttr<Self>) -> bool {
         matches!(a,
-            JAttr::AnnotationDefault |
+            JAttr::AnnotationDefault(_) |
             JAttr::Code(_) |
             JAttr::Deprecated |
-            JAttr::Exceptions |
-            JAttr::MethodParameters |
+            JAttr::Exceptions(_) |
+            JAttr::MethodParameters(_) |
             JAttr::RuntimeInvisibleAnnotations(_) |
             JAttr::RuntimeInvisibleParameterAnnotations(_) |
             JAttr::RuntimeInvisibleTypeAnnotations(_) |
@@ -119,15 +123,17 @@ impl AttrMatch for OfMethod {
 impl AttrMatch for OfClass {
     fn matches(a: &JAttr<Self>) -> bool {
         matches!(a,
-            JAttr::BootstrapMethods |
+            JAttr::BootstrapMethods(_) |
             JAttr::Deprecated |
             JAttr::EnclosingMethod(_) |
             JAttr::InnerClasses(_) |
-            JAttr::Module |
-            JAttr::ModuleMainClass |
-            JAttr::ModulePackages |
-            JAttr::PermittedSubclasses |
-            JAttr::Record |
+            JAttr::Module(_) |
+            JAttr::ModuleMainClass(_) |
+            JAttr::ModulePackages(_) |
+            JAttr::NestHost(_) |
+            JAttr::NestMembers(_) |
+            JAttr::PermittedSubclasses(_) |
+            JAttr::Record(_) |
             JAttr::RuntimeInvisibleAnnotations(_) |
             JAttr::RuntimeInvisibleTypeAnnotations(_) |
             JAttr::RuntimeVisibleAnnotations(_) |
@@ -140,6 +146,31 @@ impl AttrMatch for OfClass {
     }
 }
 
+impl AttrMatch for OfCode {
+    fn matches(a: &JAttr<Self>) -> bool {
+        matches!(a,
+            JAttr::LineNumberTable(_) |
+            JAttr::LocalVariableTable(_) |
+            JAttr::LocalVariableTypeTable(_) |
+            JAttr::RuntimeInvisibleTypeAnnotations(_) |
+            JAttr::RuntimeVisibleTypeAnnotations(_) |
+            JAttr::StackMapTable(_)
+        )
+    }
+}
+
+impl AttrMatch for OfRecordComponent {
+    fn matches(a: &JAttr<Self>) -> bool {
+        matches!(a,
+            JAttr::RuntimeInvisibleAnnotations(_) |
+            JAttr::RuntimeInvisibleTypeAnnotations(_) |
+            JAttr::RuntimeVisibleAnnotations(_) |
+            JAttr::RuntimeVisibleTypeAnnotations(_) |
+            JAttr::Signature(_)
+        )
+    }
+}
+
 impl<T: AttrMatch> TryFrom<&[u8]> for JAttr<T> {
     type Error = anyhow::Error;
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
@@ -174,7 +205,6 @@ key_enums! {
     KeyInnerClasses,
     KeyLineNumberTable,
     KeyLocalVariableTable,
-    KeyLocalVariableTypeTable,
     KeyMethodParameters,
     KeyModule,
     KeyModuleMainClass,
@@ -192,25 +222,55 @@ key_enums! {
     KeyStackMapTable
 }
 
-// impl UseAttr for KeyAnnotationDefault {}
-// impl UseAttr for KeyBootstrapMethods {}
+impl UseAttr for KeyAnnotationDefault {
+    type Out = Data<ElemVal>;
+    fn parse(mut b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        Data::parse(&mut b, pool)
+    }
+}
+impl UseAttr for KeyBootstrapMethods {
+    type Out = Iter<Data<BootstrapMethod>>;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        need(&b, 2)?;
+        Ok(Iter::new(b, pool.clone()))
+    }
+}
 impl UseAttr for KeyConstantValue {
     type Out = JVal;
-    fn parse(mut b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
-        pool.get_::<ConstVal>(b.get_u16())
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        pool.get_::<ConstVal>(u16_at(&b, 0)?)
     }
 }
 impl UseAttr for KeyCode {
-    type Out = ();
-    fn parse(_b: Bytes, _pool: &ClassPool) -> anyhow::Result<Self::Out> {
-        Ok(())
+    type Out = Code;
+    fn parse(mut b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        let need = |b: &Bytes, len: usize| if b.len() < len { anyhow::bail!("Unexpected end of code") } else { Ok(()) };
+        need(&b, 8)?;
+        let max_stack = b.get_u16();
+        let max_locals = b.get_u16();
+        let len = b.get_u32() as usize;
+        need(&b, len + 2)?;
+        let code = b.split_to(len);
+        let n = b.get_u16() as usize;
+        need(&b, n * 8 + 2)?;
+        let handlers = (0..n)
+            .map(|_| Handler {
+                start_pc: b.get_u16(),
+                end_pc: b.get_u16(),
+                handler_pc: b.get_u16(),
+                catch_type: Index::maybe(b.get_u16()),
+            })
+            .collect();
+        let attr_count = b.get_u16();
+        attrs_len(&b, attr_count)?;
+        Ok(Code { pool: pool.clone(), max_stack, max_locals, code, handlers, attr_count, attrs: b })
     }
 }
 impl UseAttr for KeyEnclosingMethod {
     type Out = (JStr, Option<(JStr, JStr)>);
     fn parse(mut b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
-        let class_name = pool.get(pool.get_::<ClassInfo>(b.get_u16())?)?;
-        let method = if let Some(idx) = Index::<NameAndType>::maybe(b.get_u16()) {
+        let class_name = pool.get(pool.get_::<ClassInfo>(get_u16(&mut b)?)?)?;
+        let method = if let Some(idx) = Index::<NameAndType>::maybe(get_u16(&mut b)?) {
             let (mn, mt) = pool.get(idx)?;
             Some((pool.get(mn)?.clone(), pool.get(mt)?.clone()))
         } else {
@@ -219,47 +279,129 @@ impl UseAttr for KeyEnclosingMethod {
         Ok((class_name.clone(), method))
     }
 }
-// impl UseAttr for KeyExceptions {}
+impl UseAttr for KeyExceptions {
+    /// Names of the checked exceptions declared by a method
+    type Out = Vec<JStr>;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        class_list(&b, pool)
+    }
+}
 impl UseAttr for KeyInnerClasses {
-    type Out = Iter<InnerClass>;
+    type Out = Iter<Data<InnerClass>>;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        need(&b, 2 + u16_at(&b, 0)? as usize * 8)?;
+        Ok(Iter::new(b, pool.clone()))
+    }
+}
+impl UseAttr for KeyLineNumberTable {
+    type Out = Iter<LineNumber>;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        need(&b, 2 + u16_at(&b, 0)? as usize * 4)?;
+        Ok(Iter::new(b, pool.clone()))
+    }
+}
+impl UseAttr for KeyLocalVariableTable {
+    /// Entries of a `LocalVariableTable`, or of a `LocalVariableTypeTable` with signatures instead of descriptors
+    type Out = Iter<Data<LocalVar>>;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        need(&b, 2 + u16_at(&b, 0)? as usize * 10)?;
+        Ok(Iter::new(b, pool.clone()))
+    }
+}
+impl UseAttr for KeyMethodParameters {
+    type Out = Vec<Data<MethodParameter>>;
     fn parse(mut b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
-        let len = b.get_u16();
-        Ok(Iter { b, pool: pool.clone(), cur: 0, len, _t: PhantomData })
-    }
-}
-// impl UseAttr for KeyLineNumberTable {}
-// impl UseAttr for KeyLocalVariableTable {}
-// impl UseAttr for KeyLocalVariableTypeTable {}
-// impl UseAttr for KeyMethodParameters {}
-// impl UseAttr for KeyModule {}
-// impl UseAttr for KeyModuleMainClass {}
-// impl UseAttr for KeyModulePackages {}
-// impl UseAttr for KeyNestHost {}
-// impl UseAttr for KeyNestMembers {}
-// impl UseAttr for KeyPermittedSubclasses {}
-// impl UseAttr for KeyRecord {}
+        need(&b, 1)?;
+        // Unlike other tables, the count is a single byte
+        let n = b.get_u8() as usize;
+        need(&b, n * 4)?;
+        (0..n).map(|_| Data::parse(&mut b, pool)).collect()
+    }
+}
+impl UseAttr for KeyModule {
+    type Out = Module;
+    fn parse(mut b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        Module::parse(&mut b, pool)
+    }
+}
+impl UseAttr for KeyModuleMainClass {
+    type Out = JStr;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        class_name(pool, u16_at(&b, 0)?)
+    }
+}
+impl UseAttr for KeyModulePackages {
+    /// Names of all packages of the module, including those not exported
+    type Out = Vec<JStr>;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        need(&b, 2 + u16_at(&b, 0)? as usize * 2)?;
+        (0..u16_at(&b, 0)? as usize)
+            .map(|i| package_name(pool, u16_at(&b, 2 + i * 2)?))
+            .collect()
+    }
+}
+impl UseAttr for KeyNestHost {
+    type Out = JStr;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        class_name(pool, u16_at(&b, 0)?)
+    }
+}
+impl UseAttr for KeyNestMembers {
+    type Out = Vec<JStr>;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        class_list(&b, pool)
+    }
+}
+impl UseAttr for KeyPermittedSubclasses {
+    /// Direct subclasses allowed by a `sealed` class or interface
+    type Out = Vec<JStr>;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        class_list(&b, pool)
+    }
+}
+impl UseAttr for KeyRecord {
+    type Out = Iter<Data<RecordComponent>>;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        need(&b, 2)?;
+        Ok(Iter::new(b, pool.clone()))
+    }
+}
 impl UseAttr for KeyAnnotations {
-    type Out = ();
-    fn parse(_b: Bytes, _pool: &ClassPool) -> anyhow::Result<Self::Out> {
-        Ok(())
+    type Out = Iter<Data<Annotation>>;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        need(&b, 2)?;
+        Ok(Iter::new(b, pool.clone()))
     }
 }
 impl UseAttr for KeyParamAnnotations {
-    type Out = ();
-    fn parse(_b: Bytes, _pool: &ClassPool) -> anyhow::Result<Self::Out> {
-        Ok(())
+    /// Annotations of each parameter
+    type Out = Vec<Iter<Data<Annotation>>>;
+    fn parse(mut b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        need(&b, 1)?;
+        let params = b.get_u8();
+        (0..params)
+            .map(|_| {
+                let mut len = 2;
+                for _ in 0..u16_at(&b, 0)? {
+                    len += annotation_len(b.get(len..).unwrap_or_default())?;
+                }
+                need(&b, len)?;
+                Ok(Iter::new(b.split_to(len), pool.clone()))
+            })
+            .collect()
     }
 }
 impl UseAttr for KeyTypeAnnotations {
-    type Out = ();
-    fn parse(_b: Bytes, _pool: &ClassPool) -> anyhow::Result<Self::Out> {
-        Ok(())
+    type Out = Iter<Data<TypeAnnotation>>;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        need(&b, 2)?;
+        Ok(Iter::new(b, pool.clone()))
     }
 }
 impl UseAttr for KeySignature {
     type Out = JStr;
-    fn parse(mut b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
-        pool.get_::<Utf8>(b.get_u16()).cloned()
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        pool.get_::<Utf8>(u16_at(&b, 0)?).cloned()
     }
 }
 impl UseAttr for KeySourceDebugExtension {
@@ -270,11 +412,17 @@ impl UseAttr for KeySourceDebugExtension {
 }
 impl UseAttr for KeySourceFile {
     type Out = JStr;
-    fn parse(mut b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
-        pool.get_::<Utf8>(b.get_u16()).cloned()
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        pool.get_::<Utf8>(u16_at(&b, 0)?).cloned()
+    }
+}
+impl UseAttr for KeyStackMapTable {
+    type Out = Iter<StackMapFrame>;
+    fn parse(b: Bytes, pool: &ClassPool) -> anyhow::Result<Self::Out> {
+        need(&b, 2)?;
+        Ok(Iter::new(b, pool.clone()))
     }
 }
-// impl UseAttr for KeyStackMapTable {}
 
 pub trait Parsing {
     fn parse(b: &mut Bytes, pool: &ClassPool) -> anyhow::Result<Self> where Self: Sized;
@@ -337,18 +485,519 @@ impl Parsing for InnerClass {
         Ok(Self { inner_class, outer_class, name, access_flags })
     }
 }
+impl Data<InnerClass> {
+    pub fn inner_name(&self) -> anyhow::Result<JStr> {
+        self.pool.get(self.pool.get(self.inner_class)?).cloned()
+    }
+    pub fn outer_name(&self) -> anyhow::Result<Option<JStr>> {
+        self.outer_class.map(|c| self.pool.get(self.pool.get(c)?).cloned()).transpose()
+    }
+    /// Simple name of the class, `None` for anonymous classes
+    pub fn simple_name(&self) -> anyhow::Result<Option<JStr>> {
+        self.name.map(|n| self.pool.get(n).cloned()).transpose()
+    }
+    pub const fn access_flags(&self) -> u16 {
+        self.data.access_flags
+    }
+}
+
+pub struct Code {
+    pool: ClassPool,
+    max_stack: u16,
+    max_locals: u16,
+    code: Bytes,
+    handlers: Vec<Handler>,
+    attr_count: u16,
+    attrs: Bytes,
+}
+impl Code {
+    pub const fn max_stack(&self) -> u16 {
+        self.max_stack
+    }
+    pub const fn max_locals(&self) -> u16 {
+        self.max_locals
+    }
+    /// Bytecode of the method
+    pub fn code(&self) -> &[u8] {
+        &self.code
+    }
+    pub fn insns(&self) -> Insns<'_> {
+        Insns::new(&self.code)
+    }
+    /// Entries of the exception table
+    pub fn handlers(&self) -> &[Handler] {
+        &self.handlers
+    }
+    /// Class caught by a handler, `None` for `finally` blocks
+    pub fn catch_type(&self, h: &Handler) -> anyhow::Result<Option<&JStr>> {
+        h.catch_type.map(|c| self.pool.get(self.pool.get(c)?)).transpose()
+    }
+    /// Attributes of the code, such as `LineNumberTable`
+    pub fn attrs(&self) -> Attrs<OfCode> {
+        Attrs::new(self.attrs.clone(), self.pool.clone(), self.attr_count)
+    }
+}
 
+/// Entry of the exception table of a method
+#[derive(Clone, Copy)]
+pub struct Handler {
+    start_pc: u16,
+    end_pc: u16,
+    handler_pc: u16,
+    catch_type: Option<Index<ClassInfo>>,
+}
+impl Handler {
+    /// Start of the protected range
+    pub const fn start_pc(&self) -> u16 {
+        self.start_pc
+    }
+    /// End of the protected range (exclusive)
+    pub const fn end_pc(&self) -> u16 {
+        self.end_pc
+    }
+    pub const fn handler_pc(&self) -> u16 {
+        self.handler_pc
+    }
+}
+
+/// Entry of a `LineNumberTable`
+#[derive(Clone, Copy)]
+pub struct LineNumber {
+    start_pc: u16,
+    line: u16,
+}
+impl Parsing for LineNumber {
+    fn parse(b: &mut Bytes, _pool: &ClassPool) -> anyhow::Result<Self> {
+        need(b, 4)?;
+        Ok(Self { start_pc: b.get_u16(), line: b.get_u16() })
+    }
+}
+impl LineNumber {
+    /// Offset of the first instruction of the line
+    pub const fn start_pc(&self) -> u16 {
+        self.start_pc
+    }
+    pub const fn line(&self) -> u16 {
+        self.line
+    }
+}
+
+/// Entry of a `LocalVariableTable` or `LocalVariableTypeTable`
+pub struct LocalVar {
+    start_pc: u16,
+    length: u16,
+    name: Index<Utf8>,
+    desc: Index<Utf8>,
+    slot: u16,
+}
+impl Parsing for LocalVar {
+    fn parse(b: &mut Bytes, _pool: &ClassPool) -> anyhow::Result<Self> {
+        need(b, 10)?;
+        let (start_pc, length) = (b.get_u16(), b.get_u16());
+        let name = Index::try_from(b.get_u16())?;
+        let desc = Index::try_from(b.get_u16())?;
+        Ok(Self { start_pc, length, name, desc, slot: b.get_u16() })
+    }
+}
+impl Data<LocalVar> {
+    pub const fn start_pc(&self) -> u16 {
+        self.data.start_pc
+    }
+    /// Length of the range where the variable has a value
+    pub const fn length(&self) -> u16 {
+        self.data.length
+    }
+    /// Whether the variable has a value at `pc`
+    pub const fn covers(&self, pc: usize) -> bool {
+        let start = self.data.start_pc as usize;
+        start <= pc && pc < start + self.data.length as usize
+    }
+    pub const fn slot(&self) -> u16 {
+        self.data.slot
+    }
+    pub fn name(&self) -> anyhow::Result<&JStr> {
+        self.pool.get(self.data.name)
+    }
+    /// Field descriptor of the variable, or its generic signature in a `LocalVariableTypeTable`
+    pub fn descriptor(&self) -> anyhow::Result<&JStr> {
+        self.pool.get(self.data.desc)
+    }
+}
+
+/// Entry of a `MethodParameters` attribute
+pub struct MethodParameter {
+    name: Option<Index<Utf8>>,
+    access_flags: u16,
+}
+impl Parsing for MethodParameter {
+    fn parse(b: &mut Bytes, _pool: &ClassPool) -> anyhow::Result<Self> {
+        need(b, 4)?;
+        Ok(Self { name: Index::maybe(b.get_u16()), access_flags: b.get_u16() })
+    }
+}
+impl Data<MethodParameter> {
+    /// Name of the parameter, `None` for unnamed parameters
+    pub fn name(&self) -> anyhow::Result<Option<&JStr>> {
+        self.data.name.map(|n| self.pool.get(n)).transpose()
+    }
+    /// `final`, `synthetic` and `mandated` flags
+    pub const fn access_flags(&self) -> u16 {
+        self.data.access_flags
+    }
+}
+
+pub struct BootstrapMethod {
+    method: Index<MethodHandle>,
+    args: Vec<u16>,
+}
+impl Parsing for BootstrapMethod {
+    fn parse(b: &mut Bytes, _pool: &ClassPool) -> anyhow::Result<Self> {
+        if b.len() < 4 {
+            anyhow::bail!("Unexpected end of bootstrap methods");
+        }
+        let method = Index::try_from(b.get_u16())?;
+        let len = b.get_u16() as usize;
+        if b.len() < len * 2 {
+            anyhow::bail!("Unexpected end of bootstrap methods");
+        }
+        let args = (0..len).map(|_| b.get_u16()).collect();
+        Ok(Self { method, args })
+    }
+}
+impl Data<BootstrapMethod> {
+    /// Class declaring the bootstrap method, such as `java/lang/invoke/LambdaMetafactory`
+    pub fn owner(&self) -> anyhow::Result<&JStr> {
+        let (_, idx) = self.pool.get(self.method)?.parts();
+        match self.pool.item(idx).ok() {
+            Some(PoolItem::RefField(c, _) | PoolItem::RefMethod(c, _) | PoolItem::RefInterfaceMethod(c, _)) => self.pool.get(self.pool.get(*c)?),
+            _ => anyhow::bail!("Invalid method handle"),
+        }
+    }
+    /// Pool indices of the static arguments
+    pub fn args(&self) -> &[u16] {
+        &self.data.args
+    }
+}
+
+/// Decoded `Module` attribute of a `module-info` class
+pub struct Module {
+    pub name: JStr,
+    /// `open`, `synthetic` and `mandated` flags
+    pub flags: u16,
+    pub version: Option<JStr>,
+    pub requires: Vec<Requires>,
+    pub exports: Vec<Exports>,
+    pub opens: Vec<Exports>,
+    /// Services used through `ServiceLoader`
+    pub uses: Vec<JStr>,
+    pub provides: Vec<Provides>,
+}
+/// Module required by a module
+pub struct Requires {
+    pub module: JStr,
+    /// `transitive`, `static`, `synthetic` and `mandated` flags
+    pub flags: u16,
+    /// Version of the module at compile time
+    pub version: Option<JStr>,
+}
+/// Package exported or opened by a module, to all modules if `to` is empty
+pub struct Exports {
+    pub package: JStr,
+    pub flags: u16,
+    pub to: Vec<JStr>,
+}
+/// Implementations of a service provided by a module
+pub struct Provides {
+    pub service: JStr,
+    pub with: Vec<JStr>,
+}
+impl Module {
+    fn parse(b: &mut Bytes, pool: &ClassPool) -> anyhow::Result<Self> {
+        let utf8 = |i| Index::<Utf8>::maybe(i).map(|i| pool.get(i).cloned()).transpose();
+        let list = |b: &mut Bytes, f: &dyn Fn(u16) -> anyhow::Result<JStr>| {
+            let n = get_u16(b)?;
+            (0..n).map(|_| f(get_u16(b)?)).collect::<anyhow::Result<Vec<_>>>()
+        };
+        let module = |i| pool.get(pool.get_::<ModuleInfo>(i)?).cloned();
+        let class = |i| class_name(pool, i);
+        let package = |i| package_name(pool, i);
+        let name = module(get_u16(b)?)?;
+        let flags = get_u16(b)?;
+        let version = utf8(get_u16(b)?)?;
+        let requires = (0..get_u16(b)?)
+            .map(|_| Ok(Requires { module: module(get_u16(b)?)?, flags: get_u16(b)?, version: utf8(get_u16(b)?)? }))
+            .collect::<anyhow::Result<_>>()?;
+        let exports = |b: &mut Bytes| {
+            (0..get_u16(b)?)
+                .map(|_| Ok(Exports { package: package(get_u16(b)?)?, flags: get_u16(b)?, to: list(b, &module)? }))
+                .collect::<anyhow::Result<Vec<_>>>()
+        };
+        let (exports, opens) = (exports(b)?, exports(b)?);
+        let uses = list(b, &class)?;
+        let provides = (0..get_u16(b)?)
+            .map(|_| Ok(Provides { service: class(get_u16(b)?)?, with: list(b, &class)? }))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { name, flags, version, requires, exports, opens, uses, provides })
+    }
+}
+
+/// Component of a record class, with its own attributes
+pub struct RecordComponent {
+    name: Index<Utf8>,
+    desc: Index<Utf8>,
+    attr_count: u16,
+    attrs: Bytes,
+}
+impl Parsing for RecordComponent {
+    fn parse(b: &mut Bytes, _pool: &ClassPool) -> anyhow::Result<Self> {
+        need(b, 6)?;
+        let name = Index::try_from(b.get_u16())?;
+        let desc = Index::try_from(b.get_u16())?;
+        let attr_count = b.get_u16();
+        let attrs = b.split_to(attrs_len(b, attr_count)?);
+        Ok(Self { name, desc, attr_count, attrs })
+    }
+}
+impl Data<RecordComponent> {
+    pub fn name(&self) -> anyhow::Result<&JStr> {
+        self.pool.get(self.data.name)
+    }
+    pub fn descriptor(&self) -> anyhow::Result<&JStr> {
+        self.pool.get(self.data.desc)
+    }
+    /// Attributes of the component, such as its generic `Signature`
+    pub fn attrs(&self) -> Attrs<OfRecordComponent> {
+        Attrs::new(self.data.attrs.clone(), self.pool.clone(), self.data.attr_count)
+    }
+}
+
+/// Type of a local variable or stack slot in a stack map frame
+#[derive(Clone, Copy)]
+pub enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    Object(Index<ClassInfo>),
+    /// Offset of the `new` instruction creating the object
+    Uninitialized(u16),
+}
+impl VerificationType {
+    fn parse(b: &mut Bytes) -> anyhow::Result<Self> {
+        need(b, 1)?;
+        Ok(match b.get_u8() {
+            0 => Self::Top,
+            1 => Self::Integer,
+            2 => Self::Float,
+            3 => Self::Double,
+            4 => Self::Long,
+            5 => Self::Null,
+            6 => Self::UninitializedThis,
+            7 => Self::Object(Index::try_from(get_u16(b)?)?),
+            8 => Self::Uninitialized(get_u16(b)?),
+            t => anyhow::bail!("Invalid verification type: {t}"),
+        })
+    }
+    fn parse_n(b: &mut Bytes, n: usize) -> anyhow::Result<Vec<Self>> {
+        (0..n).map(|_| Self::parse(b)).collect()
+    }
+}
+
+/// Frame of a `StackMapTable`, starting with the offset delta from the previous frame
+pub enum StackMapFrame {
+    /// Same locals as the previous frame and an empty stack
+    Same(u16),
+    /// Same locals as the previous frame and a single stack item
+    SameLocals1StackItem(u16, VerificationType),
+    /// Number of locals removed from the previous frame
+    Chop(u16, u8),
+    /// Locals added to the previous frame
+    Append(u16, Vec<VerificationType>),
+    /// All locals and stack items
+    Full(u16, Vec<VerificationType>, Vec<VerificationType>),
+}
+impl Parsing for StackMapFrame {
+    fn parse(b: &mut Bytes, _pool: &ClassPool) -> anyhow::Result<Self> {
+        need(b, 1)?;
+        Ok(match b.get_u8() {
+            t @ 0..=63 => Self::Same(u16::from(t)),
+            t @ 64..=127 => Self::SameLocals1StackItem(u16::from(t - 64), VerificationType::parse(b)?),
+            247 => Self::SameLocals1StackItem(get_u16(b)?, VerificationType::parse(b)?),
+            t @ 248..=250 => Self::Chop(get_u16(b)?, 251 - t),
+            251 => Self::Same(get_u16(b)?),
+            t @ 252..=254 => Self::Append(get_u16(b)?, VerificationType::parse_n(b, (t - 251) as usize)?),
+            255 => {
+                let delta = get_u16(b)?;
+                let n = get_u16(b)? as usize;
+                let locals = VerificationType::parse_n(b, n)?;
+                let n = get_u16(b)? as usize;
+                Self::Full(delta, locals, VerificationType::parse_n(b, n)?)
+            }
+            t => anyhow::bail!("Invalid stack map frame type: {t}"),
+        })
+    }
+}
+impl StackMapFrame {
+    pub const fn offset_delta(&self) -> u16 {
+        match self {
+            Self::Same(d) | Self::SameLocals1StackItem(d, _) | Self::Chop(d, _) | Self::Append(d, _) | Self::Full(d, ..) => *d,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Annotation {
     type_idx: Index<Utf8>,
     elems_len: u16,
-    b: Bytes
+    b: Bytes,
+    /// Annotations and arrays enclosing this one in an element value
+    depth: u16,
+}
+impl Parsing for Annotation {
+    fn parse(b: &mut Bytes, _pool: &ClassPool) -> anyhow::Result<Self> {
+        need(b, 2)?;
+        let type_idx = Index::try_from(b.get_u16())?;
+        let len = elem_pairs_len(b)?;
+        if len > b.len() {
+            anyhow::bail!("Unexpected end of annotation");
+        }
+        let b = b.split_to(len);
+        let elems_len = b.clone().get_u16();
+        Ok(Self { type_idx, elems_len, b, depth: 0 })
+    }
 }
 impl Data<Annotation> {
     pub fn type_name(&self) -> anyhow::Result<JStr> {
         self.pool.get(self.type_idx).cloned()
     }
-    pub fn elems(&self) -> Iter<Data<AnnElemPair>> {
-        Iter::new(self.b.clone(), self.pool.clone())
+    pub fn elems(&self) -> Nested<Data<AnnElemPair>> {
+        Nested { it: Iter::new(self.b.clone(), self.pool.clone()), depth: self.depth }
+    }
+    /// Finds an element by name.
+    pub fn elem(&self, name: &str) -> anyhow::Result<Option<Data<AnnElemPair>>> {
+        for e in self.elems() {
+            let e = e?;
+            if **e.name()? == *mutf8::encode(name) {
+                return Ok(Some(e));
+            }
+        }
+        Ok(None)
+    }
+}
+impl Iter<Data<Annotation>> {
+    /// Finds an annotation by type descriptor, such as `Lkotlin/Metadata;`.
+    pub fn find_by_desc(self, desc: &str) -> anyhow::Result<Option<Data<Annotation>>> {
+        for an in self {
+            let an = an?;
+            if *an.type_name()? == *mutf8::encode(desc) {
+                return Ok(Some(an));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Finds an annotation by type descriptor in the runtime visible and invisible annotations of a class or member.
+pub fn find_annotation<T: AttrMatch>(attrs: impl Iterator<Item = anyhow::Result<AttrInfo<T>>>, desc: &str) -> anyhow::Result<Option<Data<Annotation>>> {
+    for ai in attrs {
+        let ai = ai?;
+        if let JAttr::RuntimeVisibleAnnotations(k) | JAttr::RuntimeInvisibleAnnotations(k) = ai.kind()? {
+            if let Some(an) = ai.read(&k)?.find_by_desc(desc)? {
+                return Ok(Some(an));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Annotation on a type use, with the location of the annotated type
+pub struct TypeAnnotation {
+    target_type: u8,
+    target: TypeTarget,
+    path: Vec<(u8, u8)>,
+    annotation: Annotation,
+}
+impl Parsing for TypeAnnotation {
+    fn parse(b: &mut Bytes, pool: &ClassPool) -> anyhow::Result<Self> {
+        need(b, 1)?;
+        let target_type = b.get_u8();
+        let target = TypeTarget::parse(target_type, b)?;
+        need(b, 1)?;
+        let path_len = b.get_u8() as usize;
+        need(b, path_len * 2)?;
+        let path = (0..path_len).map(|_| (b.get_u8(), b.get_u8())).collect();
+        let annotation = Annotation::parse(b, pool)?;
+        Ok(Self { target_type, target, path, annotation })
+    }
+}
+impl Data<TypeAnnotation> {
+    /// Kind of target (`0x10` for `extends` and `implements` clauses, `0x13` for fields...)
+    pub const fn target_type(&self) -> u8 {
+        self.data.target_type
+    }
+    pub const fn target(&self) -> &TypeTarget {
+        &self.data.target
+    }
+    /// Steps of the `type_path` into array, nested, wildcard and type argument types
+    pub fn path(&self) -> &[(u8, u8)] {
+        &self.data.path
+    }
+    pub fn annotation(&self) -> Data<Annotation> {
+        Data { pool: self.pool.clone(), data: self.data.annotation.clone() }
+    }
+}
+
+/// Decoded `target_info` of a type annotation
+pub enum TypeTarget {
+    /// Index of a type parameter of a class or method
+    TypeParameter(u8),
+    /// Index in the interfaces of the class, 65535 for the superclass
+    Supertype(u16),
+    /// Index of a type parameter and of one of its bounds
+    TypeParameterBound(u8, u8),
+    /// Field type, method return type or receiver type
+    Empty,
+    FormalParameter(u8),
+    /// Index in the `Exceptions` attribute
+    Throws(u16),
+    /// Start offset, length and local variable index of each live range
+    LocalVar(Vec<(u16, u16, u16)>),
+    /// Index in the exception table
+    Catch(u16),
+    /// Offset of an `instanceof`, `new` or method reference instruction
+    Offset(u16),
+    /// Offset of a cast or invocation and the index of the type argument
+    TypeArgument(u16, u8),
+}
+impl TypeTarget {
+    fn parse(target_type: u8, b: &mut Bytes) -> anyhow::Result<Self> {
+        let len = match target_type {
+            0x13..=0x15 => 0,
+            0x00 | 0x01 | 0x16 => 1,
+            0x10..=0x12 | 0x17 | 0x40..=0x46 => 2,
+            0x47..=0x4b => 3,
+            t => anyhow::bail!("Invalid type annotation target: 0x{t:02x}"),
+        };
+        need(b, len)?;
+        Ok(match target_type {
+            0x00 | 0x01 => Self::TypeParameter(b.get_u8()),
+            0x10 => Self::Supertype(b.get_u16()),
+            0x11 | 0x12 => Self::TypeParameterBound(b.get_u8(), b.get_u8()),
+            0x16 => Self::FormalParameter(b.get_u8()),
+            0x17 => Self::Throws(b.get_u16()),
+            0x40 | 0x41 => {
+                let n = b.get_u16() as usize;
+                need(b, n * 6)?;
+                Self::LocalVar((0..n).map(|_| (b.get_u16(), b.get_u16(), b.get_u16())).collect())
+            }
+            0x42 => Self::Catch(b.get_u16()),
+            0x43..=0x46 => Self::Offset(b.get_u16()),
+            0x47..=0x4b => Self::TypeArgument(b.get_u16(), b.get_u8()),
+            _ => Self::Empty,
+        })
     }
 }
 
@@ -357,11 +1006,237 @@ pub struct AnnElemPair {
     val: ElemVal
 }
 impl Parsing for AnnElemPair {
-    fn parse(b: &mut Bytes, _pool: &ClassPool) -> anyhow::Result<Self> {
+    fn parse(b: &mut Bytes, pool: &ClassPool) -> anyhow::Result<Self> {
+        need(b, 2)?;
         let name_idx = Index::try_from(b.get_u16())?;
-        let val = ElemVal{};
+        let val = ElemVal::parse(b, pool)?;
         Ok(Self { name_idx, val })
     }
 }
+impl Data<AnnElemPair> {
+    pub fn name(&self) -> anyhow::Result<&JStr> {
+        self.pool.get(self.name_idx)
+    }
+    /// Tag of the element value (`e` for enum constants, `[` for arrays, `Z` for booleans...)
+    pub const fn tag(&self) -> u8 {
+        self.data.val.tag
+    }
+    pub fn value(&self) -> anyhow::Result<ElemValue> {
+        self.data.val.decode(&self.pool)
+    }
+    /// Type descriptor and name of an enum constant value
+    pub fn enum_const(&self) -> anyhow::Result<Option<(&JStr, &JStr)>> {
+        if self.val.tag != b'e' {
+            return Ok(None);
+        }
+        let b = &self.val.b;
+        Ok(Some((self.pool.get_::<Utf8>(u16_at(b, 1)?)?, self.pool.get_::<Utf8>(u16_at(b, 3)?)?)))
+    }
+    /// Value of a primitive or `String` element
+    pub fn const_value(&self) -> anyhow::Result<Option<JVal>> {
+        let idx = u16_at(&self.val.b, 1);
+        Ok(Some(match self.val.tag {
+            b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' => self.pool.get_::<ConstVal>(idx?)?,
+            // Unlike `ldc` operands, string elements point directly at a `Utf8` entry
+            b's' => JVal::Str(Index::try_from(idx?)?),
+            _ => return Ok(None),
+        }))
+    }
+    /// Value of a `String` element
+    pub fn str_value(&self) -> anyhow::Result<Option<&JStr>> {
+        if self.val.tag != b's' {
+            return Ok(None);
+        }
+        self.pool.get_::<Utf8>(u16_at(&self.val.b, 1)?).map(Some)
+    }
+    /// Values of an array of `String` elements
+    pub fn str_array(&self) -> anyhow::Result<Option<Vec<&JStr>>> {
+        if self.val.tag != b'[' {
+            return Ok(None);
+        }
+        let b = &self.val.b;
+        (0..u16_at(b, 1)? as usize)
+            .map(|i| {
+                let at = 3 + i * 3;
+                if b.get(at) != Some(&b's') {
+                    anyhow::bail!("Expected an array of strings");
+                }
+                self.pool.get_::<Utf8>(u16_at(b, at + 1)?)
+            })
+            .collect::<anyhow::Result<_>>()
+            .map(Some)
+    }
+}
 
-pub struct ElemVal {}
\ No newline at end of file
+fn need(b: &[u8], len: usize) -> anyhow::Result<()> {
+    if b.len() < len {
+        anyhow::bail!("Unexpected end of attribute");
+    }
+    Ok(())
+}
+fn u16_at(b: &[u8], i: usize) -> anyhow::Result<u16> {
+    match b.get(i..i + 2) {
+        Some(&[x, y]) => Ok(u16::from_be_bytes([x, y])),
+        _ => anyhow::bail!("Unexpected end of attribute"),
+    }
+}
+fn get_u16(b: &mut Bytes) -> anyhow::Result<u16> {
+    need(b, 2)?;
+    Ok(b.get_u16())
+}
+/// Length of `count` nested attributes, checked once so that iterating them cannot fail
+fn attrs_len(b: &[u8], count: u16) -> anyhow::Result<usize> {
+    let mut len = 0;
+    for _ in 0..count {
+        len += 6 + u32_at(b, len + 2)? as usize;
+        need(b, len)?;
+    }
+    Ok(len)
+}
+fn u32_at(b: &[u8], i: usize) -> anyhow::Result<u32> {
+    match b.get(i..i + 4) {
+        Some(&[w, x, y, z]) => Ok(u32::from_be_bytes([w, x, y, z])),
+        _ => anyhow::bail!("Unexpected end of attribute"),
+    }
+}
+fn class_name(pool: &ClassPool, idx: u16) -> anyhow::Result<JStr> {
+    pool.get(pool.get_::<ClassInfo>(idx)?).cloned()
+}
+fn package_name(pool: &ClassPool, idx: u16) -> anyhow::Result<JStr> {
+    pool.get(pool.get_::<PackageInfo>(idx)?).cloned()
+}
+/// Class names of a table of `Class` pool indices
+fn class_list(b: &[u8], pool: &ClassPool) -> anyhow::Result<Vec<JStr>> {
+    let n = u16_at(b, 0)? as usize;
+    (0..n).map(|i| class_name(pool, u16_at(b, 2 + i * 2)?)).collect()
+}
+/// Length of `num_element_value_pairs` and the pairs following it
+fn elem_pairs_len(b: &[u8]) -> anyhow::Result<usize> {
+    elems_len(b, true)
+}
+/// Length of an `annotation`
+fn annotation_len(b: &[u8]) -> anyhow::Result<usize> {
+    Ok(2 + elem_pairs_len(b.get(2..).unwrap_or_default())?)
+}
+/// Length of a single `element_value`
+fn elem_value_len(b: &[u8]) -> anyhow::Result<usize> {
+    elems_len(b, false)
+}
+/// Walks element values without recursion, keeping the values left and whether they are
+/// named pairs for each level of nested annotations and arrays.
+fn elems_len(b: &[u8], pairs: bool) -> anyhow::Result<usize> {
+    let (mut len, mut levels) = if pairs { (2, vec![(u16_at(b, 0)?, true)]) } else { (0, vec![(1, false)]) };
+    while let Some((left, named)) = levels.last_mut() {
+        if *left == 0 {
+            levels.pop();
+            continue;
+        }
+        *left -= 1;
+        if *named {
+            len += 2;
+        }
+        match b.get(len) {
+            Some(b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' | b's' | b'c') => len += 3,
+            Some(b'e') => len += 5,
+            Some(b'@') => {
+                levels.push((u16_at(b, len + 3)?, true));
+                len += 5;
+            }
+            Some(b'[') => {
+                levels.push((u16_at(b, len + 1)?, false));
+                len += 3;
+            }
+            Some(t) => anyhow::bail!("Invalid element value tag: {}", *t as char),
+            None => anyhow::bail!("Unexpected end of annotation"),
+        }
+        if levels.len() > MAX_NESTING {
+            anyhow::bail!("Element values nested deeper than {MAX_NESTING} levels");
+        }
+    }
+    Ok(len)
+}
+
+#[derive(Clone)]
+pub struct ElemVal {
+    tag: u8,
+    b: Bytes,
+    /// Annotations and arrays enclosing this value
+    depth: u16,
+}
+impl Parsing for ElemVal {
+    fn parse(b: &mut Bytes, _pool: &ClassPool) -> anyhow::Result<Self> {
+        let len = elem_value_len(b)?;
+        need(b, len)?;
+        let b = b.split_to(len);
+        Ok(Self { tag: b[0], b, depth: 0 })
+    }
+}
+impl ElemVal {
+    fn decode(&self, pool: &ClassPool) -> anyhow::Result<ElemValue> {
+        let utf8 = |i| pool.get_::<Utf8>(i).cloned();
+        if matches!(self.tag, b'@' | b'[') && usize::from(self.depth) >= MAX_NESTING {
+            anyhow::bail!("Element values nested deeper than {MAX_NESTING} levels");
+        }
+        Ok(match self.tag {
+            b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' => ElemValue::Const(self.tag, pool.get_::<ConstVal>(u16_at(&self.b, 1)?)?),
+            b's' => ElemValue::Str(utf8(u16_at(&self.b, 1)?)?),
+            b'e' => ElemValue::Enum(utf8(u16_at(&self.b, 1)?)?, utf8(u16_at(&self.b, 3)?)?),
+            b'c' => ElemValue::Class(utf8(u16_at(&self.b, 1)?)?),
+            b'@' => {
+                let mut an = Data::<Annotation>::parse(&mut self.b.slice(1..), pool)?;
+                an.data.depth = self.depth + 1;
+                ElemValue::Annotation(an)
+            }
+            b'[' => ElemValue::Array(Nested { it: Iter::new(self.b.slice(1..), pool.clone()), depth: self.depth + 1 }),
+            t => anyhow::bail!("Invalid element value tag: {}", t as char),
+        })
+    }
+}
+impl Data<ElemVal> {
+    pub const fn tag(&self) -> u8 {
+        self.data.tag
+    }
+    pub fn value(&self) -> anyhow::Result<ElemValue> {
+        self.data.decode(&self.pool)
+    }
+}
+
+/// Decoded `element_value` of an annotation
+pub enum ElemValue {
+    /// Primitive constant with its tag, `Z` and `C` values are stored as ints
+    Const(u8, JVal),
+    Str(JStr),
+    /// Type descriptor and name of an enum constant
+    Enum(JStr, JStr),
+    /// Return descriptor of a class literal, `V` for `void.class`
+    Class(JStr),
+    Annotation(Data<Annotation>),
+    Array(Nested<Data<ElemVal>>),
+}
+
+/// Element values of an annotation or array, passing down their depth so that
+/// decoding stops at [`MAX_NESTING`] levels
+pub struct Nested<T: Parsing> {
+    it: Iter<T>,
+    depth: u16,
+}
+impl Iterator for Nested<Data<AnnElemPair>> {
+    type Item = anyhow::Result<Data<AnnElemPair>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let depth = self.depth;
+        self.it.next().map(|e| e.map(|mut e| {
+            e.data.val.depth = depth;
+            e
+        }))
+    }
+}
+impl Iterator for Nested<Data<ElemVal>> {
+    type Item = anyhow::Result<Data<ElemVal>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let depth = self.depth;
+        self.it.next().map(|v| v.map(|mut v| {
+            v.data.depth = depth;
+            v
+        }))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,236 @@
+//! Decoding of method bytecode
+
+use super::pool::{ClassPool, PoolRef};
+
+/// Mnemonics of opcodes `0x00..=0xC9`
+const MNEMONICS: [&str; 202] = [
+    "nop", "aconst_null", "iconst_m1", "iconst_0", "iconst_1", "iconst_2", "iconst_3", "iconst_4",
+    "iconst_5", "lconst_0", "lconst_1", "fconst_0", "fconst_1", "fconst_2", "dconst_0", "dconst_1",
+    "bipush", "sipush", "ldc", "ldc_w", "ldc2_w", "iload", "lload", "fload",
+    "dload", "aload", "iload_0", "iload_1", "iload_2", "iload_3", "lload_0", "lload_1",
+    "lload_2", "lload_3", "fload_0", "fload_1", "fload_2", "fload_3", "dload_0", "dload_1",
+    "dload_2", "dload_3", "aload_0", "aload_1", "aload_2", "aload_3", "iaload", "laload",
+    "faload", "daload", "aaload", "baload", "caload", "saload", "istore", "lstore",
+    "fstore", "dstore", "astore", "istore_0", "istore_1", "istore_2", "istore_3", "lstore_0",
+    "lstore_1", "lstore_2", "lstore_3", "fstore_0", "fstore_1", "fstore_2", "fstore_3", "dstore_0",
+    "dstore_1", "dstore_2", "dstore_3", "astore_0", "astore_1", "astore_2", "astore_3", "iastore",
+    "lastore", "fastore", "dastore", "aastore", "bastore", "castore", "sastore", "pop",
+    "pop2", "dup", "dup_x1", "dup_x2", "dup2", "dup2_x1", "dup2_x2", "swap",
+    "iadd", "ladd", "fadd", "dadd", "isub", "lsub", "fsub", "dsub",
+    "imul", "lmul", "fmul", "dmul", "idiv", "ldiv", "fdiv", "ddiv",
+    "irem", "lrem", "frem", "drem", "ineg", "lneg", "fneg", "dneg",
+    "ishl", "lshl", "ishr", "lshr", "iushr", "lushr", "iand", "land",
+    "ior", "lor", "ixor", "lxor", "iinc", "i2l", "i2f", "i2d",
+    "l2i", "l2f", "l2d", "f2i", "f2l", "f2d", "d2i", "d2l",
+    "d2f", "i2b", "i2c", "i2s", "lcmp", "fcmpl", "fcmpg", "dcmpl",
+    "dcmpg", "ifeq", "ifne", "iflt", "ifge", "ifgt", "ifle", "if_icmpeq",
+    "if_icmpne", "if_icmplt", "if_icmpge", "if_icmpgt", "if_icmple", "if_acmpeq", "if_acmpne", "goto",
+    "jsr", "ret", "tableswitch", "lookupswitch", "ireturn", "lreturn", "freturn", "dreturn",
+    "areturn", "return", "getstatic", "putstatic", "getfield", "putfield", "invokevirtual", "invokespecial",
+    "invokestatic", "invokeinterface", "invokedynamic", "new", "newarray", "anewarray", "arraylength", "athrow",
+    "checkcast", "instanceof", "monitorenter", "monitorexit", "wide", "multianewarray", "ifnull", "ifnonnull",
+    "goto_w", "jsr_w",
+];
+
+/// Mnemonic of an opcode, as printed by `javap`
+pub fn mnemonic(op: u8) -> Option<&'static str> {
+    MNEMONICS.get(op as usize).copied()
+}
+
+#[derive(Clone, Copy)]
+pub enum Operand<'a> {
+    None,
+    /// Value of `bipush` and `sipush`
+    Int(i16),
+    /// Local variable slot, also for implicit slots like `iload_0`
+    Local(u16),
+    /// Slot and increment of `iinc`
+    Iinc(u16, i16),
+    /// Absolute offset of a branch target
+    Branch(i64),
+    /// Constant pool index of `ldc`, field and method instructions, `new`, `checkcast`...
+    Pool(u16),
+    /// Constant pool index and argument count of `invokeinterface`
+    Interface(u16, u8),
+    /// Constant pool index and dimensions of `multianewarray`
+    MultiArray(u16, u8),
+    /// Array type code of `newarray` (4 for `boolean` to 11 for `long`)
+    NewArray(u8),
+    Switch(Switch<'a>),
+}
+
+/// Jump table of a `tableswitch` or `lookupswitch`, read without allocating
+#[derive(Clone, Copy)]
+pub struct Switch<'a> {
+    pc: usize,
+    default: i32,
+    /// Lowest key of a `tableswitch`
+    low: Option<i32>,
+    b: &'a [u8],
+}
+impl<'a> Switch<'a> {
+    pub const fn is_table(&self) -> bool {
+        self.low.is_some()
+    }
+    /// Number of cases, not counting the default
+    pub const fn len(&self) -> usize {
+        self.b.len() / if self.is_table() { 4 } else { 8 }
+    }
+    pub const fn is_empty(&self) -> bool {
+        self.b.is_empty()
+    }
+    /// Lowest and highest key of a `tableswitch`
+    pub fn bounds(&self) -> Option<(i32, i32)> {
+        self.low.map(|low| (low, low.wrapping_add(self.len() as i32 - 1)))
+    }
+    /// Absolute offset of the default target
+    pub const fn default(&self) -> i64 {
+        self.pc as i64 + self.default as i64
+    }
+    /// Keys and absolute offsets of the targets
+    pub fn cases(&self) -> impl Iterator<Item = (i32, i64)> + 'a {
+        let (pc, low) = (self.pc as i64, self.low);
+        let size = if low.is_some() { 4 } else { 8 };
+        self.b.chunks_exact(size).enumerate().map(move |(i, x)| {
+            let int = |at: usize| i32::from_be_bytes([x[at], x[at + 1], x[at + 2], x[at + 3]]);
+            low.map_or_else(
+                || (int(0), pc + i64::from(int(4))),
+                |low| (low.wrapping_add(i as i32), pc + i64::from(int(0))),
+            )
+        })
+    }
+}
+
+/// A decoded instruction
+#[derive(Clone, Copy)]
+pub struct Insn<'a> {
+    pc: usize,
+    opcode: u8,
+    wide: bool,
+    operand: Operand<'a>,
+}
+impl<'a> Insn<'a> {
+    pub const fn pc(&self) -> usize {
+        self.pc
+    }
+    /// Opcode of the instruction, the modified one for `wide` instructions
+    pub const fn opcode(&self) -> u8 {
+        self.opcode
+    }
+    pub const fn is_wide(&self) -> bool {
+        self.wide
+    }
+    pub const fn operand(&self) -> Operand<'a> {
+        self.operand
+    }
+    pub const fn mnemonic(&self) -> &'static str {
+        MNEMONICS[self.opcode as usize]
+    }
+    pub const fn pool_index(&self) -> Option<u16> {
+        match self.operand {
+            Operand::Pool(i) | Operand::Interface(i, _) | Operand::MultiArray(i, _) => Some(i),
+            _ => None,
+        }
+    }
+    /// Resolves the constant pool operand, if any.
+    pub fn resolve<'p>(&self, pool: &'p ClassPool) -> anyhow::Result<Option<PoolRef<'p>>> {
+        self.pool_index().map(|i| pool.resolve(i)).transpose()
+    }
+}
+
+/// Iterator over the instructions of a method, stopping after the first error
+pub struct Insns<'a> {
+    code: &'a [u8],
+    pc: usize,
+}
+impl<'a> Insns<'a> {
+    pub const fn new(code: &'a [u8]) -> Self {
+        Self { code, pc: 0 }
+    }
+    fn read<const N: usize>(&self, at: &mut usize) -> anyhow::Result<[u8; N]> {
+        let x = self.code.get(*at..*at + N)
+            .ok_or_else(|| anyhow::anyhow!("Unexpected end of code at {}", self.pc))?;
+        *at += N;
+        Ok(x.try_into()?)
+    }
+    fn decode(&mut self) -> anyhow::Result<Insn<'a>> {
+        let pc = self.pc;
+        let mut at = pc + 1;
+        let op = self.code[pc];
+        let mut opcode = op;
+        let operand = match op {
+            0x10 => Operand::Int(i16::from(self.read::<1>(&mut at)?[0] as i8)),
+            0x11 => Operand::Int(i16::from_be_bytes(self.read(&mut at)?)),
+            0x12 => Operand::Pool(u16::from(self.read::<1>(&mut at)?[0])),
+            0x13 | 0x14 | 0xb2..=0xb8 | 0xbb | 0xbd | 0xc0 | 0xc1 => Operand::Pool(u16::from_be_bytes(self.read(&mut at)?)),
+            0x15..=0x19 | 0x36..=0x3a | 0xa9 => Operand::Local(u16::from(self.read::<1>(&mut at)?[0])),
+            // Loads and stores with an implicit slot (`iload_0` to `astore_3`)
+            0x1a..=0x2d | 0x3b..=0x4e => Operand::Local(u16::from((op - if op < 0x3b { 0x1a } else { 0x3b }) % 4)),
+            0x84 => {
+                let [slot, n] = self.read(&mut at)?;
+                Operand::Iinc(u16::from(slot), i16::from(n as i8))
+            }
+            0x99..=0xa8 | 0xc6 | 0xc7 => Operand::Branch(pc as i64 + i64::from(i16::from_be_bytes(self.read(&mut at)?))),
+            0xc8 | 0xc9 => Operand::Branch(pc as i64 + i64::from(i32::from_be_bytes(self.read(&mut at)?))),
+            0xaa | 0xab => {
+                // Padding aligns the operands to a multiple of 4 from the start of the code
+                at += (4 - at % 4) % 4;
+                let default = i32::from_be_bytes(self.read(&mut at)?);
+                let (low, n) = if op == 0xaa {
+                    let low = i32::from_be_bytes(self.read(&mut at)?);
+                    let high = i32::from_be_bytes(self.read(&mut at)?);
+                    if high < low {
+                        anyhow::bail!("Invalid tableswitch bounds at {pc}");
+                    }
+                    (Some(low), (i64::from(high) - i64::from(low) + 1) as usize * 4)
+                } else {
+                    let n = u32::from_be_bytes(self.read(&mut at)?);
+                    (None, n as usize * 8)
+                };
+                let b = self.code.get(at..at + n)
+                    .ok_or_else(|| anyhow::anyhow!("Unexpected end of code at {pc}"))?;
+                at += n;
+                Operand::Switch(Switch { pc, default, low, b })
+            }
+            0xb9 => {
+                let [i1, i2, count, _] = self.read(&mut at)?;
+                Operand::Interface(u16::from_be_bytes([i1, i2]), count)
+            }
+            0xba => {
+                let [i1, i2, _, _] = self.read(&mut at)?;
+                Operand::Pool(u16::from_be_bytes([i1, i2]))
+            }
+            0xbc => Operand::NewArray(self.read::<1>(&mut at)?[0]),
+            0xc4 => {
+                opcode = self.read::<1>(&mut at)?[0];
+                let slot = u16::from_be_bytes(self.read(&mut at)?);
+                match opcode {
+                    0x84 => Operand::Iinc(slot, i16::from_be_bytes(self.read(&mut at)?)),
+                    0x15..=0x19 | 0x36..=0x3a | 0xa9 => Operand::Local(slot),
+                    _ => anyhow::bail!("Invalid wide opcode 0x{opcode:02x} at {pc}"),
+                }
+            }
+            0xc5 => {
+                let [i1, i2, dims] = self.read(&mut at)?;
+                Operand::MultiArray(u16::from_be_bytes([i1, i2]), dims)
+            }
+            0..=0xc9 => Operand::None,
+            _ => anyhow::bail!("Invalid opcode 0x{op:02x} at {pc}"),
+        };
+        self.pc = at;
+        Ok(Insn { pc, opcode, wide: op == 0xc4, operand })
+    }
+}
+impl<'a> Iterator for Insns<'a> {
+    type Item = anyhow::Result<Insn<'a>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pc >= self.code.len() {
+            return None;
+        }
+        let insn = self.decode();
+        if insn.is_err() {
+            self.pc = self.code.len();
+        }
+        Some(insn)
+    }
+}
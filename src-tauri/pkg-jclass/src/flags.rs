@@ -0,0 +1,72 @@
+//! Access flags of classes, fields, methods, inner classes and module directives
+
+pub type FlagTable = &'static [(u16, &'static str)];
+
+/// Set on interfaces and annotation types
+pub const ACC_INTERFACE: u16 = 0x0200;
+
+pub const CLASS: FlagTable = &[
+    (0x0001, "public"),
+    (0x0010, "final"),
+    (0x0020, "super"),
+    (ACC_INTERFACE, "interface"),
+    (0x0400, "abstract"),
+    (0x1000, "synthetic"),
+    (0x2000, "annotation"),
+    (0x4000, "enum"),
+    (0x8000, "module"),
+];
+pub const FIELD: FlagTable = &[
+    (0x0001, "public"),
+    (0x0002, "private"),
+    (0x0004, "protected"),
+    (0x0008, "static"),
+    (0x0010, "final"),
+    (0x0040, "volatile"),
+    (0x0080, "transient"),
+    (0x1000, "synthetic"),
+    (0x4000, "enum"),
+];
+pub const METHOD: FlagTable = &[
+    (0x0001, "public"),
+    (0x0002, "private"),
+    (0x0004, "protected"),
+    (0x0008, "static"),
+    (0x0010, "final"),
+    (0x0020, "synchronized"),
+    (0x0040, "bridge"),
+    (0x0080, "varargs"),
+    (0x0100, "native"),
+    (0x0400, "abstract"),
+    (0x0800, "strict"),
+    (0x1000, "synthetic"),
+];
+pub const INNER_CLASS: FlagTable = &[
+    (0x0001, "public"),
+    (0x0002, "private"),
+    (0x0004, "protected"),
+    (0x0008, "static"),
+    (0x0010, "final"),
+    (ACC_INTERFACE, "interface"),
+    (0x0400, "abstract"),
+    (0x1000, "synthetic"),
+    (0x2000, "annotation"),
+    (0x4000, "enum"),
+];
+
+pub const MODULE: FlagTable = &[
+    (0x0020, "open"),
+    (0x1000, "synthetic"),
+    (0x8000, "mandated"),
+];
+pub const REQUIRES: FlagTable = &[
+    (0x0020, "transitive"),
+    (0x0040, "static"),
+    (0x1000, "synthetic"),
+    (0x8000, "mandated"),
+];
+
+/// Names of flags set in `flags`, in declaration order of the table
+pub fn names(flags: u16, table: FlagTable) -> impl Iterator<Item = &'static str> {
+    table.iter().filter(move |(f, _)| flags & f != 0).map(|(_, n)| *n)
+}
@@ -1,6 +1,6 @@
 use std::{marker::PhantomData, num::NonZeroU16};
 
-use super::{pool::JVal, JStr, PoolItem};
+use super::{pool::{JVal, RefKind}, JStr, PoolItem};
 
 
 
@@ -11,6 +11,9 @@ pub enum MethodRef {}
 pub enum InterfaceMethodRef {}
 pub enum AnyMethodRef {}
 pub enum ClassInfo {}
+pub enum MethodHandle {}
+pub enum ModuleInfo {}
+pub enum PackageInfo {}
 pub enum ConstVal {}
 
 pub trait UseIndex<'a> {
@@ -23,6 +26,14 @@ impl<R> Index<R> {
     pub fn maybe(value: u16) -> Option<Self> {
         NonZeroU16::new(value).map(|x| Self(x, PhantomData))
     }
+    #[inline]
+    pub const fn get(self) -> u16 {
+        self.0.get()
+    }
+    /// Replaces the index, keeping the type of the item
+    pub fn map(self, f: impl FnOnce(u16) -> u16) -> anyhow::Result<Self> {
+        Self::try_from(f(self.get()))
+    }
 }
 impl<R> Clone for Index<R> {
     fn clone(&self) -> Self { *self }
@@ -90,6 +101,24 @@ impl UseIndex<'_> for ClassInfo {
         if let PoolItem::Class(a) = item { Some(*a) } else { None }
     }
 }
+impl<'a> UseIndex<'a> for MethodHandle {
+    type Out = &'a RefKind;
+    fn at(item: &'a PoolItem) -> Option<Self::Out> {
+        if let PoolItem::MethodHandle(rk) = item { Some(rk) } else { None }
+    }
+}
+impl UseIndex<'_> for ModuleInfo {
+    type Out = Index<Utf8>;
+    fn at(item: &PoolItem) -> Option<Self::Out> {
+        if let PoolItem::Module(a) = item { Some(*a) } else { None }
+    }
+}
+impl UseIndex<'_> for PackageInfo {
+    type Out = Index<Utf8>;
+    fn at(item: &PoolItem) -> Option<Self::Out> {
+        if let PoolItem::Package(a) = item { Some(*a) } else { None }
+    }
+}
 impl UseIndex<'_> for ConstVal {
     type Out = JVal;
     fn at(item: &PoolItem) -> Option<Self::Out> {
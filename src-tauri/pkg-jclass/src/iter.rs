@@ -1,7 +1,7 @@
 use std::{io::Read, marker::PhantomData};
 use bytes::{Buf, Bytes};
 use byteorder::{ReadBytesExt, BE};
-use super::{idx::{ClassInfo, Index}, jtype::MemberType, len_member_info, pool::ClassPool, read_member_info, AttrInfo, JStr, MemberInfo};
+use super::{idx::{ClassInfo, Index}, jtype::MemberType, member_info_len, pool::ClassPool, read_member_info, AttrInfo, JStr, MemberInfo};
 
 pub struct Interfaces {
     inner: std::vec::IntoIter<u16>,
@@ -44,8 +44,8 @@ impl <T: MemberType> MemberIter<T> {
     pub fn from_read(r: &mut impl Read, pool: &ClassPool) -> anyhow::Result<Self> {
         let mut bmut = bytes::BytesMut::new();
         read_member_info(r, &mut bmut)?;
-        let b = bmut.freeze();
-        let len = b.clone().get_u16();
+        let mut b = bmut.freeze();
+        let len = b.get_u16();
         Ok(Self { b, pool: pool.clone(), cur: 0, len, _t: PhantomData })
     }
     pub const fn new(b: Bytes, pool: ClassPool, len: u16) -> Self {
@@ -58,14 +58,19 @@ impl <T: MemberType> Iterator for MemberIter<T> {
         if self.cur == self.len {
             return None
         }
-        let mut b = self.b.clone();
-        let mut l = 0;
-        len_member_info(&mut b, &mut l);
-        let mut b = self.b.split_to(l);
+        let mut b = match member_info_len(&self.b) {
+            Ok(len) => self.b.split_to(len),
+            Err(err) => {
+                // The following members can't be located
+                self.cur = self.len;
+                return Some(Err(err))
+            }
+        };
         let flags = b.get_u16();
         let iname = b.get_u16();
         let idesc = b.get_u16();
         let attr_count = b.get_u16();
+        self.cur += 1;
         let name_idx = match Index::try_from(iname) {
             Ok(idx) => idx,
             Err(err) => return Some(Err(err))
@@ -74,7 +79,6 @@ impl <T: MemberType> Iterator for MemberIter<T> {
             Ok(idx) => idx,
             Err(err) => return Some(Err(err))
         };
-        self.cur += 1;
         Some(Ok(MemberInfo {
             b,
             pool: self.pool.clone(),
@@ -85,7 +89,14 @@ impl <T: MemberType> Iterator for MemberIter<T> {
             _t: PhantomData
         }))
     }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = (self.len - self.cur) as usize;
+        (n, Some(n))
+    }
 }
+impl<T: MemberType> ExactSizeIterator for MemberIter<T> {}
 
 #[derive(Clone)]
 pub struct Attrs<T> {
@@ -106,13 +117,19 @@ impl<T> Iterator for Attrs<T> {
         if self.cur == self.len {
             return None
         }
-        let name_idx = match Index::try_from(self.b.get_u16()) {
-            Ok(idx) => idx,
-            Err(err) => return Some(Err(err))
+        let header = self.b.get(..6).map(|h| (u16::from_be_bytes([h[0], h[1]]), u32::from_be_bytes([h[2], h[3], h[4], h[5]]) as usize));
+        let Some((name, len)) = header.filter(|&(_, len)| len <= self.b.len() - 6) else {
+            // The following attributes can't be located
+            self.cur = self.len;
+            return Some(Err(anyhow::anyhow!("Unexpected end of attributes")))
         };
-        let len = self.b.get_u32() as usize;
+        self.b.advance(6);
         let b = self.b.split_to(len);
         self.cur += 1;
+        let name_idx = match Index::try_from(name) {
+            Ok(idx) => idx,
+            Err(err) => return Some(Err(err))
+        };
         Some(Ok(AttrInfo { b, pool: self.pool.clone(), name_idx, _t: PhantomData }))
     }
 }
\ No newline at end of file
@@ -5,10 +5,762 @@ pub trait MemberType {}
 pub enum OfField {}
 pub enum OfMethod {}
 pub enum OfClass {}
+/// Attributes nested in a `Code` attribute
+pub enum OfCode {}
+/// Attributes of a component of a `Record` attribute
+pub enum OfRecordComponent {}
 
 impl Type for OfField {}
 impl Type for OfMethod {}
 impl Type for OfClass {}
+impl Type for OfCode {}
+impl Type for OfRecordComponent {}
 
 impl MemberType for OfField {}
-impl MemberType for OfMethod {}
\ No newline at end of file
+impl MemberType for OfMethod {}
+
+/// Primitive types and `void`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseType {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+    Void,
+}
+impl BaseType {
+    pub const fn from_tag(c: u8) -> Option<Self> {
+        Some(match c {
+            b'B' => Self::Byte,
+            b'C' => Self::Char,
+            b'D' => Self::Double,
+            b'F' => Self::Float,
+            b'I' => Self::Int,
+            b'J' => Self::Long,
+            b'S' => Self::Short,
+            b'Z' => Self::Boolean,
+            b'V' => Self::Void,
+            _ => return None,
+        })
+    }
+    pub const fn tag(self) -> char {
+        match self {
+            Self::Byte => 'B',
+            Self::Char => 'C',
+            Self::Double => 'D',
+            Self::Float => 'F',
+            Self::Int => 'I',
+            Self::Long => 'J',
+            Self::Short => 'S',
+            Self::Boolean => 'Z',
+            Self::Void => 'V',
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Byte, Self::Char, Self::Double, Self::Float, Self::Int, Self::Long, Self::Short, Self::Boolean, Self::Void]
+            .into_iter()
+            .find(|b| b.name() == name)
+    }
+    /// Name as written in Java source
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Byte => "byte",
+            Self::Char => "char",
+            Self::Double => "double",
+            Self::Float => "float",
+            Self::Int => "int",
+            Self::Long => "long",
+            Self::Short => "short",
+            Self::Boolean => "boolean",
+            Self::Void => "void",
+        }
+    }
+}
+
+/// Type of a field descriptor or signature, or of a part of a method descriptor or signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JavaType {
+    Base(BaseType),
+    Class(ClassType),
+    /// Type variable, only in signatures
+    Var(Box<str>),
+    Array(Box<Self>),
+}
+
+/// Class type with its type arguments, which only signatures have
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassType {
+    /// Internal name, such as `java/util/Map$Entry`
+    pub name: Box<str>,
+    pub args: Vec<TypeArg>,
+    /// Inner classes of a parameterized type, as in `Outer<T>.Inner`
+    pub inner: Vec<InnerClass>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InnerClass {
+    /// Simple name
+    pub name: Box<str>,
+    pub args: Vec<TypeArg>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeArg {
+    /// `?`
+    Any,
+    Exact(JavaType),
+    /// `? extends T`
+    Extends(JavaType),
+    /// `? super T`
+    Super(JavaType),
+}
+
+/// Type parameter of a generic class or method, as in `T extends Number & Comparable<T>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeParam {
+    pub name: Box<str>,
+    /// Missing when only interface bounds are given
+    pub class_bound: Option<JavaType>,
+    pub interface_bounds: Vec<JavaType>,
+}
+
+/// Method descriptor or generic method signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodSig {
+    pub type_params: Vec<TypeParam>,
+    pub params: Vec<JavaType>,
+    pub ret: JavaType,
+    /// Only listed by signatures, and only if they mention type variables
+    pub throws: Vec<JavaType>,
+}
+
+/// Generic class signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassSig {
+    pub type_params: Vec<TypeParam>,
+    pub super_class: ClassType,
+    pub interfaces: Vec<ClassType>,
+}
+
+/// Nesting limit of array dimensions and type arguments in a signature. Parsing, rendering
+/// and dropping types recurse through every level, so deeper signatures would run out of stack.
+const MAX_DEPTH: usize = 512;
+
+/// Parses descriptors, and signatures if `generic` is set.
+struct SigParser<'a> {
+    s: &'a str,
+    i: usize,
+    generic: bool,
+    /// Array dimensions and type arguments around the current position
+    depth: usize,
+}
+impl<'a> SigParser<'a> {
+    const fn new(s: &'a str, generic: bool) -> Self {
+        Self { s, i: 0, generic, depth: 0 }
+    }
+    fn peek(&self) -> Option<u8> {
+        self.s.as_bytes().get(self.i).copied()
+    }
+    fn next(&mut self) -> anyhow::Result<u8> {
+        let c = self.peek().ok_or_else(|| anyhow::anyhow!("Unexpected end of signature"))?;
+        self.i += 1;
+        Ok(c)
+    }
+    fn eat(&mut self, c: u8) -> bool {
+        let b = self.peek() == Some(c);
+        if b {
+            self.i += 1;
+        }
+        b
+    }
+    fn expect(&mut self, c: u8) -> anyhow::Result<()> {
+        if !self.eat(c) {
+            anyhow::bail!("Expected '{}' at {} in signature", c as char, self.i);
+        }
+        Ok(())
+    }
+    /// Checks that the signature may use generics at the current position.
+    fn generic(&self) -> anyhow::Result<()> {
+        if !self.generic {
+            anyhow::bail!("Unexpected generic type at {} in descriptor", self.i);
+        }
+        Ok(())
+    }
+    fn end(&self) -> anyhow::Result<()> {
+        if self.i < self.s.len() {
+            anyhow::bail!("Trailing data at {} in signature", self.i);
+        }
+        Ok(())
+    }
+    /// Reads a non-empty identifier until one of the delimiters, which must follow it.
+    fn ident(&mut self, delims: &[u8]) -> anyhow::Result<&'a str> {
+        let start = self.i;
+        while let Some(c) = self.peek() {
+            if delims.contains(&c) {
+                break;
+            }
+            if matches!(c, b'.' | b';' | b'[' | b'/' | b'<' | b'>' | b':') {
+                anyhow::bail!("Invalid character '{}' at {} in signature", c as char, self.i);
+            }
+            self.i += 1;
+        }
+        if self.i == start || self.peek().is_none() {
+            anyhow::bail!("Invalid identifier at {} in signature", start);
+        }
+        Ok(&self.s[start..self.i])
+    }
+    /// Reads an internal class name, the packages separated by `/`.
+    fn class_name(&mut self) -> anyhow::Result<&'a str> {
+        let start = self.i;
+        while {
+            self.ident(b"/;<.")?;
+            self.eat(b'/')
+        } {}
+        Ok(&self.s[start..self.i])
+    }
+
+    /// Reads any type but `void`.
+    fn field_type(&mut self) -> anyhow::Result<JavaType> {
+        match self.return_type()? {
+            JavaType::Base(BaseType::Void) => anyhow::bail!("Unexpected void at {} in signature", self.i - 1),
+            t => Ok(t),
+        }
+    }
+    fn return_type(&mut self) -> anyhow::Result<JavaType> {
+        let start = self.i;
+        Ok(match self.next()? {
+            b'L' => JavaType::Class(self.class_type()?),
+            b'T' => {
+                self.generic()?;
+                let name = self.ident(b";")?;
+                self.expect(b';')?;
+                JavaType::Var(name.into())
+            }
+            b'[' => {
+                let mut dims = 1;
+                while self.eat(b'[') {
+                    dims += 1;
+                }
+                if dims > 255 {
+                    anyhow::bail!("Too many array dimensions at {} in signature", start);
+                }
+                let mut t = self.nested(dims, Self::field_type)?;
+                for _ in 0..dims {
+                    t = JavaType::Array(Box::new(t));
+                }
+                t
+            }
+            c => JavaType::Base(BaseType::from_tag(c).ok_or_else(|| anyhow::anyhow!("Invalid type tag '{}' at {} in signature", c as char, start))?),
+        })
+    }
+    /// Runs `f` `levels` deeper, failing past [`MAX_DEPTH`] levels.
+    fn nested<T>(&mut self, levels: usize, f: impl FnOnce(&mut Self) -> anyhow::Result<T>) -> anyhow::Result<T> {
+        if self.depth + levels > MAX_DEPTH {
+            anyhow::bail!("Nested deeper than {MAX_DEPTH} levels at {} in signature", self.i);
+        }
+        self.depth += levels;
+        let r = f(self);
+        self.depth -= levels;
+        r
+    }
+    /// Reads a class type after its `L`.
+    fn class_type(&mut self) -> anyhow::Result<ClassType> {
+        let name = self.class_name()?.into();
+        let args = self.type_args()?;
+        let mut inner = vec![];
+        while self.eat(b'.') {
+            self.generic()?;
+            let name = self.ident(b";<.")?.into();
+            inner.push(InnerClass { name, args: self.type_args()? });
+        }
+        self.expect(b';')?;
+        Ok(ClassType { name, args, inner })
+    }
+    fn class_sig_type(&mut self) -> anyhow::Result<ClassType> {
+        self.expect(b'L')?;
+        self.class_type()
+    }
+    fn reference_type(&mut self) -> anyhow::Result<JavaType> {
+        match self.peek() {
+            Some(b'L' | b'T' | b'[') => self.field_type(),
+            _ => anyhow::bail!("Expected a reference type at {} in signature", self.i),
+        }
+    }
+    fn type_args(&mut self) -> anyhow::Result<Vec<TypeArg>> {
+        if self.peek() != Some(b'<') {
+            return Ok(vec![]);
+        }
+        self.generic()?;
+        self.i += 1;
+        self.nested(1, Self::type_args_list)
+    }
+    /// Reads type arguments after their `<`.
+    fn type_args_list(&mut self) -> anyhow::Result<Vec<TypeArg>> {
+        let mut args = vec![];
+        while !self.eat(b'>') {
+            args.push(match self.peek() {
+                Some(b'*') => {
+                    self.i += 1;
+                    TypeArg::Any
+                }
+                Some(b'+') => {
+                    self.i += 1;
+                    TypeArg::Extends(self.reference_type()?)
+                }
+                Some(b'-') => {
+                    self.i += 1;
+                    TypeArg::Super(self.reference_type()?)
+                }
+                _ => TypeArg::Exact(self.reference_type()?),
+            });
+        }
+        if args.is_empty() {
+            anyhow::bail!("Empty type arguments at {} in signature", self.i);
+        }
+        Ok(args)
+    }
+    fn type_params(&mut self) -> anyhow::Result<Vec<TypeParam>> {
+        if !self.eat(b'<') {
+            return Ok(vec![]);
+        }
+        let mut params = vec![];
+        while !self.eat(b'>') {
+            let name = self.ident(b":")?.into();
+            self.expect(b':')?;
+            // The class bound may be empty, as when only interface bounds follow
+            let class_bound = if matches!(self.peek(), Some(b'L' | b'T' | b'[')) { Some(self.reference_type()?) } else { None };
+            let mut interface_bounds = vec![];
+            while self.eat(b':') {
+                interface_bounds.push(self.reference_type()?);
+            }
+            params.push(TypeParam { name, class_bound, interface_bounds });
+        }
+        if params.is_empty() {
+            anyhow::bail!("Empty type parameters at {} in signature", self.i);
+        }
+        Ok(params)
+    }
+    fn method(&mut self) -> anyhow::Result<MethodSig> {
+        let type_params = if self.generic { self.type_params()? } else { vec![] };
+        self.expect(b'(')?;
+        let mut params = vec![];
+        while !self.eat(b')') {
+            params.push(self.field_type()?);
+        }
+        let ret = self.return_type()?;
+        let mut throws = vec![];
+        while self.peek() == Some(b'^') {
+            self.generic()?;
+            self.i += 1;
+            throws.push(match self.reference_type()? {
+                JavaType::Array(_) => anyhow::bail!("Array type in throws clause at {} in signature", self.i),
+                t => t,
+            });
+        }
+        self.end()?;
+        Ok(MethodSig { type_params, params, ret, throws })
+    }
+}
+
+impl JavaType {
+    /// Parses a field descriptor, such as `[Ljava/lang/String;`.
+    pub fn from_desc(desc: &str) -> anyhow::Result<Self> {
+        let mut p = SigParser::new(desc, false);
+        let t = p.field_type()?;
+        p.end()?;
+        Ok(t)
+    }
+    /// Parses a field signature, such as `Ljava/util/List<TT;>;`.
+    pub fn from_sig(sig: &str) -> anyhow::Result<Self> {
+        let mut p = SigParser::new(sig, true);
+        let t = p.reference_type()?;
+        p.end()?;
+        Ok(t)
+    }
+    /// Element type of arrays, the type itself otherwise
+    pub fn element(&self) -> &Self {
+        match self {
+            Self::Array(t) => t.element(),
+            t => t,
+        }
+    }
+    /// Classes referenced by the type, including in type arguments and bounds
+    pub fn classes(&self) -> impl Iterator<Item = &ClassType> {
+        let mut v = vec![];
+        self.collect_classes(&mut v);
+        v.into_iter()
+    }
+    fn collect_classes<'a>(&'a self, out: &mut Vec<&'a ClassType>) {
+        match self {
+            Self::Class(c) => {
+                out.push(c);
+                for a in c.args.iter().chain(c.inner.iter().flat_map(|i| &i.args)) {
+                    if let TypeArg::Exact(t) | TypeArg::Extends(t) | TypeArg::Super(t) = a {
+                        t.collect_classes(out);
+                    }
+                }
+            }
+            Self::Array(t) => t.collect_classes(out),
+            Self::Base(_) | Self::Var(_) => {}
+        }
+    }
+    /// Renames classes by internal name, keeping those for which `f` returns `None`.
+    pub fn map_classes(&mut self, f: &mut impl FnMut(&str) -> Option<Box<str>>) {
+        match self {
+            Self::Class(c) => c.map_classes(f),
+            Self::Array(t) => t.map_classes(f),
+            Self::Base(_) | Self::Var(_) => {}
+        }
+    }
+    /// Writes the type back as a descriptor or signature.
+    pub fn to_sig(&self) -> String {
+        let mut s = String::new();
+        self.write_sig(&mut s);
+        s
+    }
+    fn write_sig(&self, out: &mut String) {
+        match self {
+            Self::Base(b) => out.push(b.tag()),
+            Self::Class(c) => c.write_sig(out),
+            Self::Var(v) => {
+                out.push('T');
+                out.push_str(v);
+                out.push(';');
+            }
+            Self::Array(t) => {
+                out.push('[');
+                t.write_sig(out);
+            }
+        }
+    }
+}
+
+impl ClassType {
+    /// Class type without type arguments
+    pub fn new(name: &str) -> Self {
+        Self { name: name.into(), args: vec![], inner: vec![] }
+    }
+    /// Internal name including inner classes, such as `java/util/Map$Entry`
+    pub fn internal_name(&self) -> String {
+        let mut s = self.name.to_string();
+        for i in &self.inner {
+            s.push('$');
+            s.push_str(&i.name);
+        }
+        s
+    }
+    pub fn is_object(&self) -> bool {
+        &*self.name == "java/lang/Object" && self.args.is_empty() && self.inner.is_empty()
+    }
+    fn map_classes(&mut self, f: &mut impl FnMut(&str) -> Option<Box<str>>) {
+        let mut full = self.name.to_string();
+        if let Some(n) = f(&self.name) {
+            self.name = n;
+        }
+        let mut mapped = self.name.to_string();
+        for a in &mut self.args {
+            a.map_classes(f);
+        }
+        for i in &mut self.inner {
+            full.push('$');
+            full.push_str(&i.name);
+            // Only the simple name can change, as the outer class is already renamed
+            if let Some(n) = f(&full).as_deref().and_then(|n| n.strip_prefix(mapped.as_str())?.strip_prefix('$')) {
+                i.name = n.into();
+            }
+            mapped.push('$');
+            mapped.push_str(&i.name);
+            for a in &mut i.args {
+                a.map_classes(f);
+            }
+        }
+    }
+    fn write_sig(&self, out: &mut String) {
+        out.push('L');
+        out.push_str(&self.name);
+        write_args_sig(&self.args, out);
+        for i in &self.inner {
+            out.push('.');
+            out.push_str(&i.name);
+            write_args_sig(&i.args, out);
+        }
+        out.push(';');
+    }
+}
+
+impl TypeArg {
+    fn map_classes(&mut self, f: &mut impl FnMut(&str) -> Option<Box<str>>) {
+        if let Self::Exact(t) | Self::Extends(t) | Self::Super(t) = self {
+            t.map_classes(f);
+        }
+    }
+}
+
+fn write_args_sig(args: &[TypeArg], out: &mut String) {
+    if args.is_empty() {
+        return;
+    }
+    out.push('<');
+    for a in args {
+        match a {
+            TypeArg::Any => out.push('*'),
+            TypeArg::Exact(t) => t.write_sig(out),
+            TypeArg::Extends(t) => {
+                out.push('+');
+                t.write_sig(out);
+            }
+            TypeArg::Super(t) => {
+                out.push('-');
+                t.write_sig(out);
+            }
+        }
+    }
+    out.push('>');
+}
+
+impl TypeParam {
+    fn bounds(&self) -> impl Iterator<Item = &JavaType> {
+        self.class_bound.iter().chain(&self.interface_bounds)
+    }
+}
+
+fn params_classes<'a>(params: &'a [TypeParam], out: &mut Vec<&'a ClassType>) {
+    for b in params.iter().flat_map(TypeParam::bounds) {
+        b.collect_classes(out);
+    }
+}
+fn params_map_classes(params: &mut [TypeParam], f: &mut impl FnMut(&str) -> Option<Box<str>>) {
+    for p in params {
+        for b in p.class_bound.iter_mut().chain(&mut p.interface_bounds) {
+            b.map_classes(f);
+        }
+    }
+}
+fn write_params_sig(params: &[TypeParam], out: &mut String) {
+    if params.is_empty() {
+        return;
+    }
+    out.push('<');
+    for p in params {
+        out.push_str(&p.name);
+        out.push(':');
+        if let Some(b) = &p.class_bound {
+            b.write_sig(out);
+        }
+        for b in &p.interface_bounds {
+            out.push(':');
+            b.write_sig(out);
+        }
+    }
+    out.push('>');
+}
+
+impl MethodSig {
+    /// Parses a method descriptor, such as `(ILjava/lang/String;)V`.
+    pub fn from_desc(desc: &str) -> anyhow::Result<Self> {
+        SigParser::new(desc, false).method()
+    }
+    /// Parses a method signature, such as `<T:Ljava/lang/Object;>(TT;)V^TE;`.
+    pub fn from_sig(sig: &str) -> anyhow::Result<Self> {
+        SigParser::new(sig, true).method()
+    }
+    pub fn classes(&self) -> impl Iterator<Item = &ClassType> {
+        let mut v = vec![];
+        params_classes(&self.type_params, &mut v);
+        for t in self.params.iter().chain([&self.ret]).chain(&self.throws) {
+            t.collect_classes(&mut v);
+        }
+        v.into_iter()
+    }
+    pub fn map_classes(&mut self, f: &mut impl FnMut(&str) -> Option<Box<str>>) {
+        params_map_classes(&mut self.type_params, f);
+        for t in self.params.iter_mut().chain([&mut self.ret]).chain(&mut self.throws) {
+            t.map_classes(f);
+        }
+    }
+    pub fn to_sig(&self) -> String {
+        let mut s = String::new();
+        write_params_sig(&self.type_params, &mut s);
+        s.push('(');
+        for p in &self.params {
+            p.write_sig(&mut s);
+        }
+        s.push(')');
+        self.ret.write_sig(&mut s);
+        for t in &self.throws {
+            s.push('^');
+            t.write_sig(&mut s);
+        }
+        s
+    }
+    /// Renders a declaration of the method as `<T> void foo(List<T>, int) throws IOException`.
+    pub fn render(&self, name: &str) -> String {
+        let mut s = String::new();
+        if !self.type_params.is_empty() {
+            s.push_str(&format!("{} ", TypeParams(&self.type_params)));
+        }
+        s.push_str(&format!("{} {name}({})", self.ret, Joined(&self.params, ", ")));
+        if !self.throws.is_empty() {
+            s.push_str(&format!(" throws {}", Joined(&self.throws, ", ")));
+        }
+        s
+    }
+}
+
+impl ClassSig {
+    /// Parses a class signature, such as `<T:Ljava/lang/Object;>Ljava/lang/Object;Ljava/lang/Comparable<TT;>;`.
+    pub fn from_sig(sig: &str) -> anyhow::Result<Self> {
+        let mut p = SigParser::new(sig, true);
+        let type_params = p.type_params()?;
+        let super_class = p.class_sig_type()?;
+        let mut interfaces = vec![];
+        while p.peek().is_some() {
+            interfaces.push(p.class_sig_type()?);
+        }
+        Ok(Self { type_params, super_class, interfaces })
+    }
+    pub fn classes(&self) -> impl Iterator<Item = &ClassType> {
+        let mut v = vec![];
+        params_classes(&self.type_params, &mut v);
+        for c in std::iter::once(&self.super_class).chain(&self.interfaces) {
+            v.push(c);
+            for a in c.args.iter().chain(c.inner.iter().flat_map(|i| &i.args)) {
+                if let TypeArg::Exact(t) | TypeArg::Extends(t) | TypeArg::Super(t) = a {
+                    t.collect_classes(&mut v);
+                }
+            }
+        }
+        v.into_iter()
+    }
+    pub fn map_classes(&mut self, f: &mut impl FnMut(&str) -> Option<Box<str>>) {
+        params_map_classes(&mut self.type_params, f);
+        for c in std::iter::once(&mut self.super_class).chain(&mut self.interfaces) {
+            c.map_classes(f);
+        }
+    }
+    pub fn to_sig(&self) -> String {
+        let mut s = String::new();
+        write_params_sig(&self.type_params, &mut s);
+        for c in std::iter::once(&self.super_class).chain(&self.interfaces) {
+            c.write_sig(&mut s);
+        }
+        s
+    }
+}
+
+/// Java source rendering, with class names shortened to their simple names
+impl std::fmt::Display for JavaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Base(b) => f.write_str(b.name()),
+            Self::Class(c) => c.fmt(f),
+            Self::Var(v) => f.write_str(v),
+            Self::Array(t) => write!(f, "{t}[]"),
+        }
+    }
+}
+impl std::fmt::Display for ClassType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", simple_name(&self.name), TypeArgs(&self.args))?;
+        for i in &self.inner {
+            write!(f, ".{}{}", i.name, TypeArgs(&i.args))?;
+        }
+        Ok(())
+    }
+}
+impl std::fmt::Display for TypeArg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Any => f.write_str("?"),
+            Self::Exact(t) => t.fmt(f),
+            Self::Extends(t) => write!(f, "? extends {t}"),
+            Self::Super(t) => write!(f, "? super {t}"),
+        }
+    }
+}
+impl std::fmt::Display for TypeParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)?;
+        let bounds = self.bounds().filter(|b| !matches!(b, JavaType::Class(c) if c.is_object())).collect::<Vec<_>>();
+        if !bounds.is_empty() {
+            write!(f, " extends {}", Joined(&bounds, " & "))?;
+        }
+        Ok(())
+    }
+}
+/// Renders as `<T> extends Base<T> implements Comparable<T>`.
+impl std::fmt::Display for ClassSig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.type_params.is_empty() {
+            write!(f, "{} ", TypeParams(&self.type_params))?;
+        }
+        write!(f, "extends {}", self.super_class)?;
+        if !self.interfaces.is_empty() {
+            write!(f, " implements {}", Joined(&self.interfaces, ", "))?;
+        }
+        Ok(())
+    }
+}
+
+struct Joined<'a, T>(&'a [T], &'a str);
+impl<T: std::fmt::Display> std::fmt::Display for Joined<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, x) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(self.1)?;
+            }
+            x.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+struct TypeArgs<'a>(&'a [TypeArg]);
+impl std::fmt::Display for TypeArgs<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        write!(f, "<{}>", Joined(self.0, ", "))
+    }
+}
+struct TypeParams<'a>(&'a [TypeParam]);
+impl std::fmt::Display for TypeParams<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{}>", Joined(self.0, ", "))
+    }
+}
+
+/// Converts an internal class name (`java/util/Map$Entry`) to its simple name (`Map.Entry`).
+pub fn simple_name(name: &str) -> String {
+    name.rsplit('/').next().unwrap_or(name).replace('$', ".")
+}
+
+/// Renders a field descriptor or signature (`Ljava/util/List<Ljava/lang/String;>;`) as `List<String>`.
+/// Also accepts `V`, as in annotation values of `void.class`.
+pub fn render_type(desc: &str) -> anyhow::Result<String> {
+    let mut p = SigParser::new(desc, true);
+    let t = p.return_type()?;
+    p.end()?;
+    Ok(t.to_string())
+}
+
+/// Renders a field as `List<String> name`, preferring the generic signature if present.
+pub fn render_field(name: &str, desc: &str, signature: Option<&str>) -> anyhow::Result<String> {
+    let ty = signature
+        .and_then(|s| JavaType::from_sig(s).ok())
+        .map_or_else(|| JavaType::from_desc(desc), Ok)?;
+    Ok(format!("{ty} {name}"))
+}
+
+/// Renders a method as `<T> void foo(List<T>, int) throws IOException`,
+/// preferring the generic signature if present.
+pub fn render_method(name: &str, desc: &str, signature: Option<&str>) -> anyhow::Result<String> {
+    signature
+        .and_then(|s| MethodSig::from_sig(s).ok())
+        .map_or_else(|| MethodSig::from_desc(desc), Ok)
+        .map(|m| m.render(name))
+}
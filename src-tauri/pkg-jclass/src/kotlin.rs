@@ -0,0 +1,1055 @@
+//! Decoding of the `@kotlin.Metadata` annotation.
+//!
+//! `d1` holds protocol buffers messages (`metadata.proto` and `jvm_metadata.proto` of the Kotlin compiler)
+//! packed into strings, and `d2` holds the strings they refer to.
+
+use std::collections::HashMap;
+
+use crate::{attr::{Annotation, Data}, jtype::MethodSig, pool::JVal, JStr};
+
+pub const METADATA_DESC: &[u8] = b"Lkotlin/Metadata;";
+
+/// Strings of the string table with a predefined index
+const PREDEFINED: &[&str] = &[
+    "kotlin/Any", "kotlin/Nothing", "kotlin/Unit", "kotlin/Throwable", "kotlin/Number",
+    "kotlin/Byte", "kotlin/Double", "kotlin/Float", "kotlin/Int", "kotlin/Long", "kotlin/Short", "kotlin/Boolean", "kotlin/Char",
+    "kotlin/CharSequence", "kotlin/String", "kotlin/Comparable", "kotlin/Enum",
+    "kotlin/Array", "kotlin/ByteArray", "kotlin/DoubleArray", "kotlin/FloatArray", "kotlin/IntArray",
+    "kotlin/LongArray", "kotlin/ShortArray", "kotlin/BooleanArray", "kotlin/CharArray",
+    "kotlin/Cloneable", "kotlin/Annotation",
+    "kotlin/collections/Iterable", "kotlin/collections/MutableIterable",
+    "kotlin/collections/Collection", "kotlin/collections/MutableCollection",
+    "kotlin/collections/List", "kotlin/collections/MutableList",
+    "kotlin/collections/Set", "kotlin/collections/MutableSet",
+    "kotlin/collections/Map", "kotlin/collections/MutableMap",
+    "kotlin/collections/Map.Entry", "kotlin/collections/MutableMap.MutableEntry",
+    "kotlin/collections/Iterator", "kotlin/collections/MutableIterator",
+    "kotlin/collections/ListIterator", "kotlin/collections/MutableListIterator",
+];
+/// Nesting limit of types, which may refer to each other through the type table
+const MAX_TYPE_DEPTH: usize = 16;
+
+/// Joins `d1` strings into bytes, undoing the 8-to-7 bit encoding of old compilers.
+fn decode_bytes(d1: &[String]) -> anyhow::Result<Vec<u8>> {
+    let utf8_mode = d1.first().is_some_and(|s| s.starts_with('\0'));
+    let chars = d1.iter().flat_map(|s| s.chars()).skip(usize::from(utf8_mode));
+    let mut b = chars
+        .map(|c| u8::try_from(c).map_err(|_| anyhow::anyhow!("Invalid character in Kotlin metadata")))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if utf8_mode {
+        return Ok(b);
+    }
+    for x in &mut b {
+        *x = x.wrapping_add(0x7F) & 0x7F;
+    }
+    let len = 7 * b.len() / 8;
+    let mut out = Vec::with_capacity(len);
+    let (mut at, mut bit) = (0, 0);
+    for _ in 0..len {
+        let lo = b[at] >> bit;
+        at += 1;
+        let hi = (b[at] & ((1 << (bit + 1)) - 1)) << (7 - bit);
+        out.push(lo.wrapping_add(hi));
+        if bit == 6 {
+            at += 1;
+            bit = 0;
+        } else {
+            bit += 1;
+        }
+    }
+    Ok(out)
+}
+
+fn varint(b: &mut &[u8]) -> anyhow::Result<u64> {
+    let mut v = 0;
+    for shift in (0..64).step_by(7) {
+        let Some((&x, rest)) = b.split_first() else {
+            anyhow::bail!("Unexpected end of Kotlin metadata");
+        };
+        *b = rest;
+        v |= u64::from(x & 0x7F) << shift;
+        if x & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    anyhow::bail!("Invalid varint in Kotlin metadata")
+}
+
+/// Splits a length-prefixed message from the start of `b`.
+fn delimited<'a>(b: &mut &'a [u8]) -> anyhow::Result<&'a [u8]> {
+    let len = usize::try_from(varint(b)?)?;
+    if len > b.len() {
+        anyhow::bail!("Unexpected end of Kotlin metadata");
+    }
+    let (msg, rest) = b.split_at(len);
+    *b = rest;
+    Ok(msg)
+}
+
+enum Wire<'a> {
+    Int(u64),
+    Bytes(&'a [u8]),
+}
+impl<'a> Wire<'a> {
+    /// Value of an `int32` or `enum` field (negative numbers are sign-extended to 64 bits)
+    #[allow(clippy::cast_possible_truncation)]
+    const fn int(&self) -> i32 {
+        match self {
+            Self::Int(v) => *v as i32,
+            Self::Bytes(_) => 0,
+        }
+    }
+    fn index(&self) -> usize {
+        usize::try_from(self.int()).unwrap_or(usize::MAX)
+    }
+    const fn bytes(&self) -> &'a [u8] {
+        match self {
+            Self::Bytes(b) => b,
+            Self::Int(_) => &[],
+        }
+    }
+    /// Values of a repeated `int32` field, packed or not
+    #[allow(clippy::cast_possible_truncation)]
+    fn ints(&self, out: &mut Vec<i32>) -> anyhow::Result<()> {
+        match self {
+            Self::Int(_) => out.push(self.int()),
+            Self::Bytes(b) => {
+                let mut b = *b;
+                while !b.is_empty() {
+                    out.push(varint(&mut b)? as i32);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fields of a protocol buffers message
+struct Proto<'a>(&'a [u8]);
+impl<'a> Iterator for Proto<'a> {
+    type Item = anyhow::Result<(u32, Wire<'a>)>;
+    fn next(&mut self) -> Option<Self::Item> {
+        (!self.0.is_empty()).then(|| self.field())
+    }
+}
+impl<'a> Proto<'a> {
+    fn take(&mut self, n: usize) -> anyhow::Result<&'a [u8]> {
+        if n > self.0.len() {
+            anyhow::bail!("Unexpected end of Kotlin metadata");
+        }
+        let (x, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(x)
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    fn field(&mut self) -> anyhow::Result<(u32, Wire<'a>)> {
+        let key = varint(&mut self.0)?;
+        let w = match key & 7 {
+            0 => Wire::Int(varint(&mut self.0)?),
+            1 => Wire::Int(u64::from_le_bytes(self.take(8)?.try_into()?)),
+            2 => Wire::Bytes(delimited(&mut self.0)?),
+            5 => Wire::Int(u64::from(u32::from_le_bytes(self.take(4)?.try_into()?))),
+            t => anyhow::bail!("Unsupported wire type {t} in Kotlin metadata"),
+        };
+        Ok(((key >> 3) as u32, w))
+    }
+}
+
+/// `StringTableTypes.Record`, applied to a range of string indices
+#[derive(Default)]
+struct Record {
+    predefined: Option<usize>,
+    string: Option<String>,
+    operation: i32,
+    substring: Vec<i32>,
+    replace_char: Vec<i32>,
+}
+
+/// Resolves string indices of the metadata messages (`JvmNameResolver`)
+struct Strings {
+    d2: Vec<String>,
+    /// Records with the index following their range
+    records: Vec<(usize, Record)>,
+}
+impl Strings {
+    fn new(types: &[u8], d2: Vec<String>) -> anyhow::Result<Self> {
+        let mut records = vec![];
+        let mut end = 0usize;
+        for f in Proto(types) {
+            let (1, w) = f? else { continue };
+            let mut r = Record::default();
+            let mut range = 1;
+            for f in Proto(w.bytes()) {
+                match f? {
+                    (1, w) => range = w.index(),
+                    (2, w) => r.predefined = Some(w.index()),
+                    (3, w) => r.operation = w.int(),
+                    (4, w) => w.ints(&mut r.substring)?,
+                    (5, w) => w.ints(&mut r.replace_char)?,
+                    (6, w) => r.string = Some(String::from_utf8_lossy(w.bytes()).into_owned()),
+                    _ => {}
+                }
+            }
+            end = end.saturating_add(range);
+            records.push((end, r));
+        }
+        Ok(Self { d2, records })
+    }
+    fn get(&self, index: usize) -> anyhow::Result<String> {
+        let i = self.records.partition_point(|(end, _)| *end <= index);
+        let d2 = || self.d2.get(index).cloned().ok_or_else(|| anyhow::anyhow!("Invalid string index {index} in Kotlin metadata"));
+        let Some((_, r)) = self.records.get(i) else {
+            return d2();
+        };
+        let mut s = match (&r.string, r.predefined.and_then(|p| PREDEFINED.get(p))) {
+            (Some(s), _) => s.clone(),
+            (None, Some(p)) => (*p).to_string(),
+            (None, None) => d2()?,
+        };
+        if let [begin, end, ..] = r.substring[..] {
+            let chars = s.chars().collect::<Vec<_>>();
+            if let (Ok(b), Ok(e)) = (usize::try_from(begin), usize::try_from(end)) {
+                if b <= e && e <= chars.len() {
+                    s = chars[b..e].iter().collect();
+                }
+            }
+        }
+        if let [from, to, ..] = r.replace_char[..] {
+            if let (Some(from), Some(to)) = (char::from_u32(from as u32), char::from_u32(to as u32)) {
+                s = s.replace(from, &to.to_string());
+            }
+        }
+        match r.operation {
+            // INTERNAL_TO_CLASS_ID
+            1 => s = s.replace('$', "."),
+            // DESC_TO_CLASS_ID
+            2 => {
+                let mut c = s.chars();
+                if c.next().is_some() && c.next_back().is_some() {
+                    s = c.as_str().to_string();
+                }
+                s = s.replace('$', ".");
+            }
+            _ => {}
+        }
+        Ok(s)
+    }
+}
+
+/// Simple name of a class id (`kotlin/collections/Map.Entry` becomes `Map.Entry`)
+fn simple_name(id: &str) -> &str {
+    id.rsplit('/').next().unwrap_or(id)
+}
+
+fn capitalize(s: &str) -> String {
+    let mut c = s.chars();
+    c.next().map_or_else(String::new, |f| f.to_ascii_uppercase().to_string() + c.as_str())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Internal,
+    Private,
+    Protected,
+    Public,
+    PrivateToThis,
+    Local,
+}
+impl Visibility {
+    const fn from_flags(flags: i32) -> Self {
+        match (flags >> 1) & 7 {
+            0 => Self::Internal,
+            1 => Self::Private,
+            2 => Self::Protected,
+            4 => Self::PrivateToThis,
+            5 => Self::Local,
+            _ => Self::Public,
+        }
+    }
+    /// Modifier as written in source, empty for the default (public) visibility
+    pub const fn keyword(self) -> &'static str {
+        match self {
+            Self::Internal => "internal ",
+            Self::Private | Self::PrivateToThis => "private ",
+            Self::Protected => "protected ",
+            Self::Public | Self::Local => "",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modality {
+    Final,
+    Open,
+    Abstract,
+    Sealed,
+}
+impl Modality {
+    const fn from_flags(flags: i32) -> Self {
+        match (flags >> 4) & 3 {
+            1 => Self::Open,
+            2 => Self::Abstract,
+            3 => Self::Sealed,
+            _ => Self::Final,
+        }
+    }
+    pub const fn keyword(self) -> &'static str {
+        match self {
+            Self::Final => "",
+            Self::Open => "open ",
+            Self::Abstract => "abstract ",
+            Self::Sealed => "sealed ",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassKind {
+    Class,
+    Interface,
+    EnumClass,
+    EnumEntry,
+    AnnotationClass,
+    Object,
+    CompanionObject,
+}
+impl ClassKind {
+    const fn from_flags(flags: i32) -> Self {
+        match (flags >> 6) & 7 {
+            1 => Self::Interface,
+            2 => Self::EnumClass,
+            3 => Self::EnumEntry,
+            4 => Self::AnnotationClass,
+            5 => Self::Object,
+            6 => Self::CompanionObject,
+            _ => Self::Class,
+        }
+    }
+    pub const fn keyword(self) -> &'static str {
+        match self {
+            Self::Class => "class",
+            Self::Interface => "interface",
+            Self::EnumClass => "enum class",
+            Self::EnumEntry => "enum entry",
+            Self::AnnotationClass => "annotation class",
+            Self::Object => "object",
+            Self::CompanionObject => "companion object",
+        }
+    }
+}
+
+/// Name and descriptor of the JVM method or field of a declaration, where they differ from the default
+#[derive(Debug, Clone, Default)]
+pub struct JvmSignature {
+    pub name: Option<String>,
+    pub desc: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct KFunction {
+    pub name: String,
+    pub visibility: Visibility,
+    pub modality: Modality,
+    /// Source modifiers, like `suspend` or `inline`
+    pub modifiers: Vec<&'static str>,
+    pub type_params: Vec<String>,
+    /// Receiver type of an extension function
+    pub receiver: Option<String>,
+    /// Parameters rendered as `name: Type`
+    pub params: Vec<String>,
+    pub returns: String,
+    pub jvm: JvmSignature,
+}
+impl KFunction {
+    pub const fn is_extension(&self) -> bool {
+        self.receiver.is_some()
+    }
+    pub fn is_suspend(&self) -> bool {
+        self.modifiers.contains(&"suspend")
+    }
+    pub fn jvm_name(&self) -> &str {
+        self.jvm.name.as_deref().unwrap_or(&self.name)
+    }
+    /// Source-style declaration
+    pub fn render(&self) -> String {
+        let mut s = String::from(self.visibility.keyword());
+        s.push_str(self.modality.keyword());
+        for m in &self.modifiers {
+            s.push_str(m);
+            s.push(' ');
+        }
+        s.push_str("fun ");
+        if !self.type_params.is_empty() {
+            s += &format!("<{}> ", self.type_params.join(", "));
+        }
+        if let Some(r) = &self.receiver {
+            s += &format!("{r}.");
+        }
+        s += &format!("{}({}): {}", self.name, self.params.join(", "), self.returns);
+        s
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KProperty {
+    pub name: String,
+    pub visibility: Visibility,
+    pub modality: Modality,
+    pub var: bool,
+    /// Source modifiers, like `const` or `lateinit`
+    pub modifiers: Vec<&'static str>,
+    pub receiver: Option<String>,
+    pub type_: String,
+    pub field: Option<JvmSignature>,
+    pub getter: Option<JvmSignature>,
+    pub setter: Option<JvmSignature>,
+    /// Method holding the annotations of the property
+    pub synthetic: Option<JvmSignature>,
+    pub delegate: Option<JvmSignature>,
+}
+impl KProperty {
+    pub const fn is_extension(&self) -> bool {
+        self.receiver.is_some()
+    }
+    fn has_is_prefix(&self) -> bool {
+        self.name.strip_prefix("is").is_some_and(|r| r.chars().next().is_some_and(|c| !c.is_lowercase()))
+    }
+    /// JVM name of the getter
+    pub fn getter_name(&self) -> String {
+        match self.getter.as_ref().and_then(|g| g.name.clone()) {
+            Some(n) => n,
+            None if self.has_is_prefix() => self.name.clone(),
+            None => format!("get{}", capitalize(&self.name)),
+        }
+    }
+    /// JVM name of the setter
+    pub fn setter_name(&self) -> String {
+        match self.setter.as_ref().and_then(|g| g.name.clone()) {
+            Some(n) => n,
+            None if self.has_is_prefix() => format!("set{}", &self.name[2..]),
+            None => format!("set{}", capitalize(&self.name)),
+        }
+    }
+    pub fn render(&self) -> String {
+        let mut s = String::from(self.visibility.keyword());
+        s.push_str(self.modality.keyword());
+        for m in &self.modifiers {
+            s.push_str(m);
+            s.push(' ');
+        }
+        s.push_str(if self.var { "var " } else { "val " });
+        if let Some(r) = &self.receiver {
+            s += &format!("{r}.");
+        }
+        s += &format!("{}: {}", self.name, self.type_);
+        s
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KConstructor {
+    pub visibility: Visibility,
+    pub secondary: bool,
+    pub params: Vec<String>,
+    pub jvm: JvmSignature,
+}
+impl KConstructor {
+    pub fn render(&self) -> String {
+        format!("{}constructor({})", self.visibility.keyword(), self.params.join(", "))
+    }
+}
+
+/// Functions and properties of a class or a file
+#[derive(Debug, Clone, Default)]
+pub struct KMembers {
+    pub functions: Vec<KFunction>,
+    pub properties: Vec<KProperty>,
+}
+
+#[derive(Debug, Clone)]
+pub struct KClass {
+    /// Class id, with `/` separating packages and `.` separating nested classes
+    pub name: String,
+    pub kind: ClassKind,
+    pub visibility: Visibility,
+    pub modality: Modality,
+    /// Source modifiers, like `data`, `value` or `inner`
+    pub modifiers: Vec<&'static str>,
+    pub type_params: Vec<String>,
+    pub supertypes: Vec<String>,
+    pub companion: Option<String>,
+    pub nested: Vec<String>,
+    pub enum_entries: Vec<String>,
+    pub sealed_subclasses: Vec<String>,
+    pub constructors: Vec<KConstructor>,
+    pub members: KMembers,
+}
+impl KClass {
+    pub fn is_data(&self) -> bool {
+        self.modifiers.contains(&"data")
+    }
+    /// Name with dots separating packages
+    pub fn dotted_name(&self) -> String {
+        self.name.replace('/', ".")
+    }
+    pub fn render(&self) -> String {
+        let mut s = String::from(self.visibility.keyword());
+        if !matches!(self.kind, ClassKind::Interface | ClassKind::Object | ClassKind::CompanionObject | ClassKind::EnumClass) {
+            s.push_str(self.modality.keyword());
+        }
+        for m in &self.modifiers {
+            s.push_str(m);
+            s.push(' ');
+        }
+        s += &format!("{} {}", self.kind.keyword(), self.dotted_name());
+        if !self.type_params.is_empty() {
+            s += &format!("<{}>", self.type_params.join(", "));
+        }
+        let implied = if self.kind == ClassKind::AnnotationClass { "Annotation" } else { "Any" };
+        let supers = self.supertypes.iter().filter(|t| *t != implied).cloned().collect::<Vec<_>>();
+        if !supers.is_empty() {
+            s += &format!(" : {}", supers.join(", "));
+        }
+        s
+    }
+}
+
+/// Contents of `@kotlin.Metadata`, by the kind `k`
+#[derive(Debug, Clone)]
+pub enum KotlinMetadata {
+    Class(KClass),
+    /// Top-level declarations of a file (`FileKt` classes)
+    FileFacade(KMembers),
+    /// Compiler-generated class (lambdas, `$WhenMappings`, `$DefaultImpls`), with the function of a lambda
+    SyntheticClass(Option<KFunction>),
+    /// Facade of a file with `@JvmMultifileClass`, listing its parts
+    MultiFileClassFacade(Vec<String>),
+    MultiFileClassPart { facade: String, members: KMembers },
+    Unknown(i32),
+}
+
+/// Context for decoding a class or a file
+struct Decoder<'a> {
+    strings: &'a Strings,
+    types: Vec<&'a [u8]>,
+    first_nullable: usize,
+    /// Type parameter names by id
+    type_params: HashMap<i32, String>,
+}
+impl<'a> Decoder<'a> {
+    fn string(&self, w: &Wire) -> anyhow::Result<String> {
+        self.strings.get(w.index())
+    }
+    fn type_table(&mut self, b: &'a [u8]) -> anyhow::Result<()> {
+        self.types.clear();
+        self.first_nullable = usize::MAX;
+        for f in Proto(b) {
+            match f? {
+                (1, w) => self.types.push(w.bytes()),
+                (2, w) => self.first_nullable = usize::try_from(w.int()).unwrap_or(usize::MAX),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+    fn type_by_id(&self, id: usize, depth: usize) -> anyhow::Result<String> {
+        let t = self.types.get(id).ok_or_else(|| anyhow::anyhow!("Invalid type id {id} in Kotlin metadata"))?;
+        let s = self.render_type(t, depth)?;
+        Ok(if id >= self.first_nullable && !s.ends_with('?') { s + "?" } else { s })
+    }
+    fn type_ref(&self, msg: Option<&[u8]>, id: Option<usize>, depth: usize) -> anyhow::Result<Option<String>> {
+        match (msg, id) {
+            (Some(t), _) => self.render_type(t, depth).map(Some),
+            (None, Some(id)) => self.type_by_id(id, depth).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+    fn render_type(&self, b: &[u8], depth: usize) -> anyhow::Result<String> {
+        if depth > MAX_TYPE_DEPTH {
+            return Ok("…".into());
+        }
+        let mut name = None;
+        let mut args = vec![];
+        let mut nullable = false;
+        let mut abbreviated = (None, None);
+        let mut flexible = false;
+        for f in Proto(b) {
+            match f? {
+                (2, w) => args.push(self.render_arg(w.bytes(), depth)?),
+                (3, w) => nullable = w.int() != 0,
+                (5 | 8, _) => flexible = true,
+                (6 | 12, w) => name = Some(simple_name(&self.string(&w)?).to_string()),
+                (7, w) => name = Some(self.type_params.get(&w.int()).cloned().unwrap_or_else(|| format!("T{}", w.int()))),
+                (9, w) => name = Some(self.string(&w)?),
+                (13, w) => abbreviated.0 = Some(w.bytes()),
+                (14, w) => abbreviated.1 = Some(w.index()),
+                _ => {}
+            }
+        }
+        // Type aliases are shown by their name
+        if let Some(a) = self.type_ref(abbreviated.0, abbreviated.1, depth + 1)? {
+            return Ok(if nullable && !a.ends_with('?') { a + "?" } else { a });
+        }
+        let name = name.unwrap_or_else(|| "?".into());
+        let mut s = match (name.strip_prefix("Function"), args.split_last()) {
+            // Function types, like `(Int) -> String`
+            (Some(n), Some((ret, params))) if n.parse::<usize>().is_ok() => {
+                let t = format!("({}) -> {ret}", params.join(", "));
+                if nullable { format!("({t})") } else { t }
+            }
+            _ if args.is_empty() => name,
+            _ => format!("{name}<{}>", args.join(", ")),
+        };
+        if nullable {
+            s.push('?');
+        }
+        if flexible {
+            s.push('!');
+        }
+        Ok(s)
+    }
+    fn render_arg(&self, b: &[u8], depth: usize) -> anyhow::Result<String> {
+        let mut projection = 2;
+        let (mut t, mut id) = (None, None);
+        for f in Proto(b) {
+            match f? {
+                (1, w) => projection = w.int(),
+                (2, w) => t = Some(w.bytes()),
+                (3, w) => id = Some(w.index()),
+                _ => {}
+            }
+        }
+        if projection == 3 {
+            return Ok("*".into());
+        }
+        let t = self.type_ref(t, id, depth + 1)?.unwrap_or_else(|| "?".into());
+        Ok(match projection {
+            0 => format!("in {t}"),
+            1 => format!("out {t}"),
+            _ => t,
+        })
+    }
+    /// Reads type parameters, making their names known to the types following them
+    fn type_param(&mut self, b: &[u8]) -> anyhow::Result<String> {
+        let (mut id, mut name, mut reified, mut variance) = (0, String::new(), false, 2);
+        let mut bounds = vec![];
+        for f in Proto(b) {
+            match f? {
+                (1, w) => id = w.int(),
+                (2, w) => name = self.string(&w)?,
+                (3, w) => reified = w.int() != 0,
+                (4, w) => variance = w.int(),
+                (5 | 6, w) => bounds.push(w),
+                _ => {}
+            }
+        }
+        self.type_params.insert(id, name.clone());
+        let mut s = String::new();
+        if reified {
+            s.push_str("reified ");
+        }
+        match variance {
+            0 => s.push_str("in "),
+            1 => s.push_str("out "),
+            _ => {}
+        }
+        s.push_str(&name);
+        for (i, w) in bounds.iter().enumerate() {
+            let bound = match w {
+                Wire::Bytes(t) => self.render_type(t, 0)?,
+                Wire::Int(_) => self.type_by_id(w.index(), 0)?,
+            };
+            if bound != "Any?" {
+                s += &format!("{}{bound}", if i == 0 { " : " } else { ", " });
+            }
+        }
+        Ok(s)
+    }
+    fn value_param(&self, b: &[u8]) -> anyhow::Result<String> {
+        let (mut name, mut t, mut id, mut vt, mut vid) = (String::new(), None, None, None, None);
+        for f in Proto(b) {
+            match f? {
+                (2, w) => name = self.string(&w)?,
+                (3, w) => t = Some(w.bytes()),
+                (4, w) => vt = Some(w.bytes()),
+                (5, w) => id = Some(w.index()),
+                (6, w) => vid = Some(w.index()),
+                _ => {}
+            }
+        }
+        Ok(match self.type_ref(vt, vid, 0)? {
+            Some(v) => format!("vararg {name}: {v}"),
+            None => format!("{name}: {}", self.type_ref(t, id, 0)?.unwrap_or_else(|| "?".into())),
+        })
+    }
+    fn jvm_signature(&self, b: &[u8]) -> anyhow::Result<JvmSignature> {
+        let mut sig = JvmSignature::default();
+        for f in Proto(b) {
+            match f? {
+                (1, w) => sig.name = Some(self.string(&w)?),
+                (2, w) => sig.desc = Some(self.string(&w)?),
+                _ => {}
+            }
+        }
+        Ok(sig)
+    }
+    fn function(&mut self, b: &'a [u8]) -> anyhow::Result<KFunction> {
+        let outer_params = self.type_params.clone();
+        let outer_types = (self.types.clone(), self.first_nullable);
+        // A function may have its own type table, which must be read before its types
+        for f in Proto(b) {
+            if let (30, w) = f? {
+                self.type_table(w.bytes())?;
+            }
+        }
+        let mut flags = 6;
+        let mut name = String::new();
+        let mut type_params = vec![];
+        let (mut ret, mut ret_id, mut recv, mut recv_id) = (None, None, None, None);
+        let mut params = vec![];
+        let mut jvm = JvmSignature::default();
+        for f in Proto(b) {
+            match f? {
+                (9, w) => flags = w.int(),
+                (2, w) => name = self.string(&w)?,
+                (3, w) => ret = Some(w.bytes()),
+                (7, w) => ret_id = Some(w.index()),
+                (4, w) => type_params.push(self.type_param(w.bytes())?),
+                (5, w) => recv = Some(w.bytes()),
+                (8, w) => recv_id = Some(w.index()),
+                (6, w) => params.push(w.bytes()),
+                (100, w) => jvm = self.jvm_signature(w.bytes())?,
+                _ => {}
+            }
+        }
+        let modifiers = [(8, "operator"), (9, "infix"), (10, "inline"), (11, "tailrec"), (12, "external"), (13, "suspend")]
+            .into_iter()
+            .filter(|(bit, _)| flags & (1 << bit) != 0)
+            .map(|(_, m)| m)
+            .collect();
+        let f = KFunction {
+            name,
+            visibility: Visibility::from_flags(flags),
+            modality: Modality::from_flags(flags),
+            modifiers,
+            type_params,
+            receiver: self.type_ref(recv, recv_id, 0)?,
+            params: params.into_iter().map(|p| self.value_param(p)).collect::<anyhow::Result<_>>()?,
+            returns: self.type_ref(ret, ret_id, 0)?.unwrap_or_else(|| "Unit".into()),
+            jvm,
+        };
+        self.type_params = outer_params;
+        (self.types, self.first_nullable) = outer_types;
+        Ok(f)
+    }
+    fn property(&mut self, b: &[u8]) -> anyhow::Result<KProperty> {
+        let outer_params = self.type_params.clone();
+        let mut flags = 518;
+        let mut name = String::new();
+        let (mut ret, mut ret_id, mut recv, mut recv_id) = (None, None, None, None);
+        let mut sigs = None;
+        for f in Proto(b) {
+            match f? {
+                (11, w) => flags = w.int(),
+                (2, w) => name = self.string(&w)?,
+                (3, w) => ret = Some(w.bytes()),
+                (9, w) => ret_id = Some(w.index()),
+                (4, w) => {
+                    self.type_param(w.bytes())?;
+                }
+                (5, w) => recv = Some(w.bytes()),
+                (10, w) => recv_id = Some(w.index()),
+                (100, w) => sigs = Some(w.bytes()),
+                _ => {}
+            }
+        }
+        let mut p = KProperty {
+            name,
+            visibility: Visibility::from_flags(flags),
+            modality: Modality::from_flags(flags),
+            var: flags & (1 << 8) != 0,
+            modifiers: [(11, "const"), (12, "lateinit"), (14, "external")]
+                .into_iter()
+                .filter(|(bit, _)| flags & (1 << bit) != 0)
+                .map(|(_, m)| m)
+                .collect(),
+            receiver: self.type_ref(recv, recv_id, 0)?,
+            type_: self.type_ref(ret, ret_id, 0)?.unwrap_or_else(|| "?".into()),
+            field: None,
+            getter: None,
+            setter: None,
+            synthetic: None,
+            delegate: None,
+        };
+        if flags & (1 << 15) != 0 {
+            p.type_ += " by …";
+        }
+        for f in Proto(sigs.unwrap_or_default()) {
+            match f? {
+                (1, w) => p.field = Some(self.jvm_signature(w.bytes())?),
+                (2, w) => p.synthetic = Some(self.jvm_signature(w.bytes())?),
+                (3, w) => p.getter = Some(self.jvm_signature(w.bytes())?),
+                (4, w) => p.setter = Some(self.jvm_signature(w.bytes())?),
+                (5, w) => p.delegate = Some(self.jvm_signature(w.bytes())?),
+                _ => {}
+            }
+        }
+        // Accessors without signatures exist with default names
+        if flags & (1 << 9) != 0 && p.getter.is_none() {
+            p.getter = Some(JvmSignature::default());
+        }
+        if flags & (1 << 10) != 0 && p.setter.is_none() {
+            p.setter = Some(JvmSignature::default());
+        }
+        self.type_params = outer_params;
+        Ok(p)
+    }
+    fn constructor(&self, b: &[u8]) -> anyhow::Result<KConstructor> {
+        let mut flags = 6;
+        let mut params = vec![];
+        let mut jvm = JvmSignature::default();
+        for f in Proto(b) {
+            match f? {
+                (1, w) => flags = w.int(),
+                (2, w) => params.push(self.value_param(w.bytes())?),
+                (100, w) => jvm = self.jvm_signature(w.bytes())?,
+                _ => {}
+            }
+        }
+        Ok(KConstructor {
+            visibility: Visibility::from_flags(flags),
+            secondary: flags & (1 << 4) != 0,
+            params,
+            jvm,
+        })
+    }
+    /// Reads functions and properties of a `Class` or `Package` message, given their field numbers
+    fn members(&mut self, b: &'a [u8], fun_field: u32, prop_field: u32) -> anyhow::Result<KMembers> {
+        let mut m = KMembers::default();
+        for f in Proto(b) {
+            match f? {
+                (n, w) if n == fun_field => m.functions.push(self.function(w.bytes())?),
+                (n, w) if n == prop_field => m.properties.push(self.property(w.bytes())?),
+                _ => {}
+            }
+        }
+        Ok(m)
+    }
+    fn read_type_table(&mut self, b: &'a [u8]) -> anyhow::Result<()> {
+        for f in Proto(b) {
+            if let (30, w) = f? {
+                self.type_table(w.bytes())?;
+            }
+        }
+        Ok(())
+    }
+    fn class(&mut self, b: &'a [u8]) -> anyhow::Result<KClass> {
+        self.read_type_table(b)?;
+        let mut flags = 6;
+        let mut c = KClass {
+            name: String::new(),
+            kind: ClassKind::Class,
+            visibility: Visibility::Public,
+            modality: Modality::Final,
+            modifiers: vec![],
+            type_params: vec![],
+            supertypes: vec![],
+            companion: None,
+            nested: vec![],
+            enum_entries: vec![],
+            sealed_subclasses: vec![],
+            constructors: vec![],
+            members: KMembers::default(),
+        };
+        let mut ids = vec![];
+        let mut supers = vec![];
+        for f in Proto(b) {
+            match f? {
+                (1, w) => flags = w.int(),
+                (3, w) => c.name = self.string(&w)?,
+                (4, w) => c.companion = Some(self.string(&w)?),
+                (5, w) => c.type_params.push(self.type_param(w.bytes())?),
+                (6, w) => supers.push(w.bytes()),
+                (2, w) => w.ints(&mut ids)?,
+                (7, w) => {
+                    let mut v = vec![];
+                    w.ints(&mut v)?;
+                    for i in v {
+                        c.nested.push(self.strings.get(usize::try_from(i)?)?);
+                    }
+                }
+                (8, w) => c.constructors.push(self.constructor(w.bytes())?),
+                (13, w) => {
+                    for f in Proto(w.bytes()) {
+                        if let (1, w) = f? {
+                            c.enum_entries.push(self.string(&w)?);
+                        }
+                    }
+                }
+                (16, w) => {
+                    let mut v = vec![];
+                    w.ints(&mut v)?;
+                    for i in v {
+                        c.sealed_subclasses.push(self.strings.get(usize::try_from(i)?)?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        for t in supers {
+            c.supertypes.push(self.render_type(t, 0)?);
+        }
+        for id in ids {
+            c.supertypes.push(self.type_by_id(usize::try_from(id)?, 0)?);
+        }
+        c.kind = ClassKind::from_flags(flags);
+        c.visibility = Visibility::from_flags(flags);
+        c.modality = Modality::from_flags(flags);
+        c.modifiers = [(9, "inner"), (10, "data"), (11, "external"), (12, "expect"), (13, "value"), (14, "fun")]
+            .into_iter()
+            .filter(|(bit, _)| flags & (1 << bit) != 0)
+            .map(|(_, m)| m)
+            .collect();
+        c.members = self.members(b, 9, 10)?;
+        Ok(c)
+    }
+    fn package(&mut self, b: &'a [u8]) -> anyhow::Result<KMembers> {
+        self.read_type_table(b)?;
+        self.members(b, 3, 4)
+    }
+}
+
+impl KotlinMetadata {
+    /// Decodes metadata from the kind `k`, the strings of `d1` and `d2`, and `xs`.
+    pub fn decode(kind: i32, d1: &[String], d2: Vec<String>, xs: Option<String>) -> anyhow::Result<Self> {
+        if kind == 4 {
+            return Ok(Self::MultiFileClassFacade(d1.to_vec()));
+        }
+        if !matches!(kind, 1..=5) {
+            return Ok(Self::Unknown(kind));
+        }
+        let bytes = decode_bytes(d1)?;
+        if bytes.is_empty() {
+            return Ok(match kind {
+                3 => Self::SyntheticClass(None),
+                2 => Self::FileFacade(KMembers::default()),
+                _ => anyhow::bail!("Missing Kotlin metadata"),
+            });
+        }
+        let mut b = &bytes[..];
+        let strings = Strings::new(delimited(&mut b)?, d2)?;
+        let mut dec = Decoder { strings: &strings, types: vec![], first_nullable: usize::MAX, type_params: HashMap::new() };
+        Ok(match kind {
+            1 => Self::Class(dec.class(b)?),
+            2 => Self::FileFacade(dec.package(b)?),
+            3 => Self::SyntheticClass(Some(dec.function(b)?)),
+            _ => Self::MultiFileClassPart { facade: xs.unwrap_or_default(), members: dec.package(b)? },
+        })
+    }
+    /// Decodes a `@kotlin.Metadata` annotation, returning `None` for other annotations.
+    pub fn from_annotation(an: &Data<Annotation>) -> anyhow::Result<Option<Self>> {
+        if &*an.type_name()? != METADATA_DESC {
+            return Ok(None);
+        }
+        let strings = |v: Option<Vec<&JStr>>| v.unwrap_or_default().into_iter().map(|s| s.to_str_lossy().into_owned()).collect::<Vec<_>>();
+        let (mut kind, mut d1, mut d2, mut xs) = (1, vec![], vec![], None);
+        for e in an.elems() {
+            let e = e?;
+            match &**e.name()? {
+                b"k" => if let Some(JVal::Int(k)) = e.const_value()? {
+                    kind = k;
+                },
+                b"d1" => d1 = strings(e.str_array()?),
+                b"d2" => d2 = strings(e.str_array()?),
+                b"xs" => xs = e.str_value()?.map(|s| s.to_str_lossy().into_owned()),
+                _ => {}
+            }
+        }
+        Self::decode(kind, &d1, d2, xs).map(Some)
+    }
+    /// Functions and properties declared in the class or file
+    pub const fn members(&self) -> Option<&KMembers> {
+        match self {
+            Self::Class(c) => Some(&c.members),
+            Self::FileFacade(m) | Self::MultiFileClassPart { members: m, .. } => Some(m),
+            _ => None,
+        }
+    }
+    /// Short description of the class in Kotlin terms
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Class(c) => c.render(),
+            Self::FileFacade(_) => "file facade".into(),
+            Self::SyntheticClass(Some(f)) => format!("lambda {}", f.render()),
+            Self::SyntheticClass(None) => "synthetic class".into(),
+            Self::MultiFileClassFacade(parts) => format!("multi-file class facade of {} parts", parts.len()),
+            Self::MultiFileClassPart { facade, .. } => format!("multi-file class part of {}", facade.replace('/', ".")),
+            Self::Unknown(k) => format!("unknown Kotlin class kind {k}"),
+        }
+    }
+    /// Source declaration of a JVM method or field of the class.
+    /// Without a descriptor in the metadata, methods are matched by their number of parameters,
+    /// so overloads with the same number of parameters are left unresolved.
+    pub fn declaration_of(&self, name: &str, desc: &str) -> Option<String> {
+        let params = jvm_param_count(desc);
+        if name == "<init>" {
+            let Self::Class(c) = self else { return None };
+            return unique(c.constructors.iter(), |k| k.jvm.desc.as_deref(), |k| Some(k.params.len()) == params, desc)
+                .map(KConstructor::render);
+        }
+        let members = self.members()?;
+        let is_method = desc.starts_with('(');
+        if is_method {
+            let f = unique(
+                members.functions.iter().filter(|f| f.jvm_name() == name),
+                |f| f.jvm.desc.as_deref(),
+                |f| Some(f.params.len() + usize::from(f.is_extension()) + usize::from(f.is_suspend())) == params,
+                desc,
+            );
+            if let Some(f) = f {
+                return Some(f.render());
+            }
+        }
+        members.properties.iter().find_map(|p| {
+            let matches = |s: &Option<JvmSignature>, default: &str| {
+                s.as_ref().is_some_and(|s| s.name.as_deref().unwrap_or(default) == name && s.desc.as_deref().is_none_or(|d| d == desc))
+            };
+            let what = if !is_method {
+                matches(&p.field, &p.name).then_some("field")
+                    .or_else(|| matches(&p.delegate, &format!("{}$delegate", p.name)).then_some("delegate"))
+            } else if matches(&p.getter, &p.getter_name()) {
+                Some("getter")
+            } else if matches(&p.setter, &p.setter_name()) {
+                Some("setter")
+            } else if matches(&p.synthetic, &format!("{}$annotations", p.name)) {
+                Some("annotations")
+            } else {
+                None
+            }?;
+            Some(format!("{} ({what})", p.render()))
+        })
+    }
+}
+
+/// The declaration with the descriptor `desc`, or the only one of the declarations without a descriptor matching `fits`
+fn unique<'a, T>(it: impl Iterator<Item = &'a T>, desc_of: impl Fn(&T) -> Option<&str>, fits: impl Fn(&T) -> bool, desc: &str) -> Option<&'a T> {
+    let mut found = None;
+    let mut count = 0;
+    for x in it {
+        match desc_of(x) {
+            Some(d) if d == desc => return Some(x),
+            Some(_) => {}
+            None if fits(x) => {
+                found = Some(x);
+                count += 1;
+            }
+            None => {}
+        }
+    }
+    found.filter(|_| count == 1)
+}
+
+/// Number of parameters of a method descriptor
+fn jvm_param_count(desc: &str) -> Option<usize> {
+    MethodSig::from_desc(desc).ok().map(|m| m.params.len())
+}
@@ -1,28 +1,42 @@
 // PURELY EXPERIMENTAL! DO NOT USE IN PRODUCTION!
 #![allow(dead_code)]
 
-use std::{io::{self, Read}, marker::PhantomData, ops::Deref, sync::Arc};
+use std::{io::{self, Read}, marker::PhantomData, ops::Deref};
 use byteorder::{ReadBytesExt, BE};
-use bytes::{Buf, BufMut, Bytes};
+use bytes::{BufMut, Bytes};
 use iter::Attrs;
 use self::{idx::{ClassInfo, Index, Utf8}, jtype::MemberType, pool::{ClassPool, PoolItem}};
 
 pub mod attr;
+pub mod code;
+pub mod flags;
 pub mod idx;
 pub mod jtype;
+pub mod kotlin;
+pub mod mutf8;
 pub mod pool;
 pub mod iter;
 pub mod read;
+pub mod readbytes;
 pub mod readseek;
+pub mod verify;
+pub mod write;
 
 pub use read::JClassReader;
+pub use readbytes::JClassBytesReader;
 
 #[derive(Clone)]
 #[repr(transparent)]
-pub struct JStr(Arc<[u8]>);
+pub struct JStr(Bytes);
 impl From<Box<[u8]>> for JStr {
     fn from(value: Box<[u8]>) -> Self {
-        Self(Arc::from(value))
+        Self(Bytes::from(value))
+    }
+}
+impl From<Bytes> for JStr {
+    /// Shares the buffer, so that strings sliced from a class file need no copy
+    fn from(value: Bytes) -> Self {
+        Self(value)
     }
 }
 impl Deref for JStr {
@@ -31,14 +45,29 @@ impl Deref for JStr {
         &self.0
     }
 }
+impl From<&str> for JStr {
+    fn from(value: &str) -> Self {
+        Self(Bytes::from(mutf8::encode(value).into_owned()))
+    }
+}
+impl JStr {
+    /// Decodes the modified UTF-8 contents, failing if they are invalid.
+    pub fn to_str(&self) -> anyhow::Result<std::borrow::Cow<'_, str>> {
+        mutf8::decode(&self.0)
+    }
+    /// Decodes the modified UTF-8 contents, replacing invalid sequences with U+FFFD.
+    pub fn to_str_lossy(&self) -> std::borrow::Cow<'_, str> {
+        mutf8::decode_lossy(&self.0)
+    }
+}
 impl std::fmt::Debug for JStr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", String::from_utf8_lossy(&self.0))
+        write!(f, "{:?}", self.to_str_lossy())
     }
 }
 impl std::fmt::Display for JStr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", String::from_utf8_lossy(&self.0))
+        f.write_str(&self.to_str_lossy())
     }
 }
 
@@ -50,6 +79,14 @@ fn read_magic<R: Read>(r: &mut R) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Reads only the header of a class file, returning `(major, minor)` version.
+pub fn read_version<R: Read>(r: &mut R) -> anyhow::Result<(u16, u16)> {
+    read_magic(r)?;
+    let minor = r.read_u16::<BE>()?;
+    let major = r.read_u16::<BE>()?;
+    Ok((major, minor))
+}
+
 fn skip_member_info<R: Read>(r: &mut R) -> io::Result<()> {
     let count = r.read_u16::<BE>()?;
     for _ in 0..count {
@@ -69,8 +106,8 @@ fn skip_attr_info<R: Read>(r: &mut R) -> io::Result<()> {
 }
 
 fn skip_exact<R: Read>(r: &mut R, n: usize) -> io::Result<()> {
-    let x = r.bytes().take(n).count();
-    if x != n {
+    let x = io::copy(&mut r.take(n as u64), &mut io::sink())?;
+    if x != n as u64 {
         return Err(io::Error::other("Invalid length"));
     }
     Ok(())
@@ -96,28 +133,23 @@ fn read_attr_info<R: Read>(r: &mut R, bmut: &mut bytes::BytesMut) -> anyhow::Res
         bmut.put_slice(&n);
         let len = r.read_u32::<BE>()?;
         bmut.put_u32(len);
-        std::io::copy(&mut r.take(len as u64), &mut bmut.writer())?;
+        if std::io::copy(&mut r.take(len as u64), &mut bmut.writer())? != u64::from(len) {
+            anyhow::bail!("Invalid attribute length");
+        }
     }
     Ok(())
 }
 
-fn len_member_info(b: &mut Bytes, len: &mut usize) {
-    let count = b.get_u16();
-    *len += count as usize * 6;
+/// Length of a single `field_info` or `method_info` structure, checked against the end of `b`
+fn member_info_len(b: &[u8]) -> anyhow::Result<usize> {
+    let at = |i: usize, n: usize| b.get(i..i + n).ok_or_else(|| anyhow::anyhow!("Unexpected end of member info"));
+    let count = u16::from_be_bytes(at(6, 2)?.try_into()?);
+    let mut len = 8;
     for _ in 0..count {
-        b.advance(6);
-        len_attr_info(b, len);
-    }
-}
-fn len_attr_info(b: &mut Bytes, len: &mut usize) {
-    let count = b.get_u16();
-    *len += count as usize * 2;
-    for _ in 0..count {
-        b.advance(2);
-        let l = b.get_u32() as usize;
-        *len += l;
-        b.advance(l);
+        len += 6 + u32::from_be_bytes(at(len + 2, 4)?.try_into()?) as usize;
     }
+    at(0, len)?;
+    Ok(len)
 }
 
 #[derive(Clone)]
@@ -131,6 +163,10 @@ pub struct MemberInfo<T: MemberType> {
     _t: PhantomData<fn() -> T>
 }
 impl<T: MemberType> MemberInfo<T> {
+    #[inline]
+    pub const fn access_flags(&self) -> u16 {
+        self.flags
+    }
     pub fn name(&self) -> anyhow::Result<&JStr> {
         self.pool.get(self.name_idx)
     }
@@ -152,8 +188,23 @@ impl<T> AttrInfo<T> {
     pub fn name(&self) -> anyhow::Result<&JStr> {
         self.pool.get(self.name_idx)
     }
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.b
+    }
+}
+impl<T: attr::AttrMatch> AttrInfo<T> {
+    /// Attribute kind, or [`attr::JAttr::Unsupported`] for unknown attributes or those invalid in this location
+    pub fn kind(&self) -> anyhow::Result<attr::JAttr<T>> {
+        Ok(attr::JAttr::try_from(&**self.name()?).unwrap_or(attr::JAttr::Unsupported(PhantomData)))
+    }
+    /// Parses the attribute using the key from [`Self::kind`].
+    pub fn read<K: attr::UseAttr>(&self, _key: &attr::AttrKey<K>) -> anyhow::Result<K::Out> {
+        K::parse(self.b.clone(), &self.pool)
+    }
 }
 
+#[derive(Clone, Copy)]
 pub struct ClassData {
     access_flags: u16,
     class_ref: Index<ClassInfo>,
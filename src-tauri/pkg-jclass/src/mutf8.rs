@@ -0,0 +1,86 @@
+//! Modified UTF-8, the encoding of strings in class files.
+//!
+//! It differs from UTF-8 in two ways: NUL is written as `C0 80`, and characters
+//! outside the BMP are written as a surrogate pair of two 3-byte sequences (as in CESU-8).
+
+use std::borrow::Cow;
+
+/// Decodes `b`, failing on invalid sequences and unpaired surrogates.
+pub fn decode(b: &[u8]) -> anyhow::Result<Cow<'_, str>> {
+    decode_with(b, false)
+}
+
+/// Decodes `b`, replacing invalid sequences and unpaired surrogates with U+FFFD.
+pub fn decode_lossy(b: &[u8]) -> Cow<'_, str> {
+    decode_with(b, true).unwrap_or_default()
+}
+
+/// Checks that `b` is well-formed, allowing unpaired surrogates as the JVM does.
+pub fn check(b: &[u8]) -> anyhow::Result<()> {
+    units(b, false).map(drop)
+}
+
+fn decode_with(b: &[u8], lossy: bool) -> anyhow::Result<Cow<'_, str>> {
+    // Without NUL and 4-byte sequences, valid UTF-8 is also valid modified UTF-8 of the same text
+    if !b.iter().any(|&c| c == 0 || c >= 0xF0) {
+        if let Ok(s) = std::str::from_utf8(b) {
+            return Ok(Cow::Borrowed(s));
+        }
+    }
+    let units = units(b, lossy)?;
+    if lossy {
+        return Ok(Cow::Owned(String::from_utf16_lossy(&units)));
+    }
+    String::from_utf16(&units).map(Cow::Owned).map_err(|_| anyhow::anyhow!("Unpaired surrogate in modified UTF-8"))
+}
+
+/// Decodes the UTF-16 code units of `b`
+fn units(b: &[u8], lossy: bool) -> anyhow::Result<Vec<u16>> {
+    let mut units = Vec::with_capacity(b.len());
+    let mut i = 0;
+    while i < b.len() {
+        let x = b[i];
+        let cont = |n: usize| b.get(i + n).map(|&c| u16::from(c)).filter(|c| c & 0xC0 == 0x80);
+        let seq = match x {
+            0x01..=0x7F => Some((u16::from(x), 1)),
+            // NUL is the only allowed overlong sequence
+            0xC0..=0xDF => cont(1).map(|y| (u16::from(x & 0x1F) << 6 | y & 0x3F, 2)).filter(|&(u, _)| u == 0 || u >= 0x80),
+            0xE0..=0xEF => cont(1).zip(cont(2))
+                .map(|(y, z)| (u16::from(x & 0x0F) << 12 | (y & 0x3F) << 6 | z & 0x3F, 3))
+                .filter(|&(u, _)| u >= 0x800),
+            _ => None,
+        };
+        match seq {
+            Some((u, n)) => {
+                units.push(u);
+                i += n;
+            }
+            None if lossy => {
+                units.push(0xFFFD);
+                i += 1;
+            }
+            None => anyhow::bail!("Invalid modified UTF-8 at byte {i}"),
+        }
+    }
+    Ok(units)
+}
+
+/// Encodes `s`, borrowing it when it contains neither NUL nor characters outside the BMP.
+pub fn encode(s: &str) -> Cow<'_, [u8]> {
+    if !s.bytes().any(|c| c == 0 || c >= 0xF0) {
+        return Cow::Borrowed(s.as_bytes());
+    }
+    let mut out = Vec::with_capacity(s.len() + 2);
+    for c in s.chars() {
+        match c {
+            '\0' => out.extend([0xC0, 0x80]),
+            '\u{10000}'.. => {
+                for &u in c.encode_utf16(&mut [0; 2]).iter() {
+                    out.extend([0xE0 | (u >> 12) as u8, 0x80 | (u >> 6 & 0x3F) as u8, 0x80 | (u & 0x3F) as u8]);
+                }
+            }
+            _ => out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    Cow::Owned(out)
+}
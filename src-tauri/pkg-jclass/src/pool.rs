@@ -1,10 +1,11 @@
-use std::{ops::Deref, sync::Arc};
+use std::{io::Read, sync::{Arc, OnceLock}};
 
 use byteorder::{BE, ReadBytesExt};
+use bytes::{BufMut, Bytes};
 
-use super::{idx::{AnyMethodRef, ClassInfo, FieldRef, Index, InterfaceMethodRef, MethodRef, NameAndType, UseIndex, Utf8}, JStr};
+use super::{idx::{AnyMethodRef, ClassInfo, FieldRef, Index, InterfaceMethodRef, MethodRef, NameAndType, UseIndex, Utf8}, mutf8, JStr};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum PoolItem {
     None,
     Utf8(JStr),
@@ -27,14 +28,33 @@ pub enum PoolItem {
     Reserved
 }
 impl PoolItem {
-    pub fn read_from(tag: u8, major: u16, r: &mut impl std::io::Read) -> anyhow::Result<Self> {
+    /// Name of the constant pool tag (as printed by `javap`)
+    pub const fn tag_name(&self) -> &'static str {
+        match self {
+            Self::None | Self::Reserved => "",
+            Self::Utf8(_) => "Utf8",
+            Self::Int(_) => "Integer",
+            Self::Float(_) => "Float",
+            Self::Long(_) => "Long",
+            Self::Double(_) => "Double",
+            Self::Class(_) => "Class",
+            Self::String(_) => "String",
+            Self::RefField(..) => "Fieldref",
+            Self::RefMethod(..) => "Methodref",
+            Self::RefInterfaceMethod(..) => "InterfaceMethodref",
+            Self::NameAndType(..) => "NameAndType",
+            Self::MethodHandle(_) => "MethodHandle",
+            Self::MethodType(_) => "MethodType",
+            Self::Dynamic(..) => "Dynamic",
+            Self::InvokeDynamic(..) => "InvokeDynamic",
+            Self::Module(_) => "Module",
+            Self::Package(_) => "Package",
+        }
+    }
+    /// Reads an item other than `Utf8`, whose contents [`ClassPool`] and [`LazyPool`] slice from a shared buffer.
+    pub fn read_from(tag: u8, major: u16, r: &mut impl Read) -> anyhow::Result<Self> {
         Ok(match tag {
-            1 => {
-                let len = r.read_u16::<BE>()? as usize;
-                let mut b = vec![0; len];
-                r.read_exact(&mut b)?;
-                Self::Utf8(JStr::from(b.into_boxed_slice()))
-            }
+            1 => anyhow::bail!("Utf8 entries are sliced from the pool buffer"),
             3 => Self::Int(r.read_i32::<BE>()?),
             4 => Self::Float(r.read_f32::<BE>()?),
             5 => Self::Long(r.read_i64::<BE>()?),
@@ -82,10 +102,91 @@ impl PoolItem {
             n => anyhow::bail!("Invalid tag: {}", n),
         })
     }
+    /// Writes the item in the class file format, nothing for [`Self::None`] and [`Self::Reserved`].
+    pub fn write_to(&self, out: &mut impl BufMut) -> anyhow::Result<()> {
+        let mut refs = |tag: u8, a: u16, b: Option<u16>| {
+            out.put_u8(tag);
+            out.put_u16(a);
+            if let Some(b) = b {
+                out.put_u16(b);
+            }
+        };
+        match self {
+            Self::None | Self::Reserved => {}
+            Self::Utf8(s) => {
+                out.put_u8(1);
+                out.put_u16(u16::try_from(s.len())?);
+                out.put_slice(s);
+            }
+            Self::Int(x) => {
+                out.put_u8(3);
+                out.put_i32(*x);
+            }
+            Self::Float(x) => {
+                out.put_u8(4);
+                out.put_f32(*x);
+            }
+            Self::Long(x) => {
+                out.put_u8(5);
+                out.put_i64(*x);
+            }
+            Self::Double(x) => {
+                out.put_u8(6);
+                out.put_f64(*x);
+            }
+            Self::Class(i) => refs(7, i.get(), None),
+            Self::String(i) => refs(8, i.get(), None),
+            Self::RefField(c, n) => refs(9, c.get(), Some(n.get())),
+            Self::RefMethod(c, n) => refs(10, c.get(), Some(n.get())),
+            Self::RefInterfaceMethod(c, n) => refs(11, c.get(), Some(n.get())),
+            Self::NameAndType(n, d) => refs(12, n.get(), Some(d.get())),
+            Self::MethodHandle(rk) => {
+                out.put_u8(15);
+                out.put_u8(rk.kind());
+                out.put_u16(rk.parts().1);
+            }
+            Self::MethodType(i) => refs(16, i.get(), None),
+            Self::Dynamic(b, n) => refs(17, *b, Some(n.get())),
+            Self::InvokeDynamic(b, n) => refs(18, *b, Some(n.get())),
+            Self::Module(i) => refs(19, i.get(), None),
+            Self::Package(i) => refs(20, i.get(), None),
+        }
+        Ok(())
+    }
+    /// Pool indices referenced by the item
+    pub fn refs(&self) -> impl Iterator<Item = u16> {
+        let (a, b) = match self {
+            Self::Class(i) | Self::String(i) | Self::MethodType(i) | Self::Module(i) | Self::Package(i) => (Some(i.get()), None),
+            Self::RefField(c, n) | Self::RefMethod(c, n) | Self::RefInterfaceMethod(c, n) => (Some(c.get()), Some(n.get())),
+            Self::NameAndType(n, d) => (Some(n.get()), Some(d.get())),
+            Self::MethodHandle(rk) => (Some(rk.parts().1), None),
+            Self::Dynamic(_, n) | Self::InvokeDynamic(_, n) => (Some(n.get()), None),
+            _ => (None, None),
+        };
+        a.into_iter().chain(b)
+    }
+    /// Copy of the item with its pool indices replaced by `f`
+    pub fn map_refs(&self, f: impl Fn(u16) -> u16) -> anyhow::Result<Self> {
+        Ok(match self {
+            Self::Class(x) => Self::Class(x.map(&f)?),
+            Self::String(x) => Self::String(x.map(&f)?),
+            Self::MethodType(x) => Self::MethodType(x.map(&f)?),
+            Self::Module(x) => Self::Module(x.map(&f)?),
+            Self::Package(x) => Self::Package(x.map(&f)?),
+            Self::RefField(c, n) => Self::RefField(c.map(&f)?, n.map(&f)?),
+            Self::RefMethod(c, n) => Self::RefMethod(c.map(&f)?, n.map(&f)?),
+            Self::RefInterfaceMethod(c, n) => Self::RefInterfaceMethod(c.map(&f)?, n.map(&f)?),
+            Self::NameAndType(n, d) => Self::NameAndType(n.map(&f)?, d.map(&f)?),
+            Self::MethodHandle(rk) => Self::MethodHandle((rk.kind(), f(rk.parts().1)).try_into()?),
+            Self::Dynamic(b, n) => Self::Dynamic(*b, n.map(&f)?),
+            Self::InvokeDynamic(b, n) => Self::InvokeDynamic(*b, n.map(&f)?),
+            x => x.clone(),
+        })
+    }
 }
 
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum RefKind {
     GetField(Index<FieldRef>),
     GetStatic(Index<FieldRef>),
@@ -97,6 +198,36 @@ pub enum RefKind {
     NewInvokeSpecial(Index<MethodRef>),
     InvokeInterface(Index<InterfaceMethodRef>),
 }
+impl RefKind {
+    /// Name of the reference kind and index of the referenced member
+    pub const fn parts(&self) -> (&'static str, u16) {
+        match self {
+            Self::GetField(i) => ("REF_getField", i.get()),
+            Self::GetStatic(i) => ("REF_getStatic", i.get()),
+            Self::PutField(i) => ("REF_putField", i.get()),
+            Self::PutStatic(i) => ("REF_putStatic", i.get()),
+            Self::InvokeVirtual(i) => ("REF_invokeVirtual", i.get()),
+            Self::InvokeStatic(i) => ("REF_invokeStatic", i.get()),
+            Self::InvokeSpecial(i) => ("REF_invokeSpecial", i.get()),
+            Self::NewInvokeSpecial(i) => ("REF_newInvokeSpecial", i.get()),
+            Self::InvokeInterface(i) => ("REF_invokeInterface", i.get()),
+        }
+    }
+    /// Reference kind as stored in the class file (1 to 9)
+    pub const fn kind(&self) -> u8 {
+        match self {
+            Self::GetField(_) => 1,
+            Self::GetStatic(_) => 2,
+            Self::PutField(_) => 3,
+            Self::PutStatic(_) => 4,
+            Self::InvokeVirtual(_) => 5,
+            Self::InvokeStatic(_) => 6,
+            Self::InvokeSpecial(_) => 7,
+            Self::NewInvokeSpecial(_) => 8,
+            Self::InvokeInterface(_) => 9,
+        }
+    }
+}
 impl TryFrom<(u8, u16)> for RefKind {
     type Error = anyhow::Error;
     fn try_from((kind, index): (u8, u16)) -> Result<Self, Self::Error> {
@@ -115,34 +246,320 @@ impl TryFrom<(u8, u16)> for RefKind {
     }
 }
 
+/// Constant pool items, either all decoded up front or each decoded from a [`LazyPool`] when first accessed
+#[derive(Clone)]
+pub struct ClassPool(Items);
 #[derive(Clone)]
-pub struct ClassPool(Arc<[PoolItem]>);
+enum Items {
+    Decoded(Arc<[PoolItem]>),
+    Lazy(LazyPool, Arc<[OnceLock<PoolItem>]>),
+}
 impl ClassPool {
-    pub fn get<R: for <'a> UseIndex<'a>>(&self, idx: Index<R>) -> anyhow::Result<<R as UseIndex<'_>>::Out> {
-        match self.0.get(idx.0.get() as usize) {
-            Some(pi) => R::at(pi).ok_or_else(|| anyhow::anyhow!("Invalid pool item")),
-            None => anyhow::bail!("Invalid pool index"),
+    pub fn new(lazy: LazyPool) -> Self {
+        let slots = (0..lazy.len()).map(|_| OnceLock::new()).collect();
+        Self(Items::Lazy(lazy, slots))
+    }
+    /// Reads and decodes the `count` items of a pool from `r`.
+    /// The contents of `Utf8` items are read into a single buffer, which they all share.
+    pub(crate) fn read(r: &mut impl Read, count: u16, major: u16) -> anyhow::Result<Self> {
+        let mut items = Vec::with_capacity(count as usize);
+        let mut strs = vec![];
+        let mut utf8s = vec![];
+        items.push(PoolItem::None);
+        while items.len() < count as usize {
+            let tag = r.read_u8()?;
+            if tag == 1 {
+                let len = r.read_u16::<BE>()? as usize;
+                let start = strs.len();
+                strs.resize(start + len, 0);
+                r.read_exact(&mut strs[start..])?;
+                utf8s.push((items.len(), start..start + len));
+                // Filled in once all strings are read
+                items.push(PoolItem::None);
+                continue;
+            }
+            items.push(PoolItem::read_from(tag, major, r)?);
+            if tag == 5 || tag == 6 {
+                items.push(PoolItem::Reserved);
+            }
+        }
+        let strs = Bytes::from(strs);
+        for (i, range) in utf8s {
+            items[i] = PoolItem::Utf8(JStr::from(strs.slice(range)));
+        }
+        Ok(Self(Items::Decoded(items.into())))
+    }
+    /// Number of slots, including index 0
+    #[inline]
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Items::Decoded(items) => items.len(),
+            Items::Lazy(_, slots) => slots.len(),
         }
     }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() <= 1
+    }
+    /// Item at `idx`, decoded on the first access for lazy pools
+    pub fn item(&self, idx: u16) -> anyhow::Result<&PoolItem> {
+        match &self.0 {
+            Items::Decoded(items) => items.get(idx as usize).ok_or_else(|| anyhow::anyhow!("Invalid pool index")),
+            Items::Lazy(lazy, slots) => {
+                let Some(slot) = slots.get(idx as usize) else { anyhow::bail!("Invalid pool index") };
+                if let Some(pi) = slot.get() {
+                    return Ok(pi);
+                }
+                let pi = lazy.item(idx)?;
+                Ok(slot.get_or_init(|| pi))
+            }
+        }
+    }
+    /// All items in pool order, with [`PoolItem::None`] in place of those which fail to decode
+    pub fn iter(&self) -> impl Iterator<Item = &PoolItem> {
+        static NONE: PoolItem = PoolItem::None;
+        (0..self.len() as u16).map(|i| self.item(i).unwrap_or(&NONE))
+    }
+    pub fn get<R: for <'a> UseIndex<'a>>(&self, idx: Index<R>) -> anyhow::Result<<R as UseIndex<'_>>::Out> {
+        R::at(self.item(idx.0.get())?).ok_or_else(|| anyhow::anyhow!("Invalid pool item"))
+    }
     pub fn get_<R: for <'a> UseIndex<'a>>(&self, idx: u16) -> anyhow::Result<<R as UseIndex<'_>>::Out> {
         self.get::<R>(idx.try_into()?)
     }
+    /// Renders the pool item at `idx` in the style of `javap -v`.
+    pub fn display_at(&self, idx: u16) -> anyhow::Result<String> {
+        let utf = |i: Index<Utf8>| self.get(i).map(ToString::to_string);
+        let class = |i: Index<ClassInfo>| utf(self.get(i)?);
+        let nat = |i: Index<NameAndType>| -> anyhow::Result<String> {
+            let (n, d) = self.get(i)?;
+            Ok(format!("{}:{}", utf(n)?, utf(d)?))
+        };
+        let member = |c, n| -> anyhow::Result<String> { Ok(format!("{}.{}", class(c)?, nat(n)?)) };
+        Ok(match self.item(idx).ok() {
+            Some(PoolItem::Utf8(s)) => s.to_string(),
+            Some(PoolItem::Int(x)) => x.to_string(),
+            Some(PoolItem::Float(x)) => format!("{x}f"),
+            Some(PoolItem::Long(x)) => format!("{x}l"),
+            Some(PoolItem::Double(x)) => format!("{x}d"),
+            Some(PoolItem::Class(i) | PoolItem::MethodType(i) | PoolItem::Module(i) | PoolItem::Package(i)) => utf(*i)?,
+            Some(PoolItem::String(i)) => format!("{:?}", utf(*i)?),
+            Some(PoolItem::RefField(c, n) | PoolItem::RefMethod(c, n) | PoolItem::RefInterfaceMethod(c, n)) => member(*c, *n)?,
+            Some(PoolItem::NameAndType(n, d)) => format!("{}:{}", utf(*n)?, utf(*d)?),
+            Some(PoolItem::MethodHandle(rk)) => {
+                let (kind, i) = rk.parts();
+                match self.item(i).ok() {
+                    Some(PoolItem::RefField(c, n) | PoolItem::RefMethod(c, n) | PoolItem::RefInterfaceMethod(c, n)) => format!("{kind} {}", member(*c, *n)?),
+                    _ => anyhow::bail!("Invalid method handle"),
+                }
+            }
+            Some(PoolItem::Dynamic(b, n) | PoolItem::InvokeDynamic(b, n)) => format!("#{b}:{}", nat(*n)?),
+            Some(PoolItem::None | PoolItem::Reserved) | None => anyhow::bail!("Invalid pool index"),
+        })
+    }
+    /// Resolves the pool item at `idx` and the items it references.
+    pub fn resolve(&self, idx: u16) -> anyhow::Result<PoolRef<'_>> {
+        let utf = |i: Index<Utf8>| self.get(i);
+        let class = |i: Index<ClassInfo>| utf(self.get(i)?);
+        let member = |c: Index<ClassInfo>, nt: Index<NameAndType>| -> anyhow::Result<MemberRef<'_>> {
+            let (n, d) = self.get(nt)?;
+            Ok(MemberRef { owner: class(c)?, name: utf(n)?, desc: utf(d)? })
+        };
+        Ok(match self.item(idx).ok() {
+            Some(PoolItem::Utf8(s)) => PoolRef::Utf8(s),
+            Some(PoolItem::Int(x)) => PoolRef::Int(*x),
+            Some(PoolItem::Float(x)) => PoolRef::Float(*x),
+            Some(PoolItem::Long(x)) => PoolRef::Long(*x),
+            Some(PoolItem::Double(x)) => PoolRef::Double(*x),
+            Some(PoolItem::Class(i)) => PoolRef::Class(utf(*i)?),
+            Some(PoolItem::String(i)) => PoolRef::Str(utf(*i)?),
+            Some(PoolItem::RefField(c, n)) => PoolRef::Field(member(*c, *n)?),
+            Some(PoolItem::RefMethod(c, n)) => PoolRef::Method(member(*c, *n)?),
+            Some(PoolItem::RefInterfaceMethod(c, n)) => PoolRef::InterfaceMethod(member(*c, *n)?),
+            Some(PoolItem::NameAndType(n, d)) => PoolRef::NameAndType(utf(*n)?, utf(*d)?),
+            Some(PoolItem::MethodHandle(rk)) => {
+                let (kind, i) = rk.parts();
+                match self.item(i).ok() {
+                    Some(PoolItem::RefField(c, n) | PoolItem::RefMethod(c, n) | PoolItem::RefInterfaceMethod(c, n)) => PoolRef::MethodHandle(kind, member(*c, *n)?),
+                    _ => anyhow::bail!("Invalid method handle"),
+                }
+            }
+            Some(PoolItem::MethodType(i)) => PoolRef::MethodType(utf(*i)?),
+            Some(PoolItem::Dynamic(b, nt)) => {
+                let (n, d) = self.get(*nt)?;
+                PoolRef::Dynamic(*b, utf(n)?, utf(d)?)
+            }
+            Some(PoolItem::InvokeDynamic(b, nt)) => {
+                let (n, d) = self.get(*nt)?;
+                PoolRef::InvokeDynamic(*b, utf(n)?, utf(d)?)
+            }
+            Some(PoolItem::Module(i)) => PoolRef::Module(utf(*i)?),
+            Some(PoolItem::Package(i)) => PoolRef::Package(utf(*i)?),
+            Some(PoolItem::None | PoolItem::Reserved) | None => anyhow::bail!("Invalid pool index"),
+        })
+    }
     pub fn str_to_index(&self, s: &str) -> Option<Index<Utf8>> {
-        let b = s.as_bytes();
-        self.0.iter().position(|i| match i {
-            PoolItem::Utf8(a) => &**a == b,
+        let items = match &self.0 {
+            Items::Decoded(items) => items,
+            Items::Lazy(lazy, _) => return lazy.str_to_index(s),
+        };
+        let b = mutf8::encode(s);
+        items.iter().position(|pi| match pi {
+            PoolItem::Utf8(a) => **a == *b,
             _ => false
         }).and_then(|i| Index::maybe(i as u16))
     }
 }
-impl<T> From<T> for ClassPool where T: Into<Arc<[PoolItem]>> {
-    fn from(t: T) -> Self {
-        Self(t.into())
+
+/// Constant pool backed by the class file buffer, decoding items only when they are accessed.
+/// `Utf8` items are slices of the buffer and are never copied.
+#[derive(Clone)]
+pub struct LazyPool {
+    b: Bytes,
+    /// Offset of the tag of each item, 0 for index 0 and the slots following `Long` and `Double`
+    offsets: Arc<[u32]>,
+    major: u16,
+}
+impl LazyPool {
+    /// Records the offsets of `count` items starting at `at`, returning the pool and the offset after it.
+    pub(crate) fn scan(b: Bytes, mut at: usize, count: u16, major: u16) -> anyhow::Result<(Self, usize)> {
+        let mut offsets = Vec::with_capacity(count as usize);
+        offsets.push(0);
+        while offsets.len() < count as usize {
+            let Some(&tag) = b.get(at) else { anyhow::bail!("Unexpected end of pool") };
+            let len = match body_len(tag, major)? {
+                Some(len) => len,
+                None => 2 + u16_at(&b, at + 1)? as usize,
+            };
+            offsets.push(u32::try_from(at)?);
+            if tag == 5 || tag == 6 {
+                offsets.push(0);
+            }
+            at += 1 + len;
+        }
+        if at > b.len() {
+            anyhow::bail!("Unexpected end of pool");
+        }
+        Ok((Self { b, offsets: offsets.into(), major }, at))
     }
+    /// Number of slots, including index 0
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.offsets.len() <= 1
+    }
+    /// Tag of the item at `idx`, `None` for invalid indices
+    pub fn tag(&self, idx: u16) -> Option<u8> {
+        match self.offsets.get(idx as usize) {
+            Some(&at) if at != 0 => Some(self.b[at as usize]),
+            _ => None,
+        }
+    }
+    fn at(&self, idx: u16, tag: u8) -> anyhow::Result<usize> {
+        match self.offsets.get(idx as usize) {
+            Some(&at) if at != 0 && self.b[at as usize] == tag => Ok(at as usize + 1),
+            Some(_) => anyhow::bail!("Invalid pool item"),
+            None => anyhow::bail!("Invalid pool index"),
+        }
+    }
+    /// Decodes the item at `idx`.
+    pub fn item(&self, idx: u16) -> anyhow::Result<PoolItem> {
+        let at = match self.offsets.get(idx as usize) {
+            Some(0) if idx == 0 => return Ok(PoolItem::None),
+            Some(0) => return Ok(PoolItem::Reserved),
+            Some(&at) => at as usize,
+            None => anyhow::bail!("Invalid pool index"),
+        };
+        match self.b[at] {
+            1 => Ok(PoolItem::Utf8(self.utf8_from(at + 1)?)),
+            tag => PoolItem::read_from(tag, self.major, &mut &self.b[at + 1..]),
+        }
+    }
+    fn utf8_from(&self, at: usize) -> anyhow::Result<JStr> {
+        let len = u16_at(&self.b, at)? as usize;
+        Ok(JStr::from(self.b.slice(at + 2..at + 2 + len)))
+    }
+    pub fn utf8(&self, idx: Index<Utf8>) -> anyhow::Result<JStr> {
+        self.utf8_from(self.at(idx.get(), 1)?)
+    }
+    /// Name of the class at `idx`, decoding only the two items involved
+    pub fn class_name(&self, idx: Index<ClassInfo>) -> anyhow::Result<JStr> {
+        self.utf8(u16_at(&self.b, self.at(idx.get(), 7)?)?.try_into()?)
+    }
+    /// All `Utf8` items in pool order
+    pub fn utf8s(&self) -> impl Iterator<Item = JStr> + '_ {
+        self.offsets.iter()
+            .filter(|&&at| at != 0 && self.b[at as usize] == 1)
+            .filter_map(|&at| self.utf8_from(at as usize + 1).ok())
+    }
+    pub fn str_to_index(&self, s: &str) -> Option<Index<Utf8>> {
+        let s = mutf8::encode(s);
+        self.offsets.iter().position(|&at| {
+            let at = at as usize;
+            at != 0 && self.b[at] == 1 && u16_at(&self.b, at + 1).is_ok_and(|len| self.b.get(at + 3..at + 3 + len as usize) == Some(&s))
+        }).and_then(|i| Index::maybe(i as u16))
+    }
+    /// Decodes every item, with `Utf8` items still sharing the buffer
+    pub fn items(&self) -> anyhow::Result<Vec<PoolItem>> {
+        (0..self.offsets.len()).map(|i| self.item(i as u16)).collect()
+    }
+}
+
+/// Length of an item after its tag, `None` for `Utf8` items, which start with their length
+fn body_len(tag: u8, major: u16) -> anyhow::Result<Option<usize>> {
+    Ok(Some(match tag {
+        1 => return Ok(None),
+        3 | 4 | 9..=12 => 4,
+        5 | 6 => 8,
+        7 | 8 => 2,
+        15 if major >= 51 => 3,
+        16 if major >= 51 => 2,
+        18 if major >= 51 => 4,
+        17 if major >= 55 => 4,
+        19 | 20 if major >= 53 => 2,
+        n => anyhow::bail!("Invalid tag: {}", n),
+    }))
 }
-impl Deref for ClassPool {
-    type Target = [PoolItem];
-    fn deref(&self) -> &Self::Target { &self.0 }
+
+fn u16_at(b: &[u8], at: usize) -> anyhow::Result<u16> {
+    match b.get(at..at + 2) {
+        Some(&[x, y]) => Ok(u16::from_be_bytes([x, y])),
+        _ => anyhow::bail!("Unexpected end of pool"),
+    }
+}
+
+/// Owner, name and descriptor of a field or method reference
+#[derive(Clone, Copy)]
+pub struct MemberRef<'a> {
+    pub owner: &'a JStr,
+    pub name: &'a JStr,
+    pub desc: &'a JStr,
+}
+
+/// A pool item with the items it references resolved
+#[derive(Clone, Copy)]
+pub enum PoolRef<'a> {
+    Utf8(&'a JStr),
+    Int(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(&'a JStr),
+    Str(&'a JStr),
+    Field(MemberRef<'a>),
+    Method(MemberRef<'a>),
+    InterfaceMethod(MemberRef<'a>),
+    NameAndType(&'a JStr, &'a JStr),
+    /// Reference kind (as in [`RefKind::parts`]) and referenced member
+    MethodHandle(&'static str, MemberRef<'a>),
+    MethodType(&'a JStr),
+    /// Bootstrap method index, name and descriptor
+    Dynamic(u16, &'a JStr, &'a JStr),
+    InvokeDynamic(u16, &'a JStr, &'a JStr),
+    Module(&'a JStr),
+    Package(&'a JStr),
 }
 
 #[derive(Clone)]
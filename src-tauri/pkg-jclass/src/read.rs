@@ -52,6 +52,11 @@ impl<R: Read, At: Step> JClassReader<R, At> {
 }
 
 impl<R: Read, At: Step> JClassReader<R, At> {
+    /// Class file version as `(major, minor)`
+    #[inline]
+    pub const fn version(&self) -> (u16, u16) {
+        (self.major, self.minor)
+    }
     pub fn class_name(&self) -> Result<&JStr> {
         self.pool.get(self.pool.get(self.data.class_ref)?)
     }
@@ -62,12 +67,24 @@ impl<R: Read, At: Step> JClassReader<R, At> {
         }
     }
     #[inline]
-    pub fn iter_pool(&'_ self) -> std::slice::Iter<'_, PoolItem> {
+    pub const fn access_flags(&self) -> u16 {
+        self.data.access_flags
+    }
+    #[inline]
+    pub fn iter_pool(&self) -> impl Iterator<Item = &PoolItem> {
         self.pool.iter()
     }
+    #[inline]
+    pub const fn pool(&self) -> &ClassPool {
+        &self.pool
+    }
 }
 
 impl<R: Read> JClassReader<R, AtInterfaces> {
+    /// Continues reading at the interfaces with an already decoded header
+    pub(super) const fn from_parts(r: R, pool: ClassPool, (major, minor): (u16, u16), data: ClassData) -> Self {
+        Self { r, pool, minor, major, data, _t: PhantomData }
+    }
     pub fn new(mut r: R) -> Result<Self> {
         read_magic(&mut r)?;
         let mut cv = [0u16; 3];
@@ -75,17 +92,7 @@ impl<R: Read> JClassReader<R, AtInterfaces> {
             Ok(()) => cv,
             Err(e) => return Err(e.into()),
         };
-        let mut pool = Vec::with_capacity(pool_count as usize);
-        pool.push(PoolItem::None);
-        while pool.len() < pool.capacity() {
-            let mut tag = 0u8;
-            r.read_exact(std::slice::from_mut(&mut tag))?;
-            pool.push(PoolItem::read_from(tag, major, &mut r)?);
-            if tag == 5 || tag == 6 {
-                pool.push(PoolItem::Reserved);
-            }
-        }
-        let pool = ClassPool::from(pool.into_boxed_slice());
+        let pool = ClassPool::read(&mut r, pool_count, major)?;
         let mut cv = [0u16; 3];
         let [access_flags, class_ref, super_ref] = match r.read_u16_into::<BE>(&mut cv) {
             Ok(()) => cv,
@@ -149,8 +156,8 @@ impl<R: Read> JClassReader<R, AtAttributes> {
     pub fn attributes(mut self) -> Result<(JClassReader<R, ()>, Attrs<jtype::OfClass>)> {
         let mut bmut = bytes::BytesMut::new();
         read_attr_info(&mut self.r, &mut bmut)?;
-        let b = bmut.freeze();
-        let len = b.clone().get_u16();
+        let mut b = bmut.freeze();
+        let len = b.get_u16();
         let pool = self.pool.clone();
         Ok((self.step(), Attrs::new(b, pool, len)))
     }
@@ -0,0 +1,80 @@
+use byteorder::{ReadBytesExt, BE};
+use bytes::Bytes;
+
+use super::{
+    idx::Index,
+    pool::{ClassPool, LazyPool},
+    read::{AtInterfaces, JClassReader},
+    read_magic, ClassData, JStr,
+};
+
+/// Reader over a class file held in memory.
+///
+/// Only the offsets of the pool items are recorded up front, so reading the header
+/// decodes just the items it needs. [`Self::reader`] continues with members and attributes.
+pub struct JClassBytesReader {
+    b: Bytes,
+    pool: LazyPool,
+    minor: u16,
+    major: u16,
+    data: ClassData,
+    pos_interfaces: usize,
+}
+
+impl JClassBytesReader {
+    pub fn new(b: impl Into<Bytes>) -> anyhow::Result<Self> {
+        let b = b.into();
+        let mut r = &b[..];
+        read_magic(&mut r)?;
+        let minor = r.read_u16::<BE>()?;
+        let major = r.read_u16::<BE>()?;
+        let pool_count = r.read_u16::<BE>()?;
+        let (pool, at) = LazyPool::scan(b.clone(), 10, pool_count, major)?;
+        let mut r = &b[at..];
+        let access_flags = r.read_u16::<BE>()?;
+        let class_ref = r.read_u16::<BE>()?.try_into()?;
+        let super_ref = Index::maybe(r.read_u16::<BE>()?);
+        Ok(Self {
+            b,
+            pool,
+            minor,
+            major,
+            data: ClassData {
+                access_flags,
+                class_ref,
+                super_ref,
+            },
+            pos_interfaces: at + 6,
+        })
+    }
+
+    /// Class file version as `(major, minor)`
+    #[inline]
+    pub const fn version(&self) -> (u16, u16) {
+        (self.major, self.minor)
+    }
+    #[inline]
+    pub const fn access_flags(&self) -> u16 {
+        self.data.access_flags
+    }
+    #[inline]
+    pub const fn pool(&self) -> &LazyPool {
+        &self.pool
+    }
+    pub fn class_name(&self) -> anyhow::Result<JStr> {
+        self.pool.class_name(self.data.class_ref)
+    }
+    pub fn super_class(&self) -> anyhow::Result<Option<JStr>> {
+        self.data.super_ref.map(|i| self.pool.class_name(i)).transpose()
+    }
+    pub fn interfaces(&self) -> anyhow::Result<impl Iterator<Item = anyhow::Result<JStr>> + '_> {
+        let mut r = &self.b[self.pos_interfaces..];
+        let count = r.read_u16::<BE>()? as usize;
+        let Some(v) = r.get(..count * 2) else { anyhow::bail!("Unexpected end of interfaces") };
+        Ok(v.chunks_exact(2).map(|x| self.pool.class_name(u16::from_be_bytes([x[0], x[1]]).try_into()?)))
+    }
+    /// Continues with [`JClassReader`] from the interfaces, which decodes pool items as it needs them.
+    pub fn reader(&self) -> JClassReader<&[u8], AtInterfaces> {
+        JClassReader::from_parts(&self.b[self.pos_interfaces..], ClassPool::new(self.pool.clone()), self.version(), self.data)
+    }
+}
@@ -7,7 +7,7 @@ use super::{
     idx::{ClassInfo, Index},
     iter::MemberIter,
     jtype,
-    pool::{ClassPool, PoolItem},
+    pool::ClassPool,
     read_magic, skip_attr_info, skip_member_info, Attrs, JStr,
 };
 
@@ -34,15 +34,7 @@ impl<R: Read + Seek> JClassSeekReader<R> {
             Ok(()) => cv,
             Err(e) => return Err(e.into()),
         };
-        let mut pool = vec![PoolItem::None];
-        while pool.len() < pool_count as usize {
-            let tag = r.read_u8()?;
-            pool.push(PoolItem::read_from(tag, major, &mut r)?);
-            if tag == 5 || tag == 6 {
-                pool.push(PoolItem::Reserved);
-            }
-        }
-        let pool = ClassPool::from(pool.into_boxed_slice());
+        let pool = ClassPool::read(&mut r, pool_count, major)?;
         let mut cv = [0u16; 3];
         let [access_flags, class_ref, super_ref] = match r.read_u16_into::<BE>(&mut cv) {
             Ok(()) => cv,
@@ -0,0 +1,411 @@
+//! Structural checks of class files
+//!
+//! The readers stop at the first error they meet. [`verify`] carries on wherever the layout of the
+//! rest of the file is still known, to explain everything that is wrong with a corrupt class.
+
+use std::fmt::Display;
+
+use bytes::Bytes;
+
+use super::{
+    jtype::{ClassSig, JavaType, MethodSig},
+    mutf8,
+    pool::PoolItem,
+    write::{LayoutError, Sites, Want},
+    JStr,
+};
+
+/// Latest major version known to the checks (Java 25)
+const MAX_MAJOR: u16 = 69;
+/// First major version where minor versions other than 0 are reserved for preview features (0xFFFF)
+const PREVIEW_MAJOR: u16 = 56;
+const ACC_MODULE: u16 = 0x8000;
+
+/// A problem found by [`verify`]
+#[derive(Debug, Clone)]
+pub struct Problem {
+    /// Offset in the class file
+    pub offset: usize,
+    /// Location in the class file structure, like `methods[run()V].Code.StackMapTable`, empty for the header
+    pub path: String,
+    pub message: String,
+}
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{:#06x}: {}", self.offset, self.message)
+        } else {
+            write!(f, "{:#06x} {}: {}", self.offset, self.path, self.message)
+        }
+    }
+}
+
+/// Checks constant pool indices and entry types, versions, attribute layouts and lengths,
+/// member descriptors and the end of a class file, returning every problem found.
+pub fn verify(data: &[u8]) -> Vec<Problem> {
+    let mut v = Verifier { b: data, at: 0, major: 0, path: vec![], pool: vec![], offsets: vec![], bootstrap_methods: None, problems: vec![] };
+    // Stops early only when the rest of the file cannot be located, after reporting why
+    let _ = v.class();
+    v.problems
+}
+
+/// The layout of the rest of the file is unknown after a problem
+struct Stop;
+
+struct Verifier<'a> {
+    b: &'a [u8],
+    at: usize,
+    major: u16,
+    path: Vec<String>,
+    /// Constant pool, with [`PoolItem::None`] in place of invalid entries
+    pool: Vec<PoolItem>,
+    /// Offset of each pool entry
+    offsets: Vec<usize>,
+    /// Number of entries of the `BootstrapMethods` attribute
+    bootstrap_methods: Option<u16>,
+    problems: Vec<Problem>,
+}
+impl<'a> Verifier<'a> {
+    fn problem(&mut self, offset: usize, message: impl Display) {
+        self.problems.push(Problem { offset, path: self.path.join("."), message: message.to_string() });
+    }
+    fn within<T>(&mut self, segment: String, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.path.push(segment);
+        let r = f(self);
+        self.path.pop();
+        r
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], Stop> {
+        let Some(x) = self.b.get(self.at..self.at.saturating_add(n)) else {
+            self.problem(self.at, format_args!("Unexpected end of file, {n} bytes needed but {} left", self.b.len() - self.at));
+            return Err(Stop);
+        };
+        self.at += n;
+        Ok(x)
+    }
+    fn u8(&mut self) -> Result<u8, Stop> {
+        Ok(self.bytes(1)?[0])
+    }
+    fn u16(&mut self) -> Result<u16, Stop> {
+        let x = self.bytes(2)?;
+        Ok(u16::from_be_bytes([x[0], x[1]]))
+    }
+    fn u32(&mut self) -> Result<u32, Stop> {
+        let x = self.bytes(4)?;
+        Ok(u32::from_be_bytes([x[0], x[1], x[2], x[3]]))
+    }
+
+    /// Decoded `Utf8` entry, if `idx` refers to one
+    fn utf8(&self, idx: u16) -> Option<String> {
+        match self.pool.get(idx as usize) {
+            Some(PoolItem::Utf8(s)) => Some(s.to_str_lossy().into_owned()),
+            _ => None,
+        }
+    }
+    /// Checks that the pool index at `offset` refers to an entry of the wanted kind.
+    fn index(&mut self, offset: usize, idx: u16, want: Want, optional: bool) -> bool {
+        if idx == 0 {
+            if !optional {
+                self.problem(offset, format_args!("Missing {} index", want.name()));
+            }
+            return optional;
+        }
+        let message = match self.pool.get(idx as usize) {
+            None => format!("Pool index {idx} out of bounds (pool count {})", self.pool.len()),
+            Some(item) if want.accepts(item) => return true,
+            // Invalid entries are already reported
+            Some(PoolItem::None) => return false,
+            Some(PoolItem::Reserved) => format!("Pool index {idx} is the second slot of a Long or Double, expected {}", want.name()),
+            Some(item) => format!("Pool index {idx} is a {}, expected {}", item.tag_name(), want.name()),
+        };
+        self.problem(offset, message);
+        false
+    }
+    /// Checks a field or method descriptor.
+    fn descriptor(&mut self, offset: usize, desc: &str, method: bool) {
+        let res = if method { MethodSig::from_desc(desc).map(drop) } else { JavaType::from_desc(desc).map(drop) };
+        if let Err(e) = res {
+            self.problem(offset, format_args!("Invalid descriptor {desc}: {e}"));
+        }
+    }
+    /// Checks the descriptor of the `NameAndType` entry `nat`, if it is one.
+    fn nat_descriptor(&mut self, offset: usize, nat: u16, method: bool) {
+        if let Some(PoolItem::NameAndType(_, d)) = self.pool.get(nat as usize) {
+            if let Some(desc) = self.utf8(d.get()) {
+                self.descriptor(offset, &desc, method);
+            }
+        }
+    }
+
+    fn class(&mut self) -> Result<(), Stop> {
+        let magic = self.u32()?;
+        if magic != 0xCAFE_BABE {
+            self.problem(0, format_args!("Invalid magic 0x{magic:08x}"));
+            return Err(Stop);
+        }
+        let minor = self.u16()?;
+        let major = self.u16()?;
+        self.major = major;
+        if !(45..=MAX_MAJOR).contains(&major) {
+            self.problem(6, format_args!("Unsupported major version {major}"));
+        } else if major >= PREVIEW_MAJOR && minor != 0 && minor != 0xFFFF {
+            self.problem(4, format_args!("Invalid minor version {minor} for major version {major}"));
+        }
+        self.constant_pool()?;
+        let access_flags = self.u16()?;
+        if access_flags & ACC_MODULE == 0 {
+            for i in 1..self.pool.len() {
+                if let PoolItem::Module(_) | PoolItem::Package(_) = self.pool[i] {
+                    let (offset, tag) = (self.offsets[i], self.pool[i].tag_name());
+                    self.within(format!("constant_pool[{i}]"), |v| v.problem(offset, format_args!("{tag} entries are only allowed in modules")));
+                }
+            }
+        }
+        let this_at = self.at;
+        let this_class = self.u16()?;
+        self.within("this_class".into(), |v| v.index(this_at, this_class, Want::Class, false));
+        let super_at = self.at;
+        let super_class = self.u16()?;
+        self.within("super_class".into(), |v| {
+            if access_flags & ACC_MODULE != 0 {
+                if super_class != 0 {
+                    v.problem(super_at, "Modules have no superclass");
+                }
+            } else if super_class != 0 {
+                v.index(super_at, super_class, Want::Class, false);
+            } else if !matches!(v.pool.get(this_class as usize), Some(PoolItem::Class(n)) if v.utf8(n.get()).as_deref() == Some("java/lang/Object")) {
+                v.problem(super_at, "Missing superclass, only java/lang/Object has none");
+            }
+        });
+        for i in 0..self.u16()? {
+            let at = self.at;
+            let idx = self.u16()?;
+            self.within(format!("interfaces[{i}]"), |v| v.index(at, idx, Want::Class, false));
+        }
+        for kind in ["fields", "methods"] {
+            for i in 0..self.u16()? {
+                self.within(format!("{kind}[{i}]"), |v| v.member(kind == "methods"))?;
+            }
+        }
+        self.attrs()?;
+        self.bootstrap_indices();
+        if self.at < self.b.len() {
+            self.problem(self.at, format_args!("{} trailing bytes after the class attributes", self.b.len() - self.at));
+        }
+        Ok(())
+    }
+
+    fn constant_pool(&mut self) -> Result<(), Stop> {
+        let count_at = self.at;
+        let count = self.u16()? as usize;
+        if count == 0 {
+            self.problem(count_at, "Constant pool count is 0, it must be at least 1");
+        }
+        self.pool.push(PoolItem::None);
+        self.offsets.push(count_at);
+        while self.pool.len() < count {
+            let i = self.pool.len();
+            self.path.push(format!("constant_pool[{i}]"));
+            let item = self.pool_entry();
+            self.path.pop();
+            let item = item?;
+            let wide = matches!(item, PoolItem::Long(_) | PoolItem::Double(_));
+            self.pool.push(item);
+            if wide {
+                if self.pool.len() == count {
+                    self.within(format!("constant_pool[{i}]"), |v| v.problem(v.offsets[i], "Long or Double entry is missing its second slot at the end of the pool"));
+                }
+                self.pool.push(PoolItem::Reserved);
+                self.offsets.push(self.offsets[i]);
+            }
+        }
+        for i in 1..self.pool.len() {
+            self.within(format!("constant_pool[{i}]"), |v| v.pool_refs(i));
+        }
+        Ok(())
+    }
+
+    /// Reads a pool entry, [`PoolItem::None`] if it is invalid but its length is known
+    fn pool_entry(&mut self) -> Result<PoolItem, Stop> {
+        let at = self.at;
+        self.offsets.push(at);
+        let tag = self.u8()?;
+        if tag == 1 {
+            let len = self.u16()? as usize;
+            let s = JStr::from(Bytes::copy_from_slice(self.bytes(len)?));
+            if let Err(e) = mutf8::check(&s) {
+                self.problem(at, e);
+            }
+            return Ok(PoolItem::Utf8(s));
+        }
+        let (len, since) = match tag {
+            3 | 4 | 9..=12 => (4, 45),
+            5 | 6 => (8, 45),
+            7 | 8 => (2, 45),
+            15 => (3, 51),
+            16 => (2, 51),
+            18 => (4, 51),
+            17 => (4, 55),
+            19 | 20 => (2, 53),
+            _ => {
+                self.problem(at, format_args!("Invalid tag {tag}"));
+                return Err(Stop);
+            }
+        };
+        let mut data = self.bytes(len)?;
+        let item = PoolItem::read_from(tag, u16::MAX, &mut data);
+        if let Ok(item) = &item {
+            if self.major < since {
+                self.problem(at, format_args!("{} entries require major version {since}", item.tag_name()));
+            }
+        }
+        Ok(item.unwrap_or_else(|e| {
+            self.problem(at, e);
+            PoolItem::None
+        }))
+    }
+
+    /// Checks the indices of the pool entry `i`.
+    fn pool_refs(&mut self, i: usize) {
+        let at = self.offsets[i];
+        match self.pool[i].clone() {
+            PoolItem::Class(n) => {
+                let Some(name) = self.index(at + 1, n.get(), Want::Utf8, false).then(|| self.utf8(n.get())).flatten() else { return };
+                if name.starts_with('[') {
+                    self.descriptor(at + 1, &name, false);
+                } else if name.is_empty() || name.contains(['.', ';', '[']) {
+                    self.problem(at + 1, format_args!("Invalid class name {name:?}"));
+                }
+            }
+            PoolItem::String(n) | PoolItem::Module(n) | PoolItem::Package(n) => {
+                self.index(at + 1, n.get(), Want::Utf8, false);
+            }
+            PoolItem::MethodType(n) => {
+                let Some(desc) = self.index(at + 1, n.get(), Want::Utf8, false).then(|| self.utf8(n.get())).flatten() else { return };
+                self.descriptor(at + 1, &desc, true);
+            }
+            PoolItem::RefField(c, n) | PoolItem::RefMethod(c, n) | PoolItem::RefInterfaceMethod(c, n) => {
+                self.index(at + 1, c.get(), Want::Class, false);
+                self.index(at + 3, n.get(), Want::NameAndType, false);
+                self.nat_descriptor(at + 3, n.get(), !matches!(self.pool[i], PoolItem::RefField(..)));
+            }
+            PoolItem::NameAndType(n, d) => {
+                self.index(at + 1, n.get(), Want::Utf8, false);
+                self.index(at + 3, d.get(), Want::Utf8, false);
+            }
+            PoolItem::MethodHandle(rk) => {
+                let want = match rk.kind() {
+                    1..=4 => Want::Field,
+                    5 | 8 => Want::Method,
+                    6 | 7 if self.major >= 52 => Want::AnyMethod,
+                    6 | 7 => Want::Method,
+                    _ => Want::InterfaceMethod,
+                };
+                self.index(at + 2, rk.parts().1, want, false);
+            }
+            PoolItem::Dynamic(_, n) | PoolItem::InvokeDynamic(_, n) => {
+                self.index(at + 3, n.get(), Want::NameAndType, false);
+                self.nat_descriptor(at + 3, n.get(), matches!(self.pool[i], PoolItem::InvokeDynamic(..)));
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks that dynamic pool entries refer to existing bootstrap methods.
+    fn bootstrap_indices(&mut self) {
+        for i in 1..self.pool.len() {
+            let (PoolItem::Dynamic(b, _) | PoolItem::InvokeDynamic(b, _)) = self.pool[i] else { continue };
+            let message = match self.bootstrap_methods {
+                None => format!("{} entry without a BootstrapMethods attribute", self.pool[i].tag_name()),
+                Some(n) if b >= n => format!("Bootstrap method index {b} out of bounds ({n} bootstrap methods)"),
+                Some(_) => continue,
+            };
+            let at = self.offsets[i] + 1;
+            self.within(format!("constant_pool[{i}]"), |v| v.problem(at, message));
+        }
+    }
+
+    fn member(&mut self, method: bool) -> Result<(), Stop> {
+        self.u16()?;
+        let name_at = self.at;
+        let name = self.u16()?;
+        let name_ok = self.index(name_at, name, Want::Utf8, false);
+        let desc_at = self.at;
+        let desc = self.u16()?;
+        if self.index(desc_at, desc, Want::Utf8, false) {
+            let desc = self.utf8(desc).unwrap_or_default();
+            self.descriptor(desc_at, &desc, method);
+            if name_ok {
+                // Names members once they are known, which is easier to follow than their position
+                let name = self.utf8(name).unwrap_or_default();
+                let kind = if method { "methods" } else { "fields" };
+                *self.path.last_mut().unwrap() = if method { format!("{kind}[{name}{desc}]") } else { format!("{kind}[{name}]") };
+            }
+        }
+        self.attrs()
+    }
+
+    fn attrs(&mut self) -> Result<(), Stop> {
+        for i in 0..self.u16()? {
+            let name_at = self.at;
+            let name = self.u16()?;
+            let segment = self.utf8(name).unwrap_or_else(|| format!("attributes[{i}]"));
+            self.within(segment, |v| v.attr(name_at, name))?;
+        }
+        Ok(())
+    }
+
+    fn attr(&mut self, name_at: usize, name: u16) -> Result<(), Stop> {
+        self.index(name_at, name, Want::Utf8, false);
+        let len = self.u32()? as usize;
+        let data_at = self.at;
+        let left = self.b.len() - data_at;
+        if len > left {
+            self.problem(name_at + 2, format_args!("Attribute length {len} exceeds the {left} bytes left"));
+            return Err(Stop);
+        }
+        let data = self.bytes(len)?;
+        let Some(PoolItem::Utf8(name)) = self.pool.get(name as usize).cloned() else { return Ok(()) };
+        let mut sites = Sites::new(&self.pool, data);
+        let res = sites.attr(&name);
+        let sites = sites.out;
+        for s in sites {
+            let end = s.at + if s.narrow { 1 } else { 2 };
+            if end <= data.len() {
+                self.index(data_at + s.at, s.read(data), s.want, s.optional);
+            }
+        }
+        if let Err(e) = res {
+            match e.downcast::<LayoutError>() {
+                Ok(le) => {
+                    let depth = self.path.len();
+                    self.path.extend(le.path.iter().map(|x| x.to_string()));
+                    self.problem(data_at + le.at, le.msg);
+                    self.path.truncate(depth);
+                }
+                Err(e) => self.problem(data_at, e),
+            }
+            return Ok(());
+        }
+        match &*name {
+            b"BootstrapMethods" if self.path.len() == 1 => self.bootstrap_methods = Some(u16::from_be_bytes([data[0], data[1]])),
+            b"Signature" => self.signature(data_at, u16::from_be_bytes([data[0], data[1]])),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Checks the generic signature of the class or member containing the attribute.
+    fn signature(&mut self, offset: usize, idx: u16) {
+        let Some(sig) = self.utf8(idx) else { return };
+        let res = match self.path.first().map(String::as_str) {
+            Some(p) if p.starts_with("fields") => JavaType::from_sig(&sig).map(drop),
+            Some(p) if p.starts_with("methods") => MethodSig::from_sig(&sig).map(drop),
+            _ => ClassSig::from_sig(&sig).map(drop),
+        };
+        if let Err(e) = res {
+            self.problem(offset, format_args!("Invalid signature {sig}: {e}"));
+        }
+    }
+}
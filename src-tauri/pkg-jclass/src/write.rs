@@ -0,0 +1,699 @@
+//! Editing and writing class files
+
+use byteorder::{ReadBytesExt, BE};
+use bytes::{BufMut, Bytes};
+
+use super::{attr::MAX_NESTING, code::Insns, mutf8, pool::{LazyPool, PoolItem}, read_magic, JStr};
+
+/// Attributes removed by [`ClassFile::strip_debug`]
+const DEBUG_ATTRS: &[&[u8]] = &[b"LineNumberTable", b"LocalVariableTable", b"LocalVariableTypeTable", b"SourceFile", b"SourceDebugExtension"];
+
+/// Attribute with its data kept as read, so that it is written back unchanged
+#[derive(Clone)]
+pub struct RawAttr {
+    pub name: u16,
+    pub data: Bytes,
+}
+
+#[derive(Clone)]
+pub struct RawMember {
+    pub access_flags: u16,
+    pub name: u16,
+    pub descriptor: u16,
+    pub attrs: Vec<RawAttr>,
+}
+
+/// A class file decoded into an editable form, written back byte for byte unless modified
+#[derive(Clone)]
+pub struct ClassFile {
+    pub minor: u16,
+    pub major: u16,
+    /// Constant pool, with [`PoolItem::None`] at index 0 and [`PoolItem::Reserved`] after each `Long` and `Double`
+    pub pool: Vec<PoolItem>,
+    pub access_flags: u16,
+    pub this_class: u16,
+    /// Pool index of the superclass, 0 for `java/lang/Object` and modules
+    pub super_class: u16,
+    pub interfaces: Vec<u16>,
+    pub fields: Vec<RawMember>,
+    pub methods: Vec<RawMember>,
+    pub attrs: Vec<RawAttr>,
+}
+
+impl ClassFile {
+    pub fn parse(data: &[u8]) -> anyhow::Result<Self> {
+        let all = Bytes::copy_from_slice(data);
+        let mut r = &all[..];
+        read_magic(&mut r)?;
+        let minor = r.read_u16::<BE>()?;
+        let major = r.read_u16::<BE>()?;
+        let pool_count = r.read_u16::<BE>()?;
+        let (lazy, at) = LazyPool::scan(all.clone(), 10, pool_count, major)?;
+        let pool = lazy.items()?;
+        let mut r = &all[at..];
+        let access_flags = r.read_u16::<BE>()?;
+        let this_class = r.read_u16::<BE>()?;
+        let super_class = r.read_u16::<BE>()?;
+        let interfaces = (0..r.read_u16::<BE>()?).map(|_| r.read_u16::<BE>()).collect::<Result<_, _>>()?;
+        let fields = read_members(&all, &mut r)?;
+        let methods = read_members(&all, &mut r)?;
+        let attrs = read_attrs(&all, &mut r)?;
+        if !r.is_empty() {
+            anyhow::bail!("Invalid end");
+        }
+        Ok(Self { minor, major, pool, access_flags, this_class, super_class, interfaces, fields, methods, attrs })
+    }
+
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut out = vec![];
+        out.put_u32(0xCAFE_BABE);
+        out.put_u16(self.minor);
+        out.put_u16(self.major);
+        out.put_u16(count(self.pool.len())?);
+        for item in &self.pool {
+            item.write_to(&mut out)?;
+        }
+        out.put_u16(self.access_flags);
+        out.put_u16(self.this_class);
+        out.put_u16(self.super_class);
+        out.put_u16(count(self.interfaces.len())?);
+        for &i in &self.interfaces {
+            out.put_u16(i);
+        }
+        for members in [&self.fields, &self.methods] {
+            out.put_u16(count(members.len())?);
+            for m in members {
+                out.put_u16(m.access_flags);
+                out.put_u16(m.name);
+                out.put_u16(m.descriptor);
+                write_attrs(&mut out, &m.attrs)?;
+            }
+        }
+        write_attrs(&mut out, &self.attrs)?;
+        Ok(out)
+    }
+
+    pub fn utf8_at(&self, idx: u16) -> anyhow::Result<&JStr> {
+        utf8_at(&self.pool, idx)
+    }
+
+    /// Pool index of a `Utf8` entry, added to the end of the pool if missing
+    pub fn intern_utf8(&mut self, s: &str) -> anyhow::Result<u16> {
+        let s = JStr::from(s);
+        if let Some(i) = self.pool.iter().position(|x| matches!(x, PoolItem::Utf8(u) if **u == *s)) {
+            return Ok(i as u16);
+        }
+        let i = count(self.pool.len())?;
+        self.pool.push(PoolItem::Utf8(s));
+        Ok(i)
+    }
+
+    /// Attributes of the class and all of its fields and methods
+    fn all_attrs(&self) -> impl Iterator<Item = &RawAttr> {
+        self.attrs.iter().chain(self.fields.iter().chain(&self.methods).flat_map(|m| &m.attrs))
+    }
+    fn all_attrs_mut(&mut self) -> impl Iterator<Item = &mut RawAttr> {
+        self.attrs.iter_mut().chain(self.fields.iter_mut().chain(&mut self.methods).flat_map(|m| &mut m.attrs))
+    }
+
+    /// Removes the attributes for which `keep` returns `false` by name,
+    /// including those nested in `Code` attributes.
+    pub fn retain_attrs(&mut self, mut keep: impl FnMut(&[u8]) -> bool) -> anyhow::Result<()> {
+        let pool = &self.pool;
+        let mut keep_attr = |a: &RawAttr| utf8_at(pool, a.name).map(|n| keep(n));
+        let mut retain = |attrs: &mut Vec<RawAttr>| -> anyhow::Result<()> {
+            let mut kept = Vec::with_capacity(attrs.len());
+            for mut a in attrs.drain(..) {
+                if !keep_attr(&a)? {
+                    continue;
+                }
+                if **utf8_at(pool, a.name)? == *b"Code" {
+                    a.data = retain_code_attrs(&a.data, &mut keep_attr)?;
+                }
+                kept.push(a);
+            }
+            *attrs = kept;
+            Ok(())
+        };
+        retain(&mut self.attrs)?;
+        for m in self.fields.iter_mut().chain(&mut self.methods) {
+            retain(&mut m.attrs)?;
+        }
+        Ok(())
+    }
+
+    /// Removes line numbers, local variable names and source file information.
+    pub fn strip_debug(&mut self) -> anyhow::Result<()> {
+        self.retain_attrs(|name| !DEBUG_ATTRS.contains(&name))
+    }
+
+    /// Removes unused pool entries, keeping the order of the others.
+    /// Fails for classes with attributes of unknown layout, which could reference any entry.
+    pub fn compact_pool(&mut self) -> anyhow::Result<()> {
+        let sites = self.all_attrs()
+            .map(|a| {
+                let mut s = Sites::new(&self.pool, &a.data);
+                s.attr(utf8_at(&self.pool, a.name)?)?;
+                if let Some(name) = s.unknown {
+                    anyhow::bail!("Unknown attribute: {name}");
+                }
+                Ok(s.out)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let mut used = vec![false; self.pool.len()];
+        let mut todo = vec![self.this_class, self.super_class];
+        todo.extend(&self.interfaces);
+        for m in self.fields.iter().chain(&self.methods) {
+            todo.extend([m.name, m.descriptor]);
+        }
+        for (a, sites) in self.all_attrs().zip(&sites) {
+            todo.push(a.name);
+            todo.extend(sites.iter().map(|s| s.read(&a.data)));
+        }
+        while let Some(i) = todo.pop() {
+            let Some(u) = used.get_mut(i as usize) else { anyhow::bail!("Invalid pool index") };
+            if i == 0 || *u {
+                continue;
+            }
+            *u = true;
+            todo.extend(self.pool[i as usize].refs());
+        }
+        let mut map = vec![0; self.pool.len()];
+        let mut pool = vec![PoolItem::None];
+        for (i, item) in self.pool.iter().enumerate().skip(1) {
+            // The second slot of a `Long` or `Double` follows its entry
+            if used[i] || (matches!(item, PoolItem::Reserved) && used[i - 1]) {
+                map[i] = count(pool.len())?;
+                pool.push(item.clone());
+            }
+        }
+        if pool.len() == self.pool.len() {
+            return Ok(());
+        }
+        let f = |i: u16| map[i as usize];
+        for item in &mut pool {
+            *item = item.map_refs(f)?;
+        }
+        self.this_class = f(self.this_class);
+        self.super_class = f(self.super_class);
+        for i in &mut self.interfaces {
+            *i = f(*i);
+        }
+        for m in self.fields.iter_mut().chain(&mut self.methods) {
+            m.name = f(m.name);
+            m.descriptor = f(m.descriptor);
+        }
+        for (a, sites) in self.all_attrs_mut().zip(&sites) {
+            a.name = f(a.name);
+            if sites.is_empty() {
+                continue;
+            }
+            let mut data = a.data.to_vec();
+            for s in sites {
+                s.write(&mut data, f(s.read(&a.data)))?;
+            }
+            a.data = Bytes::from(data);
+        }
+        self.pool = pool;
+        Ok(())
+    }
+}
+
+fn count(n: usize) -> anyhow::Result<u16> {
+    u16::try_from(n).map_err(|_| anyhow::anyhow!("Too many items ({n})"))
+}
+
+fn utf8_at(pool: &[PoolItem], idx: u16) -> anyhow::Result<&JStr> {
+    match pool.get(idx as usize) {
+        Some(PoolItem::Utf8(s)) => Ok(s),
+        _ => anyhow::bail!("Invalid pool item"),
+    }
+}
+
+fn read_members(all: &Bytes, r: &mut &[u8]) -> anyhow::Result<Vec<RawMember>> {
+    (0..r.read_u16::<BE>()?)
+        .map(|_| Ok(RawMember {
+            access_flags: r.read_u16::<BE>()?,
+            name: r.read_u16::<BE>()?,
+            descriptor: r.read_u16::<BE>()?,
+            attrs: read_attrs(all, r)?,
+        }))
+        .collect()
+}
+fn read_attrs(all: &Bytes, r: &mut &[u8]) -> anyhow::Result<Vec<RawAttr>> {
+    (0..r.read_u16::<BE>()?)
+        .map(|_| {
+            let name = r.read_u16::<BE>()?;
+            let len = r.read_u32::<BE>()? as usize;
+            let Some((data, rest)) = r.split_at_checked(len) else { anyhow::bail!("Invalid attribute length") };
+            *r = rest;
+            Ok(RawAttr { name, data: all.slice_ref(data) })
+        })
+        .collect()
+}
+fn write_attrs(out: &mut Vec<u8>, attrs: &[RawAttr]) -> anyhow::Result<()> {
+    out.put_u16(count(attrs.len())?);
+    for a in attrs {
+        out.put_u16(a.name);
+        out.put_u32(u32::try_from(a.data.len())?);
+        out.put_slice(&a.data);
+    }
+    Ok(())
+}
+
+/// Copy of a `Code` attribute without the nested attributes for which `keep` returns `false`
+fn retain_code_attrs(data: &Bytes, keep: &mut impl FnMut(&RawAttr) -> anyhow::Result<bool>) -> anyhow::Result<Bytes> {
+    let mut r = &data[..];
+    r.read_u32::<BE>()?;
+    let code_len = r.read_u32::<BE>()? as usize;
+    let handlers_at = 8 + code_len;
+    r = data.get(handlers_at..).ok_or_else(|| anyhow::anyhow!("Unexpected end of code"))?;
+    let attrs_at = handlers_at + 2 + r.read_u16::<BE>()? as usize * 8;
+    r = data.get(attrs_at..).ok_or_else(|| anyhow::anyhow!("Unexpected end of code"))?;
+    let attrs = read_attrs(data, &mut r)?;
+    let mut kept = vec![];
+    for a in attrs {
+        if keep(&a)? {
+            kept.push(a);
+        }
+    }
+    let mut out = data[..attrs_at].to_vec();
+    write_attrs(&mut out, &kept)?;
+    Ok(Bytes::from(out))
+}
+
+/// Kind of pool entry expected at a [`Site`]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Want {
+    Utf8,
+    Class,
+    NameAndType,
+    Module,
+    Package,
+    MethodHandle,
+    Int,
+    Long,
+    Float,
+    Double,
+    /// Value of `ConstantValue`
+    Constant,
+    /// Operand of `ldc` and `ldc_w`
+    Ldc,
+    /// Operand of `ldc2_w`
+    Ldc2,
+    /// Static argument of a bootstrap method
+    Loadable,
+    Field,
+    Method,
+    InterfaceMethod,
+    /// Operand of `invokespecial` and `invokestatic`, which may also call interface methods
+    AnyMethod,
+    InvokeDynamic,
+}
+impl Want {
+    pub(crate) const fn accepts(self, item: &PoolItem) -> bool {
+        use PoolItem as P;
+        match self {
+            Self::Utf8 => matches!(item, P::Utf8(_)),
+            Self::Class => matches!(item, P::Class(_)),
+            Self::NameAndType => matches!(item, P::NameAndType(..)),
+            Self::Module => matches!(item, P::Module(_)),
+            Self::Package => matches!(item, P::Package(_)),
+            Self::MethodHandle => matches!(item, P::MethodHandle(_)),
+            Self::Int => matches!(item, P::Int(_)),
+            Self::Long => matches!(item, P::Long(_)),
+            Self::Float => matches!(item, P::Float(_)),
+            Self::Double => matches!(item, P::Double(_)),
+            Self::Constant => matches!(item, P::Int(_) | P::Float(_) | P::Long(_) | P::Double(_) | P::String(_)),
+            Self::Ldc => matches!(item, P::Int(_) | P::Float(_) | P::String(_) | P::Class(_) | P::MethodType(_) | P::MethodHandle(_) | P::Dynamic(..)),
+            Self::Ldc2 => matches!(item, P::Long(_) | P::Double(_) | P::Dynamic(..)),
+            Self::Loadable => Self::Ldc.accepts(item) || Self::Ldc2.accepts(item),
+            Self::Field => matches!(item, P::RefField(..)),
+            Self::Method => matches!(item, P::RefMethod(..)),
+            Self::InterfaceMethod => matches!(item, P::RefInterfaceMethod(..)),
+            Self::AnyMethod => matches!(item, P::RefMethod(..) | P::RefInterfaceMethod(..)),
+            Self::InvokeDynamic => matches!(item, P::InvokeDynamic(..)),
+        }
+    }
+    /// Description of the accepted entries, for error messages
+    pub(crate) const fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "Utf8",
+            Self::Class => "Class",
+            Self::NameAndType => "NameAndType",
+            Self::Module => "Module",
+            Self::Package => "Package",
+            Self::MethodHandle => "MethodHandle",
+            Self::Int => "Integer",
+            Self::Long => "Long",
+            Self::Float => "Float",
+            Self::Double => "Double",
+            Self::Constant => "constant value",
+            Self::Ldc => "single-slot loadable constant",
+            Self::Ldc2 => "Long, Double or Dynamic",
+            Self::Loadable => "loadable constant",
+            Self::Field => "Fieldref",
+            Self::Method => "Methodref",
+            Self::InterfaceMethod => "InterfaceMethodref",
+            Self::AnyMethod => "Methodref or InterfaceMethodref",
+            Self::InvokeDynamic => "InvokeDynamic",
+        }
+    }
+}
+
+/// Kind of pool entry referenced by an instruction
+const fn insn_want(opcode: u8) -> Want {
+    match opcode {
+        0x12 | 0x13 => Want::Ldc,
+        0x14 => Want::Ldc2,
+        0xb2..=0xb5 => Want::Field,
+        0xb6 => Want::Method,
+        0xb7 | 0xb8 => Want::AnyMethod,
+        0xb9 => Want::InterfaceMethod,
+        0xba => Want::InvokeDynamic,
+        _ => Want::Class,
+    }
+}
+
+/// Failure to follow the layout of an attribute
+#[derive(Debug)]
+pub(crate) struct LayoutError {
+    /// Offset in the data of the outermost attribute
+    pub at: usize,
+    /// Names of the nested attributes containing the error
+    pub path: Vec<Box<str>>,
+    pub msg: String,
+}
+impl std::fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.msg)
+    }
+}
+impl std::error::Error for LayoutError {}
+
+/// Location of a pool index in attribute data
+pub(crate) struct Site {
+    pub at: usize,
+    /// `ldc` has a single byte index
+    pub narrow: bool,
+    pub want: Want,
+    /// Index 0 means that the entry is absent
+    pub optional: bool,
+}
+impl Site {
+    pub(crate) fn read(&self, b: &[u8]) -> u16 {
+        if self.narrow { u16::from(b[self.at]) } else { u16::from_be_bytes([b[self.at], b[self.at + 1]]) }
+    }
+    fn write(&self, b: &mut [u8], idx: u16) -> anyhow::Result<()> {
+        if self.narrow {
+            b[self.at] = u8::try_from(idx)?;
+        } else {
+            b[self.at..self.at + 2].copy_from_slice(&idx.to_be_bytes());
+        }
+        Ok(())
+    }
+}
+
+/// Finds the pool indices in the data of an attribute
+pub(crate) struct Sites<'a> {
+    pool: &'a [PoolItem],
+    b: &'a [u8],
+    at: usize,
+    /// Offset of `b` in the data of the outermost attribute
+    base: usize,
+    pub out: Vec<Site>,
+    /// Name of the first attribute of unknown layout, skipped without finding its indices
+    pub unknown: Option<Box<str>>,
+    /// Nested attributes, annotations and arrays enclosing the current position
+    depth: usize,
+}
+impl<'a> Sites<'a> {
+    pub(crate) const fn new(pool: &'a [PoolItem], b: &'a [u8]) -> Self {
+        Self { pool, b, at: 0, base: 0, out: vec![], unknown: None, depth: 0 }
+    }
+    fn error(&self, msg: impl std::fmt::Display) -> anyhow::Error {
+        LayoutError { at: self.base + self.at, path: vec![], msg: msg.to_string() }.into()
+    }
+    /// Runs `f` one level deeper, failing past [`MAX_NESTING`] levels instead of running out of stack
+    fn nested(&mut self, f: impl FnOnce(&mut Self) -> anyhow::Result<()>) -> anyhow::Result<()> {
+        if self.depth >= MAX_NESTING {
+            return Err(self.error(format_args!("Nested deeper than {MAX_NESTING} levels")));
+        }
+        self.depth += 1;
+        let r = f(self);
+        self.depth -= 1;
+        r
+    }
+    fn skip(&mut self, n: usize) -> anyhow::Result<()> {
+        if self.b.len() < self.at + n {
+            return Err(self.error("Unexpected end of attribute"));
+        }
+        self.at += n;
+        Ok(())
+    }
+    fn u8(&mut self) -> anyhow::Result<u8> {
+        self.skip(1)?;
+        Ok(self.b[self.at - 1])
+    }
+    fn u16(&mut self) -> anyhow::Result<u16> {
+        self.skip(2)?;
+        Ok(u16::from_be_bytes([self.b[self.at - 2], self.b[self.at - 1]]))
+    }
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        Ok(u32::from(self.u16()?) << 16 | u32::from(self.u16()?))
+    }
+    fn site(&mut self, want: Want, optional: bool) -> anyhow::Result<u16> {
+        self.out.push(Site { at: self.base + self.at, narrow: false, want, optional });
+        self.u16()
+    }
+    fn idx(&mut self, want: Want) -> anyhow::Result<()> {
+        self.site(want, false).map(drop)
+    }
+    fn opt_idx(&mut self, want: Want) -> anyhow::Result<()> {
+        self.site(want, true).map(drop)
+    }
+    fn idx_list(&mut self, want: Want) -> anyhow::Result<()> {
+        for _ in 0..self.u16()? {
+            self.idx(want)?;
+        }
+        Ok(())
+    }
+    fn attrs(&mut self) -> anyhow::Result<()> {
+        for _ in 0..self.u16()? {
+            let name = self.site(Want::Utf8, false)?;
+            let name = utf8_at(self.pool, name).map_err(|e| LayoutError { at: self.base + self.at - 2, path: vec![], msg: e.to_string() })?;
+            let len = self.u32()? as usize;
+            let start = self.at;
+            self.skip(len)?;
+            let mut nested = Sites { base: self.base + start, depth: self.depth, ..Sites::new(self.pool, &self.b[start..self.at]) };
+            nested.nested(|n| n.attr(name)).map_err(|e| match e.downcast::<LayoutError>() {
+                Ok(mut le) => {
+                    le.path.insert(0, mutf8::decode_lossy(name).into());
+                    le.into()
+                }
+                Err(e) => e,
+            })?;
+            self.out.append(&mut nested.out);
+            self.unknown = self.unknown.take().or(nested.unknown);
+        }
+        Ok(())
+    }
+    /// Follows the layout of the attribute named `name`, recording the sites of pool indices,
+    /// or skips it if its layout is unknown.
+    pub(crate) fn attr(&mut self, name: &[u8]) -> anyhow::Result<()> {
+        match name {
+            b"ConstantValue" => self.idx(Want::Constant)?,
+            b"Signature" | b"SourceFile" => self.idx(Want::Utf8)?,
+            b"ModuleMainClass" | b"NestHost" => self.idx(Want::Class)?,
+            b"Exceptions" | b"NestMembers" | b"PermittedSubclasses" => self.idx_list(Want::Class)?,
+            b"ModulePackages" => self.idx_list(Want::Package)?,
+            // Added by jlink and the JDK build to module-info classes
+            b"ModuleTarget" => self.opt_idx(Want::Utf8)?,
+            b"ModuleResolution" => self.skip(2)?,
+            b"ModuleHashes" => {
+                self.idx(Want::Utf8)?;
+                for _ in 0..self.u16()? {
+                    self.idx(Want::Module)?;
+                    let n = self.u16()? as usize;
+                    self.skip(n)?;
+                }
+            }
+            b"Synthetic" | b"Deprecated" | b"SourceDebugExtension" | b"LineNumberTable" => self.at = self.b.len(),
+            b"InnerClasses" => for _ in 0..self.u16()? {
+                self.idx(Want::Class)?;
+                self.opt_idx(Want::Class)?;
+                self.opt_idx(Want::Utf8)?;
+                self.skip(2)?;
+            },
+            b"EnclosingMethod" => {
+                self.idx(Want::Class)?;
+                self.opt_idx(Want::NameAndType)?;
+            }
+            b"LocalVariableTable" | b"LocalVariableTypeTable" => for _ in 0..self.u16()? {
+                self.skip(4)?;
+                self.idx(Want::Utf8)?;
+                self.idx(Want::Utf8)?;
+                self.skip(2)?;
+            },
+            b"RuntimeVisibleAnnotations" | b"RuntimeInvisibleAnnotations" => for _ in 0..self.u16()? {
+                self.annotation()?;
+            },
+            b"RuntimeVisibleParameterAnnotations" | b"RuntimeInvisibleParameterAnnotations" => for _ in 0..self.u8()? {
+                for _ in 0..self.u16()? {
+                    self.annotation()?;
+                }
+            },
+            b"RuntimeVisibleTypeAnnotations" | b"RuntimeInvisibleTypeAnnotations" => for _ in 0..self.u16()? {
+                self.type_annotation()?;
+            },
+            b"AnnotationDefault" => self.elem_value()?,
+            b"BootstrapMethods" => for _ in 0..self.u16()? {
+                self.idx(Want::MethodHandle)?;
+                self.idx_list(Want::Loadable)?;
+            },
+            b"MethodParameters" => for _ in 0..self.u8()? {
+                self.opt_idx(Want::Utf8)?;
+                self.skip(2)?;
+            },
+            b"Module" => {
+                self.idx(Want::Module)?;
+                self.skip(2)?;
+                self.opt_idx(Want::Utf8)?;
+                for _ in 0..self.u16()? {
+                    self.idx(Want::Module)?;
+                    self.skip(2)?;
+                    self.opt_idx(Want::Utf8)?;
+                }
+                // exports, then opens
+                for _ in 0..2 {
+                    for _ in 0..self.u16()? {
+                        self.idx(Want::Package)?;
+                        self.skip(2)?;
+                        self.idx_list(Want::Module)?;
+                    }
+                }
+                self.idx_list(Want::Class)?;
+                for _ in 0..self.u16()? {
+                    self.idx(Want::Class)?;
+                    self.idx_list(Want::Class)?;
+                }
+            }
+            b"Record" => for _ in 0..self.u16()? {
+                self.idx(Want::Utf8)?;
+                self.idx(Want::Utf8)?;
+                self.attrs()?;
+            },
+            b"StackMapTable" => for _ in 0..self.u16()? {
+                self.frame()?;
+            },
+            b"Code" => {
+                self.skip(4)?;
+                let len = self.u32()? as usize;
+                let start = self.at;
+                self.skip(len)?;
+                for insn in Insns::new(&self.b[start..self.at]) {
+                    let insn = insn.map_err(|e| LayoutError { at: self.base + start, path: vec![], msg: e.to_string() })?;
+                    if insn.pool_index().is_some() {
+                        let at = self.base + start + insn.pc() + 1;
+                        // ldc, the other instructions have two byte indices
+                        self.out.push(Site { at, narrow: insn.opcode() == 0x12, want: insn_want(insn.opcode()), optional: false });
+                    }
+                }
+                for _ in 0..self.u16()? {
+                    self.skip(6)?;
+                    // 0 catches any exception
+                    self.opt_idx(Want::Class)?;
+                }
+                self.attrs()?;
+            }
+            _ => {
+                self.unknown = Some(mutf8::decode_lossy(name).into());
+                self.at = self.b.len();
+            }
+        }
+        if self.at != self.b.len() {
+            return Err(self.error(format_args!("Invalid length of attribute {}", mutf8::decode_lossy(name))));
+        }
+        Ok(())
+    }
+    fn annotation(&mut self) -> anyhow::Result<()> {
+        self.idx(Want::Utf8)?;
+        for _ in 0..self.u16()? {
+            self.idx(Want::Utf8)?;
+            self.elem_value()?;
+        }
+        Ok(())
+    }
+    fn elem_value(&mut self) -> anyhow::Result<()> {
+        match self.u8()? {
+            b'B' | b'C' | b'I' | b'S' | b'Z' => self.idx(Want::Int),
+            b'D' => self.idx(Want::Double),
+            b'F' => self.idx(Want::Float),
+            b'J' => self.idx(Want::Long),
+            b's' | b'c' => self.idx(Want::Utf8),
+            b'e' => {
+                self.idx(Want::Utf8)?;
+                self.idx(Want::Utf8)
+            }
+            b'@' => self.nested(Self::annotation),
+            b'[' => self.nested(|s| {
+                for _ in 0..s.u16()? {
+                    s.elem_value()?;
+                }
+                Ok(())
+            }),
+            t => Err(self.error(format_args!("Invalid element value tag: {}", t as char))),
+        }
+    }
+    fn type_annotation(&mut self) -> anyhow::Result<()> {
+        match self.u8()? {
+            0x13..=0x15 => {}
+            0x00 | 0x01 | 0x16 => self.skip(1)?,
+            0x10..=0x12 | 0x17 | 0x42..=0x46 => self.skip(2)?,
+            0x40 | 0x41 => {
+                let n = self.u16()? as usize;
+                self.skip(n * 6)?;
+            }
+            0x47..=0x4b => self.skip(3)?,
+            t => return Err(self.error(format_args!("Invalid type annotation target: 0x{t:02x}"))),
+        }
+        let path = self.u8()? as usize;
+        self.skip(path * 2)?;
+        self.annotation()
+    }
+    fn frame(&mut self) -> anyhow::Result<()> {
+        match self.u8()? {
+            0..=63 => {}
+            64..=127 => self.verification_type()?,
+            247 => {
+                self.skip(2)?;
+                self.verification_type()?;
+            }
+            248..=251 => self.skip(2)?,
+            t @ 252..=254 => {
+                self.skip(2)?;
+                for _ in 251..t {
+                    self.verification_type()?;
+                }
+            }
+            255 => {
+                self.skip(2)?;
+                for _ in 0..2 {
+                    for _ in 0..self.u16()? {
+                        self.verification_type()?;
+                    }
+                }
+            }
+            t => return Err(self.error(format_args!("Invalid stack map frame type: {t}"))),
+        }
+        Ok(())
+    }
+    fn verification_type(&mut self) -> anyhow::Result<()> {
+        match self.u8()? {
+            0..=6 => Ok(()),
+            7 => self.idx(Want::Class),
+            8 => self.skip(2),
+            t => Err(self.error(format_args!("Invalid verification type: {t}"))),
+        }
+    }
+}
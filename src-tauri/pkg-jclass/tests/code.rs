@@ -0,0 +1,170 @@
+use cm_jclass::code::{Insn, Insns, Operand, Switch};
+
+const NOP: u8 = 0x00;
+const RETURN: u8 = 0xb1;
+
+fn insns(code: &[u8]) -> Vec<Insn<'_>> {
+    Insns::new(code).collect::<anyhow::Result<_>>().unwrap()
+}
+
+fn switch<'a>(insn: &Insn<'a>) -> Switch<'a> {
+    match insn.operand() {
+        Operand::Switch(s) => s,
+        _ => panic!("{} at {} is not a switch", insn.mnemonic(), insn.pc()),
+    }
+}
+
+/// `tableswitch` or `lookupswitch` after `pc` `nop`s, followed by a `return`
+fn with_switch(pc: usize, op: u8, ints: &[i32]) -> Vec<u8> {
+    let mut code = vec![NOP; pc];
+    code.push(op);
+    // Operands start at a multiple of 4 from the start of the code
+    code.resize(code.len().next_multiple_of(4), 0xFF);
+    code.extend(ints.iter().flat_map(|i| i.to_be_bytes()));
+    code.push(RETURN);
+    code
+}
+
+#[test]
+fn tableswitch_alignments() {
+    for pc in 0..4 {
+        let code = with_switch(pc, 0xaa, &[100, -1, 1, 20, 30, -40]);
+        let v = insns(&code);
+        assert_eq!(v.len(), pc + 2);
+        let (sw, ret) = (&v[pc], &v[pc + 1]);
+        assert_eq!((sw.pc(), sw.mnemonic()), (pc, "tableswitch"));
+        let s = switch(sw);
+        assert!(s.is_table());
+        assert_eq!(s.len(), 3);
+        assert_eq!(s.bounds(), Some((-1, 1)));
+        assert_eq!(s.default(), pc as i64 + 100);
+        assert_eq!(s.cases().collect::<Vec<_>>(), [(-1, pc as i64 + 20), (0, pc as i64 + 30), (1, pc as i64 - 40)]);
+        // 3 - pc bytes of padding, then 24 bytes of operands
+        assert_eq!(ret.pc(), pc + 1 + (3 - pc) + 24);
+        assert_eq!(ret.mnemonic(), "return");
+    }
+    // A single case at the top of the range
+    let code = with_switch(1, 0xaa, &[8, i32::MAX, i32::MAX, 12]);
+    let s = switch(&insns(&code)[1]);
+    assert_eq!(s.bounds(), Some((i32::MAX, i32::MAX)));
+    assert_eq!(s.cases().collect::<Vec<_>>(), [(i32::MAX, 13)]);
+    // The highest key may not be below the lowest
+    let code = with_switch(2, 0xaa, &[8, 1, 0]);
+    assert!(Insns::new(&code).nth(2).unwrap().is_err());
+}
+
+#[test]
+fn lookupswitch_alignments() {
+    for pc in 0..4 {
+        let code = with_switch(pc, 0xab, &[-8, 3, i32::MIN, 16, -5, 24, 1000, 32]);
+        let v = insns(&code);
+        assert_eq!(v.len(), pc + 2);
+        let (sw, ret) = (&v[pc], &v[pc + 1]);
+        assert_eq!((sw.pc(), sw.mnemonic()), (pc, "lookupswitch"));
+        let s = switch(sw);
+        assert!(!s.is_table());
+        assert_eq!((s.len(), s.bounds()), (3, None));
+        assert_eq!(s.default(), pc as i64 - 8);
+        assert_eq!(s.cases().collect::<Vec<_>>(), [(i32::MIN, pc as i64 + 16), (-5, pc as i64 + 24), (1000, pc as i64 + 32)]);
+        assert_eq!(ret.pc(), pc + 1 + (3 - pc) + 32);
+    }
+    let code = with_switch(3, 0xab, &[4, 0]);
+    let s = switch(&insns(&code)[3]);
+    assert!(s.is_empty());
+    assert_eq!(s.cases().count(), 0);
+}
+
+#[test]
+fn wide() {
+    // wide iinc 256 -200, wide iload 258, wide astore 65535, wide ret 1, iload 7
+    let code = [0xc4, 0x84, 0x01, 0x00, 0xff, 0x38, 0xc4, 0x15, 0x01, 0x02, 0xc4, 0x3a, 0xff, 0xff, 0xc4, 0xa9, 0x00, 0x01, 0x15, 0x07];
+    let v = insns(&code);
+    let got = v.iter().map(|i| (i.pc(), i.opcode(), i.is_wide(), i.mnemonic())).collect::<Vec<_>>();
+    assert_eq!(got, [
+        (0, 0x84, true, "iinc"),
+        (6, 0x15, true, "iload"),
+        (10, 0x3a, true, "astore"),
+        (14, 0xa9, true, "ret"),
+        (18, 0x15, false, "iload"),
+    ]);
+    assert!(matches!(v[0].operand(), Operand::Iinc(256, -200)));
+    assert!(matches!(v[1].operand(), Operand::Local(258)));
+    assert!(matches!(v[2].operand(), Operand::Local(65535)));
+    assert!(matches!(v[3].operand(), Operand::Local(1)));
+    assert!(matches!(v[4].operand(), Operand::Local(7)));
+    // Only loads, stores, `ret` and `iinc` can be widened
+    for op in [0x10, 0x1a, 0x60, 0xa7, 0xc4] {
+        let code = [0xc4, op, 0, 1, 0, 1];
+        let mut it = Insns::new(&code);
+        assert!(it.next().unwrap().is_err(), "wide 0x{op:02x}");
+        assert!(it.next().is_none());
+    }
+}
+
+/// Code using every operand layout, with 0 to 3 bytes of switch padding
+fn all_operands(pad: usize) -> Vec<u8> {
+    let mut code = vec![NOP; pad];
+    code.extend([
+        0x10, 0xfe, // bipush -2
+        0x11, 0x80, 0x00, // sipush -32768
+        0x12, 0x05, // ldc 5
+        0x13, 0x01, 0x02, // ldc_w 258
+        0x15, 0x03, // iload 3
+        0x1c, // iload_2
+        0x84, 0x02, 0xff, // iinc 2 -1
+        0x99, 0x00, 0x10, // ifeq +16
+        0xc8, 0x00, 0x00, 0x01, 0x00, // goto_w +256
+        0xb9, 0x00, 0x07, 0x02, 0x00, // invokeinterface #7 2
+        0xba, 0x00, 0x09, 0x00, 0x00, // invokedynamic #9
+        0xbc, 0x0a, // newarray int
+        0xc5, 0x00, 0x0b, 0x02, // multianewarray #11 2
+        0xc4, 0x84, 0x01, 0x00, 0x00, 0x05, // wide iinc 256 5
+        0xc4, 0x19, 0x01, 0x00, // wide aload 256
+    ]);
+    code.push(0xaa);
+    code.resize(code.len().next_multiple_of(4), 0);
+    code.extend([1, 0, 1, 2, 4].iter().flat_map(|i: &i32| i.to_be_bytes()));
+    code.push(0xab);
+    code.resize(code.len().next_multiple_of(4), 0);
+    code.extend([1, 2, -3, 4, 5, 6].iter().flat_map(|i: &i32| i.to_be_bytes()));
+    code.push(RETURN);
+    code
+}
+
+#[test]
+fn truncated_operands() {
+    for pad in 0..4 {
+        let code = all_operands(pad);
+        let v = insns(&code);
+        assert_eq!(v.len(), pad + 18);
+        let ends = v.iter().map(Insn::pc).skip(1).chain([code.len()]).collect::<Vec<_>>();
+        for n in 0..code.len() {
+            let r = Insns::new(&code[..n]).collect::<Vec<_>>();
+            let whole = ends.iter().take_while(|&&e| e <= n).count();
+            assert!(r[..whole].iter().all(Result::is_ok), "cut to {n}");
+            if ends.contains(&n) || n == 0 {
+                assert_eq!(r.len(), whole, "cut to {n}");
+            } else {
+                // The iterator stops after the error
+                assert_eq!(r.len(), whole + 1, "cut to {n}");
+                assert!(r[whole].is_err(), "cut to {n}");
+            }
+        }
+    }
+}
+
+#[test]
+fn invalid_opcodes() {
+    for op in 0xca..=0xff {
+        let code = [NOP, op, NOP];
+        let mut it = Insns::new(&code);
+        assert!(it.next().unwrap().is_ok());
+        assert!(it.next().unwrap().is_err(), "0x{op:02x}");
+        assert!(it.next().is_none());
+    }
+    // Table sizes past the end of the code
+    let code = with_switch(0, 0xaa, &[0, 0, i32::MAX]);
+    assert!(Insns::new(&code).next().unwrap().is_err());
+    let code = with_switch(0, 0xab, &[0, -1]);
+    assert!(Insns::new(&code).next().unwrap().is_err());
+}
@@ -0,0 +1,88 @@
+//! Classes of `tests/fixtures/crash` that ran readers out of stack: element values nested
+//! 200000 arrays deep, descriptors with 65000 array dimensions, and a signature nesting
+//! 13000 type arguments. Every reader must reject them with errors.
+
+use std::path::PathBuf;
+
+use cm_jclass::{
+    attr::{Annotation, Data, ElemValue, JAttr},
+    jtype::{self, ClassSig},
+    verify::verify,
+    write::ClassFile,
+    JClassReader,
+};
+
+fn read(name: &str) -> Vec<u8> {
+    std::fs::read(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/crash").join(name)).unwrap()
+}
+
+fn annotation(an: &Data<Annotation>) -> anyhow::Result<()> {
+    for e in an.elems() {
+        elem_value(e?.value()?)?;
+    }
+    Ok(())
+}
+fn elem_value(v: ElemValue) -> anyhow::Result<()> {
+    match v {
+        ElemValue::Annotation(an) => annotation(&an),
+        ElemValue::Array(mut it) => it.try_for_each(|v| elem_value(v?.value()?)),
+        _ => Ok(()),
+    }
+}
+
+/// Reads the class attributes, rendering the signature and walking annotations.
+fn class_attrs(data: &[u8]) -> anyhow::Result<()> {
+    let jcr = JClassReader::new(data)?;
+    let (_, attrs) = jcr.skip_interfaces()?.skip_fields()?.skip_methods()?.attributes()?;
+    for ai in attrs {
+        let ai = ai?;
+        match ai.kind()? {
+            JAttr::RuntimeVisibleAnnotations(k) => for an in ai.read(&k)? {
+                annotation(&an?)?;
+            },
+            JAttr::Signature(k) => {
+                ClassSig::from_sig(&ai.read(&k)?.to_str()?)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn edit(data: &[u8]) -> anyhow::Result<()> {
+    let mut class = ClassFile::parse(data)?;
+    class.compact_pool()?;
+    Ok(())
+}
+
+#[test]
+fn deep_annotation() {
+    let data = read("deep-annotation.class");
+    assert!(class_attrs(&data).is_err());
+    assert!(edit(&data).is_err());
+    assert!(!verify(&data).is_empty());
+}
+
+#[test]
+fn deep_descriptor() {
+    let data = read("deep-descriptor.class");
+    let jcr = JClassReader::new(&*data).unwrap();
+    let (jcr, fields) = jcr.skip_interfaces().unwrap().fields().unwrap();
+    for f in fields {
+        let f = f.unwrap();
+        assert!(jtype::render_field(&f.name().unwrap().to_str_lossy(), &f.descriptor().unwrap().to_str_lossy(), None).is_err());
+    }
+    let (_, methods) = jcr.methods().unwrap();
+    for m in methods {
+        let m = m.unwrap();
+        assert!(jtype::render_method(&m.name().unwrap().to_str_lossy(), &m.descriptor().unwrap().to_str_lossy(), None).is_err());
+    }
+    assert_eq!(verify(&data).len(), 2);
+}
+
+#[test]
+fn deep_signature() {
+    let data = read("deep-signature.class");
+    assert!(class_attrs(&data).is_err());
+    assert!(!verify(&data).is_empty());
+}
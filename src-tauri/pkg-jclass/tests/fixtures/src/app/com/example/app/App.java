@@ -0,0 +1,2 @@
+package com.example.app;
+public class App { public static void main(String[] a) { new com.example.lib.Task().run(); } }
@@ -0,0 +1 @@
+module com.example.app { requires com.example.lib; }
@@ -0,0 +1,69 @@
+package fixture;
+
+import java.lang.annotation.*;
+import java.util.*;
+import java.util.function.*;
+
+/** Sealed hierarchy, records, enums, lambdas and nested annotations */
+public sealed interface Shapes permits Shapes.Circle, Shapes.Rect, Shapes.Empty {
+    @Retention(RetentionPolicy.RUNTIME)
+    @Target({ElementType.TYPE, ElementType.METHOD, ElementType.TYPE_USE, ElementType.PARAMETER})
+    @interface Tag {
+        String value() default "shape";
+        int[] sizes() default {1, 2};
+        Kind kind() default Kind.ROUND;
+        Class<?> type() default Object.class;
+        Meta[] meta() default {@Meta(name = "a"), @Meta(name = "b", level = 2.5)};
+    }
+
+    @Retention(RetentionPolicy.CLASS)
+    @interface Meta {
+        String name();
+        double level() default 1.0;
+    }
+
+    enum Kind { ROUND, SQUARE, NONE }
+
+    double area();
+
+    default String describe() {
+        if (this instanceof Circle c) return "circle of radius " + c.r();
+        if (this instanceof Rect r) return "rect " + r.w() + "x" + r.h();
+        return "empty";
+    }
+
+    @Tag(value = "circle", sizes = {3}, meta = {@Meta(name = "c", level = -1)})
+    record Circle(double r) implements Shapes {
+        public Circle {
+            if (r < 0) throw new IllegalArgumentException("negative radius: " + r);
+        }
+        public double area() { return Math.PI * r * r; }
+    }
+
+    @Tag(kind = Kind.SQUARE, type = int[].class)
+    record Rect(@Tag("width") double w, double h) implements Shapes {
+        public double area() { return w * h; }
+    }
+
+    final class Empty implements Shapes {
+        public double area() { return 0; }
+    }
+
+    static <T extends Shapes & Comparable<? super T>> List<@Tag("sorted") T> sorted(Collection<? extends T> in, Predicate<? super T> keep) {
+        var out = new ArrayList<T>();
+        for (T t : in) {
+            if (keep.test(t)) out.add(t);
+        }
+        out.sort(Comparator.naturalOrder());
+        return out;
+    }
+
+    static Map<String, DoubleSupplier> areas(Shapes... shapes) {
+        var m = new LinkedHashMap<String, DoubleSupplier>();
+        for (int i = 0; i < shapes.length; i++) {
+            Shapes s = shapes[i];
+            m.put(s.describe() + '#' + i, s::area);
+        }
+        return m;
+    }
+}
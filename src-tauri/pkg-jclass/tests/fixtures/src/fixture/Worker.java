@@ -0,0 +1,80 @@
+package fixture;
+
+import java.io.*;
+import java.util.concurrent.*;
+
+/** Exception tables, synchronization, inner and local classes, constants of every type */
+public class Worker<V> implements Callable<V>, Serializable {
+    private static final long serialVersionUID = 42L;
+    public static final String NAME = "worker\u0000😀";
+    static final float RATIO = 0.75f;
+    static final double LIMIT = 1e300;
+    static final char SEP = 'é';
+    static final byte SMALL = -3;
+    static final short MID = 30000;
+    static final boolean ON = true;
+
+    private final Callable<V> task;
+    private transient volatile int attempts;
+    private final Object lock = new Object();
+
+    public Worker(Callable<V> task) {
+        this.task = task;
+    }
+
+    @Override
+    public V call() throws Exception {
+        synchronized (lock) {
+            attempts++;
+        }
+        try (var w = new StringWriter(); var p = new PrintWriter(w)) {
+            return task.call();
+        } catch (IOException | IllegalStateException e) {
+            throw new ExecutionException(e);
+        } finally {
+            attempts--;
+        }
+    }
+
+    class Retry implements Runnable {
+        int left = 3;
+        public void run() {
+            while (left-- > 0) {
+                try {
+                    call();
+                    return;
+                } catch (Exception e) {
+                    if (left == 0) throw new RuntimeException(e);
+                }
+            }
+        }
+    }
+
+    static int classify(Object o) {
+        class Local {
+            int weight(String s) { return s.length() * 31; }
+        }
+        if (o instanceof String s && !s.isEmpty()) {
+            return new Local().weight(s);
+        }
+        long[][] grid = new long[3][4];
+        int sum = 0;
+        for (long[] row : grid) {
+            sum += row.length;
+        }
+        switch (sum) {
+            case 1: return 10;
+            case 12: return 20;
+            case 1000: return 30;
+            default: return sum > 100 ? Integer.MAX_VALUE : Long.hashCode(Long.MIN_VALUE);
+        }
+    }
+
+    static String kind(String s) {
+        switch (s) {
+            case "a": return "first";
+            case "b": return "second";
+            default: return new Object() { public String toString() { return "other " + s; } }.toString();
+        }
+    }
+}
@@ -0,0 +1,7 @@
+package com.example.lib;
+
+public class Task implements Runnable {
+    public void run() {
+        System.getLogger("task").log(System.Logger.Level.INFO, "run");
+    }
+}
@@ -0,0 +1,9 @@
+/** Module with every directive */
+module com.example.lib {
+    requires transitive java.logging;
+    requires static java.sql;
+    exports com.example.lib;
+    opens com.example.lib to java.base;
+    uses java.lang.Runnable;
+    provides java.lang.Runnable with com.example.lib.Task;
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use cm_jclass::jtype::{render_field, render_method, render_type, ClassSig, ClassType, JavaType, MethodSig};
+
+#[test]
+fn array_dimensions() {
+    let max = format!("{}I", "[".repeat(255));
+    assert_eq!(JavaType::from_desc(&max).unwrap().to_sig(), max);
+    assert!(JavaType::from_desc(&format!("{}I", "[".repeat(256))).is_err());
+    assert!(JavaType::from_desc(&format!("{}I", "[".repeat(65000))).is_err());
+    assert!(MethodSig::from_desc(&format!("({}I)V", "[".repeat(65000))).is_err());
+}
+
+#[test]
+fn generic_nesting() {
+    let nested = |n| format!("{}Ljava/lang/Object;{}", "Ljava/util/List<".repeat(n), ">;".repeat(n));
+    assert!(JavaType::from_sig(&nested(100)).is_ok());
+    assert!(JavaType::from_sig(&nested(13000)).is_err());
+    assert!(ClassSig::from_sig(&format!("<T:{}>Ljava/lang/Object;", nested(13000))).is_err());
+    let arrays = format!("{}Ljava/lang/Object;{}", "Ljava/util/List<[".repeat(13000), ">;".repeat(13000));
+    assert!(JavaType::from_sig(&arrays).is_err());
+}
+
+#[test]
+fn class_signatures() {
+    let cases = [
+        (
+            "<K:Ljava/lang/Object;V:Ljava/lang/Object;>Ljava/util/AbstractMap<TK;TV;>;Ljava/util/Map<TK;TV;>;Ljava/io/Serializable;",
+            "<K, V> extends AbstractMap<K, V> implements Map<K, V>, Serializable",
+        ),
+        ("<E:Ljava/lang/Enum<TE;>;>Ljava/lang/Object;Ljava/lang/Comparable<TE;>;", "<E extends Enum<E>> extends Object implements Comparable<E>"),
+        // Only an interface bound, so the class bound is empty
+        ("<T::Ljava/lang/Comparable<-TT;>;>Ljava/lang/Object;", "<T extends Comparable<? super T>> extends Object"),
+        (
+            "<T:Ljava/lang/Number;:Ljava/lang/Comparable<TT;>;:Ljava/io/Serializable;>Ljava/lang/Object;",
+            "<T extends Number & Comparable<T> & Serializable> extends Object",
+        ),
+        ("<U:Ljava/lang/Object;>Lcom/example/Outer<Ljava/lang/String;>.Inner<TU;>;", "<U> extends Outer<String>.Inner<U>"),
+    ];
+    for (sig, java) in cases {
+        let cs = ClassSig::from_sig(sig).unwrap();
+        assert_eq!(cs.to_sig(), sig);
+        assert_eq!(cs.to_string(), java);
+    }
+    let cs = ClassSig::from_sig(cases[2].0).unwrap();
+    assert_eq!(cs.type_params[0].class_bound, None);
+    assert_eq!(cs.type_params[0].interface_bounds.len(), 1);
+}
+
+#[test]
+fn method_signatures() {
+    let cases = [
+        ("<T:Ljava/lang/Object;>(Ljava/util/List<+TT;>;Ljava/util/Comparator<-TT;>;)V", "<T> void m(List<? extends T>, Comparator<? super T>)"),
+        ("<T:Ljava/lang/Object;>([TT;I)[Ljava/util/Map<*Ljava/lang/String;>;", "<T> Map<?, String>[] m(T[], int)"),
+        (
+            "<E:Ljava/lang/Exception;>(Ljava/util/concurrent/Callable<TE;>;)TE;^TE;^Ljava/io/IOException;",
+            "<E extends Exception> E m(Callable<E>) throws E, IOException",
+        ),
+        (
+            "(Lcom/example/Outer<TT;>.Inner<Ljava/lang/Integer;>.Deeper;)Ljava/util/Map$Entry<TK;TV;>;",
+            "Map.Entry<K, V> m(Outer<T>.Inner<Integer>.Deeper)",
+        ),
+        ("(Ljava/lang/Class<*>;[[J)Ljava/util/List<+[Ljava/lang/Number;>;", "List<? extends Number[]> m(Class<?>, long[][])"),
+    ];
+    for (sig, java) in cases {
+        let ms = MethodSig::from_sig(sig).unwrap();
+        assert_eq!(ms.to_sig(), sig);
+        assert_eq!(ms.render("m"), java);
+    }
+    let ms = MethodSig::from_sig(cases[2].0).unwrap();
+    assert_eq!(ms.throws, [JavaType::Var("E".into()), JavaType::from_desc("Ljava/io/IOException;").unwrap()]);
+    let JavaType::Class(c) = &MethodSig::from_sig(cases[3].0).unwrap().params[0] else { panic!("not a class") };
+    assert_eq!(c.internal_name(), "com/example/Outer$Inner$Deeper");
+    assert_eq!(c.inner.iter().map(|i| (&*i.name, i.args.len())).collect::<Vec<_>>(), [("Inner", 1), ("Deeper", 0)]);
+    // Type variables and wildcards are not allowed in descriptors
+    assert!(MethodSig::from_desc(cases[0].0).is_err());
+    assert!(MethodSig::from_sig("(TT)V").is_err());
+    assert!(MethodSig::from_sig("(Ljava/util/List<+>;)V").is_err());
+}
+
+#[test]
+fn rendering() {
+    assert_eq!(render_type("Ljava/util/Map<Ljava/lang/String;+[Ljava/lang/Number;>;").unwrap(), "Map<String, ? extends Number[]>");
+    assert_eq!(render_type("V").unwrap(), "void");
+    let field = render_field("names", "Ljava/util/List;", Some("Ljava/util/List<Ljava/lang/String;>;")).unwrap();
+    assert_eq!(field, "List<String> names");
+    // An invalid signature falls back to the descriptor
+    assert_eq!(render_method("get", "(I)Ljava/lang/Object;", Some("<T>(I)TT;")).unwrap(), "Object get(int)");
+    assert_eq!(render_method("get", "(I)Ljava/lang/Object;", Some("<T:Ljava/lang/Object;>(I)TT;")).unwrap(), "<T> T get(int)");
+}
+
+fn names<'a>(it: impl Iterator<Item = &'a ClassType>) -> Vec<String> {
+    it.map(ClassType::internal_name).collect()
+}
+
+#[test]
+fn classes() {
+    let ms = MethodSig::from_sig("<T:Ljava/lang/Number;>(Ljava/util/List<+TT;>;Lcom/example/Outer<TT;>.Inner<Ljava/lang/Integer;>;)[Ljava/util/Set<*>;^Ljava/io/IOException;").unwrap();
+    let expected = ["java/lang/Number", "java/util/List", "com/example/Outer$Inner", "java/lang/Integer", "java/util/Set", "java/io/IOException"];
+    assert_eq!(names(ms.classes()), expected);
+    let cs = ClassSig::from_sig("<E::Ljava/lang/Comparable<TE;>;>Ljava/util/AbstractList<TE;>;Ljava/util/RandomAccess;Ljava/util/function/Function<-TE;+Ljava/lang/String;>;").unwrap();
+    let expected = ["java/lang/Comparable", "java/util/AbstractList", "java/util/RandomAccess", "java/util/function/Function", "java/lang/String"];
+    assert_eq!(names(cs.classes()), expected);
+    let t = JavaType::from_sig("[[Ljava/util/Map<TK;Ljava/util/List<Ljava/lang/Byte;>;>;").unwrap();
+    assert_eq!(names(t.classes()), ["java/util/Map", "java/util/List", "java/lang/Byte"]);
+    assert_eq!(names(JavaType::from_desc("I").unwrap().classes()), Vec::<String>::new());
+}
+
+#[test]
+fn map_classes() {
+    let map = HashMap::from([
+        ("com/example/Outer", "a/b"),
+        ("com/example/Outer$Inner", "a/b$c"),
+        ("java/util/List", "x/L"),
+        // Inner classes keep their outer class
+        ("com/example/Other$Nested", "z/y$Moved"),
+    ]);
+    let mut f = |n: &str| map.get(n).map(|&m| m.into());
+    let mut ms = MethodSig::from_sig("<T:Ljava/util/List<TT;>;>(Lcom/example/Outer<TT;>.Inner<Ljava/util/List<*>;>.Deeper;)Lcom/example/Other.Nested;").unwrap();
+    ms.map_classes(&mut f);
+    assert_eq!(ms.to_sig(), "<T:Lx/L<TT;>;>(La/b<TT;>.c<Lx/L<*>;>.Deeper;)Lcom/example/Other.Nested;");
+    let mut cs = ClassSig::from_sig("<T:Ljava/lang/Object;>Lcom/example/Outer<TT;>;Ljava/lang/Iterable<Ljava/util/List<TT;>;>;").unwrap();
+    cs.map_classes(&mut f);
+    assert_eq!(cs.to_sig(), "<T:Ljava/lang/Object;>La/b<TT;>;Ljava/lang/Iterable<Lx/L<TT;>;>;");
+    let mut t = JavaType::from_desc("[Lcom/example/Outer$Inner;").unwrap();
+    t.map_classes(&mut f);
+    assert_eq!(t.to_sig(), "[La/b$c;");
+}
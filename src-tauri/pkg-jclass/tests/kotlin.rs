@@ -0,0 +1,159 @@
+//! Decodes the `@kotlin.Metadata` of the classes in `tests/fixtures/kotlin`, taken from
+//! `kotlin-stdlib` 1.8 (Apache License 2.0): a class, an object, a data class and a file facade
+//! with an extension function.
+
+use std::path::PathBuf;
+
+use cm_jclass::{attr::JAttr, kotlin::KotlinMetadata, pool::JVal, JClassReader};
+
+/// `@kotlin.Metadata` of a fixture, with the names and descriptors of its fields and methods
+struct Fixture {
+    meta: KotlinMetadata,
+    members: Vec<(String, String)>,
+    kind: i32,
+    d1: Vec<String>,
+    d2: Vec<String>,
+}
+
+fn fixture(name: &str) -> Fixture {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/kotlin").join(name);
+    let data = std::fs::read(path).unwrap();
+    let jcr = JClassReader::new(&data[..]).unwrap();
+    let (jcr, _) = jcr.interfaces().unwrap();
+    let (jcr, fields) = jcr.fields().unwrap();
+    let mut members = fields.map(|f| {
+        let f = f.unwrap();
+        (f.name().unwrap().to_string(), f.descriptor().unwrap().to_string())
+    }).collect::<Vec<_>>();
+    let (jcr, methods) = jcr.methods().unwrap();
+    members.extend(methods.map(|m| {
+        let m = m.unwrap();
+        (m.name().unwrap().to_string(), m.descriptor().unwrap().to_string())
+    }));
+    let (_, attrs) = jcr.attributes().unwrap();
+    for ai in attrs {
+        let ai = ai.unwrap();
+        let JAttr::RuntimeVisibleAnnotations(k) = ai.kind().unwrap() else { continue };
+        for an in ai.read(&k).unwrap() {
+            let an = an.unwrap();
+            let Some(meta) = KotlinMetadata::from_annotation(&an).unwrap() else { continue };
+            let strings = |v: Option<Vec<&cm_jclass::JStr>>| v.unwrap().iter().map(|s| s.to_string()).collect::<Vec<_>>();
+            let (mut kind, mut d1, mut d2) = (1, vec![], vec![]);
+            for e in an.elems() {
+                let e = e.unwrap();
+                match &**e.name().unwrap() {
+                    b"k" => if let Some(JVal::Int(k)) = e.const_value().unwrap() {
+                        kind = k;
+                    },
+                    b"d1" => d1 = strings(e.str_array().unwrap()),
+                    b"d2" => d2 = strings(e.str_array().unwrap()),
+                    _ => {}
+                }
+            }
+            return Fixture { meta, members, kind, d1, d2 };
+        }
+    }
+    panic!("{name} has no Kotlin metadata");
+}
+
+impl Fixture {
+    fn declaration(&self, name: &str, desc: &str) -> Option<String> {
+        assert!(self.members.iter().any(|(n, d)| n == name && d == desc), "no member {name}{desc}");
+        self.meta.declaration_of(name, desc)
+    }
+}
+
+#[test]
+fn class() {
+    let f = fixture("NoSuchFileException.class");
+    assert_eq!(f.meta.describe(), "class kotlin.io.NoSuchFileException : FileSystemException");
+    assert_eq!(
+        f.declaration("<init>", "(Ljava/io/File;Ljava/io/File;Ljava/lang/String;)V").as_deref(),
+        Some("constructor(file: File, other: File?, reason: String?)"),
+    );
+    // The synthetic constructor for default arguments has no declaration of its own
+    assert_eq!(f.declaration("<init>", "(Ljava/io/File;Ljava/io/File;Ljava/lang/String;ILkotlin/jvm/internal/DefaultConstructorMarker;)V"), None);
+}
+
+#[test]
+fn object() {
+    let f = fixture("Unit.class");
+    assert_eq!(f.meta.describe(), "object kotlin.Unit");
+    assert_eq!(f.declaration("<init>", "()V").as_deref(), Some("private constructor()"));
+    assert_eq!(f.declaration("toString", "()Ljava/lang/String;").as_deref(), Some("open fun toString(): String"));
+    assert_eq!(f.declaration("INSTANCE", "Lkotlin/Unit;"), None);
+}
+
+#[test]
+fn data_class() {
+    let f = fixture("Pair.class");
+    assert_eq!(f.meta.describe(), "data class kotlin.Pair<out A, out B> : Serializable");
+    let KotlinMetadata::Class(c) = &f.meta else { panic!("not a class") };
+    assert!(c.is_data());
+    let cases = [
+        ("first", "Ljava/lang/Object;", "val first: A (field)"),
+        ("getFirst", "()Ljava/lang/Object;", "val first: A (getter)"),
+        ("getSecond", "()Ljava/lang/Object;", "val second: B (getter)"),
+        ("<init>", "(Ljava/lang/Object;Ljava/lang/Object;)V", "constructor(first: A, second: B)"),
+        ("component1", "()Ljava/lang/Object;", "operator fun component1(): A"),
+        ("copy", "(Ljava/lang/Object;Ljava/lang/Object;)Lkotlin/Pair;", "fun copy(first: A, second: B): Pair<A, B>"),
+        ("equals", "(Ljava/lang/Object;)Z", "open operator fun equals(other: Any?): Boolean"),
+    ];
+    for (name, desc, decl) in cases {
+        assert_eq!(f.declaration(name, desc).as_deref(), Some(decl), "{name}{desc}");
+    }
+    assert_eq!(f.declaration("copy$default", "(Lkotlin/Pair;Ljava/lang/Object;Ljava/lang/Object;ILjava/lang/Object;)Lkotlin/Pair;"), None);
+}
+
+#[test]
+fn file_facade_with_extension() {
+    let f = fixture("HashCodeKt.class");
+    assert_eq!(f.meta.describe(), "file facade");
+    let m = f.meta.members().unwrap();
+    assert_eq!(m.functions.len(), 1);
+    assert!(m.functions[0].is_extension());
+    // The receiver is the first JVM parameter
+    assert_eq!(f.declaration("hashCode", "(Ljava/lang/Object;)I").as_deref(), Some("inline fun Any?.hashCode(): Int"));
+}
+
+#[test]
+fn truncated_d1_fails() {
+    for name in ["NoSuchFileException.class", "Unit.class", "Pair.class", "HashCodeKt.class"] {
+        let f = fixture(name);
+        let chars = f.d1.concat().chars().collect::<Vec<_>>();
+        assert_eq!(chars[0], '\0', "{name} is not in the UTF-8 mode");
+        // The string table comes first, as a message with a varint length
+        let (mut len, mut shift, mut at) = (0, 0, 1);
+        while chars[at] as u32 >= 0x80 {
+            len |= (chars[at] as usize & 0x7F) << shift;
+            shift += 7;
+            at += 1;
+        }
+        let table_end = at + 1 + (len | (chars[at] as usize) << shift);
+        // Prefixes ending on a field boundary are valid messages, but none may panic
+        for n in 1..chars.len() {
+            let cut = [chars[..n].iter().collect::<String>()];
+            let r = KotlinMetadata::decode(f.kind, &cut, f.d2.clone(), None);
+            if (2..table_end).contains(&n) {
+                assert!(r.is_err(), "{name} cut to {n} chars in the string table decoded");
+            }
+        }
+    }
+}
+
+#[test]
+fn invalid_d1_d2_fail() {
+    let f = fixture("Pair.class");
+    // Strings referring past the end of d2
+    let r = KotlinMetadata::decode(f.kind, &f.d1, vec![], None);
+    assert!(r.is_err());
+    // Characters which are not bytes
+    let mut d1 = f.d1.clone();
+    d1[0].push('\u{2603}');
+    assert!(KotlinMetadata::decode(f.kind, &d1, f.d2.clone(), None).is_err());
+    // A length prefix larger than the data
+    let d1 = ["\0\u{7f}\u{1}".to_string()];
+    assert!(KotlinMetadata::decode(f.kind, &d1, f.d2, None).is_err());
+    // Classes must have metadata
+    assert!(KotlinMetadata::decode(1, &[], vec![], None).is_err());
+}
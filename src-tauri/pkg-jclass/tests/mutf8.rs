@@ -0,0 +1,85 @@
+use std::borrow::Cow;
+
+use cm_jclass::mutf8::{check, decode, decode_lossy, encode};
+
+/// U+1F600 as the CESU-8 encoding of its surrogate pair D83D DE00
+const GRINNING: &[u8] = b"\xED\xA0\xBD\xED\xB8\x80";
+const HIGH: &[u8] = b"\xED\xA0\xBD";
+const LOW: &[u8] = b"\xED\xB8\x80";
+
+#[test]
+fn nul() {
+    assert_eq!(encode("\0"), &b"\xC0\x80"[..]);
+    assert_eq!(encode("a\0b"), &b"a\xC0\x80b"[..]);
+    assert_eq!(decode(b"\xC0\x80").unwrap(), "\0");
+    assert_eq!(decode(b"a\xC0\x80b").unwrap(), "a\0b");
+    // A raw NUL byte and other overlong sequences are invalid
+    assert!(decode(b"\0").is_err());
+    assert!(decode(b"\xC1\x81").is_err());
+    assert!(decode(b"\xE0\x80\x80").is_err());
+}
+
+#[test]
+fn surrogate_pairs() {
+    assert_eq!(encode("\u{1F600}"), GRINNING);
+    assert_eq!(decode(GRINNING).unwrap(), "\u{1F600}");
+    assert_eq!(decode_lossy(GRINNING), "\u{1F600}");
+    assert_eq!(encode("\u{10000}\u{10FFFF}"), &b"\xED\xA0\x80\xED\xB0\x80\xED\xAF\xBF\xED\xBF\xBF"[..]);
+    assert_eq!(decode(b"\xED\xA0\x80\xED\xB0\x80\xED\xAF\xBF\xED\xBF\xBF").unwrap(), "\u{10000}\u{10FFFF}");
+    // The 4-byte UTF-8 form is not modified UTF-8
+    assert!(decode("\u{1F600}".as_bytes()).is_err());
+    assert!(check("\u{1F600}".as_bytes()).is_err());
+}
+
+#[test]
+fn unpaired_surrogates() {
+    let cases: [(&[u8], &str); 5] = [
+        (HIGH, "\u{FFFD}"),
+        (LOW, "\u{FFFD}"),
+        (&[LOW, HIGH].concat(), "\u{FFFD}\u{FFFD}"),
+        (&[b"a", HIGH, b"b"].concat(), "a\u{FFFD}b"),
+        (&[HIGH, HIGH, LOW].concat(), "\u{FFFD}\u{1F600}"),
+    ];
+    for (b, lossy) in cases {
+        assert!(decode(b).is_err(), "{b:X?}");
+        assert_eq!(decode_lossy(b), lossy, "{b:X?}");
+        // The JVM accepts them in class files
+        assert!(check(b).is_ok(), "{b:X?}");
+    }
+}
+
+#[test]
+fn truncated_sequences() {
+    let cases: [&[u8]; 6] = [b"\xC3", b"\xE2\x82", b"\xED\xA0", &GRINNING[..4], &GRINNING[..5], b"a\xE2"];
+    for b in cases {
+        assert!(decode(b).is_err(), "{b:X?}");
+        assert!(check(b).is_err(), "{b:X?}");
+        assert!(decode_lossy(b).contains('\u{FFFD}'), "{b:X?}");
+    }
+    assert_eq!(decode_lossy(b"a\xE2\x82"), "a\u{FFFD}\u{FFFD}");
+    assert_eq!(decode_lossy(&GRINNING[..5]), "\u{FFFD}\u{FFFD}\u{FFFD}");
+    // A continuation byte without a lead byte
+    assert!(decode(b"\x80").is_err());
+    assert_eq!(decode_lossy(b"a\x80b"), "a\u{FFFD}b");
+}
+
+#[test]
+fn round_trip() {
+    let cases = [
+        ("", true),
+        ("java/lang/Object", true),
+        ("h\u{E9}llo w\u{F6}rld \u{20AC} \u{65E5}\u{672C}\u{8A9E} \u{FFFF}", true),
+        ("\u{1D11E} clef and \u{1F600}", false),
+        ("mixed \0 nul, \u{7FF}\u{800} and \u{10FFFF}", false),
+    ];
+    for (s, borrowed) in cases {
+        let b = encode(s);
+        assert_eq!(matches!(b, Cow::Borrowed(_)), borrowed, "{s:?}");
+        assert!(check(&b).is_ok(), "{s:?}");
+        let d = decode(&b).unwrap();
+        assert_eq!(d, s);
+        // Text encoded the same way in UTF-8 decodes without a copy
+        assert_eq!(matches!(d, Cow::Borrowed(_)), borrowed, "{s:?}");
+        assert_eq!(decode_lossy(&b), s);
+    }
+}
@@ -0,0 +1,53 @@
+//! Round trips the class files of `tests/fixtures`, compiled from `tests/fixtures/src` with
+//! `javac -g -parameters --release 17`. `module-info.class` comes from a jmod of the `lib` module
+//! created with `--target-platform`, `--do-not-resolve-by-default` and `--hash-modules`.
+
+use std::path::PathBuf;
+
+use cm_jclass::{verify::verify, write::ClassFile};
+
+fn fixtures() -> Vec<(String, Vec<u8>)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut v: Vec<_> = std::fs::read_dir(dir).unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|x| x == "class"))
+        .map(|p| (p.file_name().unwrap().to_string_lossy().into_owned(), std::fs::read(&p).unwrap()))
+        .collect();
+    v.sort();
+    assert!(v.len() >= 13, "missing fixtures");
+    v
+}
+
+#[test]
+fn writes_back_unchanged() {
+    for (name, data) in fixtures() {
+        let class = ClassFile::parse(&data).unwrap_or_else(|e| panic!("{name}: {e}"));
+        assert!(class.to_bytes().unwrap() == data, "{name} differs");
+    }
+}
+
+#[test]
+fn compacts_and_strips() {
+    for (name, data) in fixtures() {
+        let mut class = ClassFile::parse(&data).unwrap();
+        class.compact_pool().unwrap_or_else(|e| panic!("{name}: {e}"));
+        let out = class.to_bytes().unwrap();
+        assert!(out.len() <= data.len(), "{name} grew");
+        let mut again = ClassFile::parse(&out).unwrap_or_else(|e| panic!("{name}: {e}"));
+        again.compact_pool().unwrap();
+        assert!(again.to_bytes().unwrap() == out, "{name} changed when compacted twice");
+        assert!(verify(&out).is_empty(), "{name} compacted has problems");
+        again.strip_debug().unwrap();
+        again.compact_pool().unwrap();
+        let stripped = again.to_bytes().unwrap();
+        assert!(verify(&stripped).is_empty(), "{name} stripped has problems");
+    }
+}
+
+#[test]
+fn fixtures_verify() {
+    for (name, data) in fixtures() {
+        let problems = verify(&data);
+        assert!(problems.is_empty(), "{name}: {} problems", problems.len());
+    }
+}
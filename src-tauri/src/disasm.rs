@@ -0,0 +1,225 @@
+use std::fmt::Write;
+
+use cm_jclass::{
+    attr::{Code, JAttr},
+    code::Operand,
+    flags::{self, FlagTable},
+    jtype,
+    pool::ClassPool,
+    JClassReader,
+};
+
+use crate::mappings::Remapper;
+
+const NEWARRAY_TYPES: [&str; 8] = ["boolean", "char", "float", "double", "byte", "short", "int", "long"];
+
+struct LocalVar {
+    start: u16,
+    len: u16,
+    slot: u16,
+    name: String,
+    desc: String,
+}
+
+/// Line numbers and local variable names of a method
+struct DebugInfo {
+    lines: Vec<(u16, u16)>,
+    locals: Vec<LocalVar>,
+}
+impl DebugInfo {
+    fn parse(code: &Code) -> anyhow::Result<Self> {
+        let mut lines = vec![];
+        let mut locals = vec![];
+        for ai in code.attrs() {
+            let ai = ai?;
+            match ai.kind()? {
+                JAttr::LineNumberTable(k) => {
+                    for ln in ai.read(&k)? {
+                        let ln = ln?;
+                        lines.push((ln.start_pc(), ln.line()));
+                    }
+                }
+                JAttr::LocalVariableTable(k) => {
+                    for lv in ai.read(&k)? {
+                        let lv = lv?;
+                        let (name, desc) = (lv.name()?.to_string(), lv.descriptor()?.to_string());
+                        locals.push(LocalVar { start: lv.start_pc(), len: lv.length(), slot: lv.slot(), name, desc });
+                    }
+                }
+                _ => {}
+            }
+        }
+        lines.sort_unstable();
+        Ok(Self { lines, locals })
+    }
+    fn local_name(&self, slot: u16, pc: usize) -> Option<&str> {
+        self.locals.iter()
+            .find(|l| l.slot == slot && (l.start as usize..l.start as usize + l.len as usize).contains(&pc))
+            .map(|l| l.name.as_str())
+    }
+}
+
+/// Comment for a constant pool operand, in the style of `javap -c`
+fn pool_comment(pool: &ClassPool, idx: u16) -> String {
+    let kind = match pool.item(idx).ok().map(cm_jclass::pool::PoolItem::tag_name) {
+        Some("Fieldref") => "Field",
+        Some("Methodref") => "Method",
+        Some("InterfaceMethodref") => "InterfaceMethod",
+        Some("Class") => "class",
+        Some("Integer") => "int",
+        Some("Long") => "long",
+        Some("Float") => "float",
+        Some("Double") => "double",
+        Some(x) => x,
+        None => "",
+    };
+    match pool.display_at(idx) {
+        Ok(s) => format!("{kind} {s}"),
+        Err(e) => format!("<{e}>"),
+    }
+}
+
+fn write_insn(out: &mut String, pc: usize, mnemonic: &str, operand: impl std::fmt::Display, comment: Option<String>) {
+    let line = format!("{pc:>10}: {mnemonic:<14}{operand}");
+    match comment {
+        Some(c) => writeln!(out, "{line:<44}// {c}"),
+        None => writeln!(out, "{}", line.trim_end()),
+    }.ok();
+}
+
+fn disassemble_code(out: &mut String, code: &Code, debug: &DebugInfo, pool: &ClassPool) -> anyhow::Result<()> {
+    for insn in code.insns() {
+        let insn = insn?;
+        let pc = insn.pc();
+        let mn = if insn.is_wide() { format!("wide {}", insn.mnemonic()) } else { insn.mnemonic().to_string() };
+        let mn = mn.as_str();
+        let local = |slot: u16| debug.local_name(slot, pc).map(ToString::to_string);
+        match insn.operand() {
+            Operand::None => write_insn(out, pc, mn, "", None),
+            Operand::Int(x) => write_insn(out, pc, mn, x, None),
+            // invokedynamic is followed by two zero bytes
+            Operand::Pool(idx) if insn.opcode() == 0xba => write_insn(out, pc, mn, format!("#{idx},  0"), Some(pool_comment(pool, idx))),
+            Operand::Pool(idx) => write_insn(out, pc, mn, format!("#{idx}"), Some(pool_comment(pool, idx))),
+            // Loads and stores with an implicit slot (`iload_0` to `astore_3`)
+            Operand::Local(slot) if matches!(insn.opcode(), 0x1a..=0x2d | 0x3b..=0x4e) => write_insn(out, pc, mn, "", local(slot)),
+            Operand::Local(slot) => write_insn(out, pc, mn, slot, local(slot)),
+            Operand::Iinc(slot, n) => write_insn(out, pc, mn, format!("{slot}, {n}"), local(slot)),
+            Operand::Branch(target) => write_insn(out, pc, mn, target, None),
+            Operand::Interface(idx, count) => write_insn(out, pc, mn, format!("#{idx},  {count}"), Some(pool_comment(pool, idx))),
+            Operand::MultiArray(idx, dims) => write_insn(out, pc, mn, format!("#{idx},  {dims}"), Some(pool_comment(pool, idx))),
+            Operand::NewArray(t) => {
+                let name = t.checked_sub(4).and_then(|i| NEWARRAY_TYPES.get(i as usize)).unwrap_or(&"?");
+                write_insn(out, pc, mn, format!(" {name}"), None);
+            }
+            Operand::Switch(sw) => {
+                match sw.bounds() {
+                    Some((low, high)) => writeln!(out, "{pc:>10}: {mn:<14}{{ // {low} to {high}"),
+                    None => writeln!(out, "{pc:>10}: {mn:<14}{{ // {}", sw.len()),
+                }.ok();
+                for (k, target) in sw.cases() {
+                    writeln!(out, "{k:>26}: {target}").ok();
+                }
+                writeln!(out, "{:>26}: {}", "default", sw.default()).ok();
+                writeln!(out, "{:>14}", "}").ok();
+            }
+        }
+    }
+    Ok(())
+}
+
+fn access_line(access: u16, table: FlagTable) -> String {
+    let names = flags::names(access, table)
+        .map(|n| format!("ACC_{}", n.to_uppercase()))
+        .collect::<Vec<_>>();
+    format!("(0x{access:04x}) {}", names.join(", "))
+}
+
+fn method_matches(filter: &str, name: &str, desc: &str) -> bool {
+    filter == name || format!("{name}{desc}").starts_with(filter)
+}
+
+/// Disassembles methods of a class in the style of `javap -c -l -p`.
+/// `method` filters methods by name, or by name and (a prefix of) the descriptor,
+/// in the original names or the names of `remapper`, which are also used for the output.
+pub fn disassemble(data: &[u8], method: Option<&str>, remapper: Option<&Remapper>) -> anyhow::Result<String> {
+    let jcr = JClassReader::new(data)?;
+    let pool = jcr.pool().clone();
+    let mut out = String::new();
+    let (major, minor) = jcr.version();
+    let class_flags = flags::names(jcr.access_flags(), flags::CLASS)
+        .filter(|&f| f != "super")
+        .collect::<Vec<_>>();
+    write!(out, "{} class {}", class_flags.join(" "), jcr.class_name()?).ok();
+    if let Some(s) = jcr.super_class()? {
+        write!(out, " extends {s}").ok();
+    }
+    writeln!(out, "\n  version: {major}.{minor}\n").ok();
+    let (jcr, methods) = jcr.skip_interfaces()?.skip_fields()?.methods()?;
+    drop(jcr);
+    for m in methods {
+        let mut m = m?;
+        let name = m.name()?.to_string();
+        let desc = m.descriptor()?.to_string();
+        if method.is_some_and(|f| {
+            !method_matches(f, &name, &desc) && !remapper.is_some_and(|r| {
+                method_matches(f, &r.remap_str(&name), &r.remap_str(&desc))
+            })
+        }) {
+            continue;
+        }
+        let mut sig = None;
+        let mut code = None;
+        for ai in m.attrs() {
+            let ai = ai?;
+            match ai.kind()? {
+                JAttr::Signature(k) => sig = Some(ai.read(&k)?.to_string()),
+                JAttr::Code(k) => code = Some((ai, k)),
+                _ => {}
+            }
+        }
+        let decl = jtype::render_method(&name, &desc, sig.as_deref())
+            .unwrap_or_else(|_| format!("{name}{desc}"));
+        let mflags = flags::names(m.access_flags(), flags::METHOD).collect::<Vec<_>>();
+        writeln!(out, "  {}{}{decl};", mflags.join(" "), if mflags.is_empty() { "" } else { " " }).ok();
+        writeln!(out, "    descriptor: {desc}").ok();
+        writeln!(out, "    flags: {}", access_line(m.access_flags(), flags::METHOD)).ok();
+        let Some((ai, code)) = code else {
+            out.push('\n');
+            continue;
+        };
+        let code = ai.read(&code)?;
+        let debug = DebugInfo::parse(&code)?;
+        writeln!(out, "    Code:\n      stack={}, locals={}, code_length={}", code.max_stack(), code.max_locals(), code.code().len()).ok();
+        if let Err(e) = disassemble_code(&mut out, &code, &debug, &pool) {
+            writeln!(out, "      // {e}").ok();
+        }
+        if !code.handlers().is_empty() {
+            writeln!(out, "      Exception table:\n         from    to  target type").ok();
+            for h in code.handlers() {
+                let ty = match code.catch_type(h) {
+                    Ok(Some(name)) => format!("Class {name}"),
+                    Ok(None) => "any".to_string(),
+                    Err(e) => format!("<{e}>"),
+                };
+                writeln!(out, "        {:>5} {:>5} {:>5}   {ty}", h.start_pc(), h.end_pc(), h.handler_pc()).ok();
+            }
+        }
+        if !debug.lines.is_empty() {
+            writeln!(out, "      LineNumberTable:").ok();
+            for (pc, line) in &debug.lines {
+                writeln!(out, "        line {line}: {pc}").ok();
+            }
+        }
+        if !debug.locals.is_empty() {
+            writeln!(out, "      LocalVariableTable:\n        Start  Length  Slot  Name   Signature").ok();
+            for l in &debug.locals {
+                writeln!(out, "        {:>5}  {:>6}  {:>4}  {:>5}   {}", l.start, l.len, l.slot, l.name, l.desc).ok();
+            }
+        }
+        out.push('\n');
+    }
+    Ok(match remapper {
+        Some(r) => r.remap_str(&out).into_owned(),
+        None => out,
+    })
+}
@@ -0,0 +1,251 @@
+use std::{collections::HashMap, io::{Read, Seek}, sync::Arc};
+
+use cm_jclass::{
+    attr::{Annotation, AttrKey, AttrMatch, Code, Data, JAttr, KeyAnnotations},
+    jtype::{JavaType, MethodSig},
+    pool::{ClassPool, JVal, PoolRef},
+    AttrInfo, JClassReader,
+};
+use cm_zipext::FileMap;
+use serde::Serialize;
+
+use crate::{ext::Extension, id::Id, mappings::{Remap, Remapper}, slice::{iter_extend, ExtendSelf}};
+
+const FABRIC_EVENT: &str = "net/fabricmc/fabric/api/event/Event";
+/// Forge and NeoForge priorities in the order listeners are called
+const PRIORITIES: &[&str] = &["HIGHEST", "HIGH", "NORMAL", "LOW", "LOWEST"];
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventBus {
+    /// The game (Forge) event bus, the default of `@EventBusSubscriber`
+    Game,
+    Mod,
+    /// Listeners of classes without `@EventBusSubscriber`, registered at runtime
+    Manual,
+    Fabric,
+}
+
+#[derive(Serialize, Clone)]
+pub struct EventHandler {
+    /// Internal name of the event class, or `Owner.FIELD` of a Fabric event
+    event: Box<str>,
+    class: Box<str>,
+    method: Box<str>,
+    /// Forge priority, or `default` and `phase` for Fabric events
+    priority: Box<str>,
+    receive_canceled: bool,
+    bus: EventBus,
+}
+impl EventHandler {
+    fn order(&self) -> usize {
+        PRIORITIES.iter().position(|p| **p == *self.priority).unwrap_or(PRIORITIES.len())
+    }
+}
+/// Remaps an event class, or the simple owner name and the field of a Fabric event.
+fn remap_event(event: &mut Box<str>, r: &Remapper) {
+    if event.contains('.') {
+        r.text(event);
+    } else {
+        r.class(event);
+    }
+}
+impl Remap for EventHandler {
+    fn remap(&mut self, r: &Remapper) {
+        remap_event(&mut self.event, r);
+        r.member(&self.class, &mut self.method, None);
+        r.class(&mut self.class);
+    }
+}
+
+/// Event listeners declared by a jar
+#[derive(Serialize, Default, Clone)]
+pub struct EventHandlers(Vec<EventHandler>);
+impl Remap for EventHandlers {
+    fn remap(&mut self, r: &Remapper) {
+        self.0.remap(r);
+    }
+}
+impl ExtendSelf for EventHandlers {
+    fn extend(&mut self, other: &Self) {
+        self.0.extend_from_slice(&other.0);
+    }
+}
+iter_extend!(EventHandlers);
+
+fn ends_with_class(desc: &str, simple: &str) -> bool {
+    desc.strip_suffix(';')
+        .and_then(|d| d.strip_suffix(simple))
+        .is_some_and(|d| d.ends_with(['/', '$']))
+}
+
+/// Annotations of an attribute matching `simple`, the simple name of the annotation class
+fn find_annotations<T: AttrMatch>(ai: &AttrInfo<T>, key: &AttrKey<KeyAnnotations>, simple: &str, out: &mut Vec<Data<Annotation>>) -> anyhow::Result<()> {
+    for an in ai.read(key)? {
+        let an = an?;
+        if ends_with_class(&an.type_name()?.to_string(), simple) {
+            out.push(an);
+        }
+    }
+    Ok(())
+}
+
+/// Bus named by the `bus` element of `@EventBusSubscriber`
+fn subscriber_bus(an: &Data<Annotation>) -> anyhow::Result<EventBus> {
+    for e in an.elems() {
+        let e = e?;
+        if &**e.name()? == b"bus" {
+            if let Some((_, name)) = e.enum_const()? {
+                return Ok(if &**name == b"MOD" { EventBus::Mod } else { EventBus::Game });
+            }
+        }
+    }
+    Ok(EventBus::Game)
+}
+
+/// Priority and `receiveCanceled` of `@SubscribeEvent`
+fn subscribe_options(an: &Data<Annotation>) -> anyhow::Result<(Box<str>, bool)> {
+    let mut priority = "NORMAL".into();
+    let mut receive_canceled = false;
+    for e in an.elems() {
+        let e = e?;
+        match &**e.name()? {
+            b"priority" => if let Some((_, name)) = e.enum_const()? {
+                priority = name.to_string().into_boxed_str();
+            },
+            b"receiveCanceled" => receive_canceled = matches!(e.const_value()?, Some(JVal::Int(1))),
+            _ => {}
+        }
+    }
+    Ok((priority, receive_canceled))
+}
+
+/// Event class of a listener method, from its only parameter
+fn event_of(desc: &str) -> Option<Box<str>> {
+    match MethodSig::from_desc(desc).ok()?.params.as_slice() {
+        [JavaType::Class(c)] => Some(c.name.clone()),
+        _ => None,
+    }
+}
+
+/// Finds Fabric `Event.register` calls, naming each event by the last `Event` field read before it.
+fn scan_fabric(pool: &ClassPool, code: &Code, class: &str, method: &str, out: &mut Vec<EventHandler>) -> anyhow::Result<()> {
+    let mut event = None;
+    for insn in code.insns() {
+        let insn = insn?;
+        match (insn.opcode(), insn.resolve(pool)) {
+            // getstatic
+            (0xb2, Ok(Some(PoolRef::Field(f)))) => {
+                if matches!(JavaType::from_desc(&f.desc.to_str_lossy()), Ok(JavaType::Class(c)) if &*c.name == FABRIC_EVENT) {
+                    let owner = f.owner.to_string();
+                    let simple = owner.rsplit('/').next().unwrap_or(&owner);
+                    event = Some(format!("{simple}.{}", f.name).into_boxed_str());
+                }
+            }
+            // invokevirtual
+            (0xb6, Ok(Some(PoolRef::Method(m)))) if **m.owner == *FABRIC_EVENT.as_bytes() && **m.name == *b"register" => {
+                if let Some(event) = event.take() {
+                    // The two-argument overload takes the identifier of a phase
+                    let priority = if **m.desc == *b"(Ljava/lang/Object;)V" { "default" } else { "phase" };
+                    out.push(EventHandler {
+                        event,
+                        class: class.into(),
+                        method: method.into(),
+                        priority: priority.into(),
+                        receive_canceled: false,
+                        bus: EventBus::Fabric,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Finds `@SubscribeEvent` methods and Fabric event callback registrations of a jar.
+pub fn gather_event_handlers<RS: Read + Seek>(fm: &FileMap, rs: &mut RS) -> anyhow::Result<EventHandlers> {
+    let mut v = vec![];
+    for (_, fe) in fm
+        .iter()
+        .filter(|(k, _)| Extension::Class.matches(k.as_ref()))
+    {
+        let jcr = JClassReader::new(fe.reader(rs)?)?;
+        let pool = jcr.pool().clone();
+        let class = jcr.class_name()?.to_string();
+        let (jcr, methods) = jcr.skip_interfaces()?.skip_fields()?.methods()?;
+        let mut listeners = vec![];
+        for m in methods {
+            let mut m = m?;
+            let name = m.name()?.to_string();
+            let desc = m.descriptor()?.to_string();
+            let mut subscribe = vec![];
+            for ai in m.attrs() {
+                let ai = ai?;
+                match ai.kind()? {
+                    JAttr::RuntimeVisibleAnnotations(k) => find_annotations(&ai, &k, "SubscribeEvent", &mut subscribe)?,
+                    JAttr::Code(k) => scan_fabric(&pool, &ai.read(&k)?, &class, &name, &mut v)?,
+                    _ => {}
+                }
+            }
+            if let (Some(an), Some(event)) = (subscribe.first(), event_of(&desc)) {
+                let (priority, receive_canceled) = subscribe_options(an)?;
+                listeners.push((event, name, priority, receive_canceled));
+            }
+        }
+        if listeners.is_empty() {
+            continue;
+        }
+        let mut subscriber = vec![];
+        let (_, attrs) = jcr.attributes()?;
+        for ai in attrs {
+            let ai = ai?;
+            if let JAttr::RuntimeVisibleAnnotations(k) = ai.kind()? {
+                find_annotations(&ai, &k, "EventBusSubscriber", &mut subscriber)?;
+            }
+        }
+        let bus = subscriber.first().map_or(Ok(EventBus::Manual), subscriber_bus)?;
+        for (event, method, priority, receive_canceled) in listeners {
+            v.push(EventHandler {
+                event,
+                class: class.as_str().into(),
+                method: method.into_boxed_str(),
+                priority,
+                receive_canceled,
+                bus,
+            });
+        }
+    }
+    Ok(EventHandlers(v))
+}
+
+#[derive(Serialize)]
+pub struct EventListeners {
+    event: Box<str>,
+    /// Listeners in the order they are called
+    listeners: Vec<(Id, EventHandler)>,
+}
+impl Remap for EventListeners {
+    fn remap(&mut self, r: &Remapper) {
+        remap_event(&mut self.event, r);
+        self.listeners.remap(r);
+    }
+}
+
+/// Groups the listeners of all mods by event.
+pub fn listeners_by_event(it: impl Iterator<Item = (Id, Arc<EventHandlers>)>) -> Vec<EventListeners> {
+    let mut m = HashMap::<Box<str>, Vec<(Id, EventHandler)>>::new();
+    for (id, eh) in it {
+        for h in &eh.0 {
+            m.entry(h.event.clone()).or_default().push((id, h.clone()));
+        }
+    }
+    let mut v = m.into_iter()
+        .map(|(event, mut listeners)| {
+            listeners.sort_by_key(|(_, h)| h.order());
+            EventListeners { event, listeners }
+        })
+        .collect::<Vec<_>>();
+    v.sort_by(|a, b| b.listeners.len().cmp(&a.listeners.len()).then_with(|| a.event.cmp(&b.event)));
+    v
+}
@@ -1,6 +1,6 @@
 use std::{collections::VecDeque, ffi::OsStr, hash, marker::PhantomData, ops::Deref, path::Path, sync::Arc};
 use serde::Serialize;
-use crate::{iter_extend, slice::ExtendSelf};
+use crate::{iter_extend, mappings::{Remap, Remapper}, slice::ExtendSelf};
 
 
 pub enum Extension {
@@ -54,15 +54,18 @@ impl Extension {
     }
 }
 
-#[derive(Serialize, Default)]
+#[derive(Serialize, Default, Clone)]
 pub struct Inheritance {
     pub indices: Vec<(Box<str>, usize)>,
     pub inherits: Vec<Vec<usize>>,
+    /// Names by index, the reverse of `indices`
+    #[serde(skip)]
+    names: Vec<Box<str>>,
 }
 #[allow(dead_code)]
 impl Inheritance {
     pub fn name_by_index(&self, index: usize) -> Option<&str> {
-        self.indices.iter().find(|(_, i)| *i == index).map(|(n, _)| n.as_ref())
+        self.names.get(index).map(AsRef::as_ref)
         //self.indices.binary_search_by_key(&&index, |(_, i)| i).map_or_else(|_| Cow::from(format!("#{index}")), |i| Cow::from(&self.indices[i].0))
     }
     pub fn iter_inherits(&self, index: usize) -> impl Iterator<Item = &str> + '_ {
@@ -73,7 +76,8 @@ impl Inheritance {
             Ok(i) => self.indices[i].1,
             Err(i) => {
                 let ni = self.inherits.len();
-                self.indices.insert(i, (name.to_string().into_boxed_str(), ni));
+                self.indices.insert(i, (name.into(), ni));
+                self.names.push(name.into());
                 self.inherits.push(vec![]);
                 ni
             }
@@ -85,10 +89,14 @@ impl Inheritance {
         ni
     }
     pub fn inherits(&self, index: usize, name: &str) -> bool {
+        let mut visited = vec![false; self.inherits.len()];
         let mut q = VecDeque::new();
         q.push_back(index);
         while let Some(i) = q.pop_front() {
-            if name == self.indices[i].0.as_ref() {
+            if std::mem::replace(&mut visited[i], true) {
+                continue;
+            }
+            if self.name_by_index(i) == Some(name) {
                 return true;
             }
             q.extend(self.inherits[i].iter());
@@ -96,17 +104,28 @@ impl Inheritance {
         false
     }
 }
+impl Remap for Inheritance {
+    fn remap(&mut self, r: &Remapper) {
+        for (name, _) in &mut self.indices {
+            r.class(name);
+        }
+        self.indices.sort_unstable();
+        for name in &mut self.names {
+            r.class(name);
+        }
+    }
+}
 impl ExtendSelf for Inheritance {
     fn extend(&mut self, other: &Self) {
         for (n, oi) in &other.indices {
             let i = self.find(n);
-            let v: &mut Vec<usize> = unsafe {
-                std::mem::transmute(&mut self.inherits[i])
-            };
-            for x in other.inherits[*oi].iter().filter_map(|&k| other.name_by_index(k)) {
-                let nk = self.find(x);
-                v.push(nk);
-            }
+            // Finding new names grows `inherits`, so they are all resolved before pushing
+            let found = other.inherits[*oi].iter()
+                .filter_map(|&k| other.name_by_index(k))
+                .map(|x| self.find(x))
+                .collect::<Vec<_>>();
+            let v = &mut self.inherits[i];
+            v.extend(found);
             v.dedup();
         }
     }
@@ -4,7 +4,7 @@ use anyhow::Result;
 use cm_zipext::FileMap;
 use serde::{Deserialize, Serialize};
 
-use crate::{ext::Extension, slice::{iter_extend, ExtendSelf}};
+use crate::{ext::Extension, libs, slice::{iter_extend, ExtendSelf}};
 
 #[derive(Serialize, Default)]
 pub struct ModFileTypeSizes(HashMap<Box<str>, [usize; 3]>);
@@ -26,6 +26,7 @@ pub struct ModContentSizes {
     classes: [usize; 3],
     assets: [usize; 3],
     data: [usize; 3],
+    libraries: [usize; 3],
     other: [usize; 3]
 }
 impl ExtendSelf for ModContentSizes {
@@ -35,6 +36,7 @@ impl ExtendSelf for ModContentSizes {
             self.classes[i] += other.classes[i];
             self.assets[i] += other.assets[i];
             self.data[i] += other.data[i];
+            self.libraries[i] += other.libraries[i];
             self.other[i] += other.other[i];
         }
     }
@@ -55,7 +57,8 @@ pub fn compute_file_type_sizes(fm: &FileMap) -> Result<ModFileTypeSizes> {
     })
 }
 
-pub fn compute_mod_content_sizes(fm: &FileMap) -> Result<ModContentSizes> {
+pub fn compute_mod_content_sizes(fm: &FileMap, entries: &[Box<str>]) -> Result<ModContentSizes> {
+    let roots = libs::find_library_roots(fm, entries);
     fm.iter().try_fold(ModContentSizes::default(), |mut mcs, (name, fe)| {
         let op = match name.split_once('/').map(|x| x.0) {
             Some("assets") => &mut mcs.assets,
@@ -64,6 +67,7 @@ pub fn compute_mod_content_sizes(fm: &FileMap) -> Result<ModContentSizes> {
                 Extension::Toml | Extension::Json | Extension::Properties | Extension::Mf => &mut mcs.meta,
                 _ => &mut mcs.other
             }
+            _ if libs::library_of(&roots, name).is_some() => &mut mcs.libraries,
             _ => match Extension::from_path(name.as_ref()) {
                 Extension::Class => &mut mcs.classes,
                 Extension::Json => &mut mcs.meta,
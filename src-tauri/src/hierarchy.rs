@@ -0,0 +1,239 @@
+use std::{collections::{HashMap, VecDeque}, io::{Read, Seek}, sync::Arc};
+
+use cm_jclass::{flags::ACC_INTERFACE, JClassBytesReader};
+use cm_zipext::FileMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{ext::Extension, id::Id, mappings::{Remap, Remapper}};
+
+const OBJECT: &str = "java/lang/Object";
+/// Largest page returned by a single query
+const MAX_LIMIT: usize = 1000;
+
+struct ClassDecl {
+    name: Box<str>,
+    super_class: Option<Box<str>>,
+    interfaces: Box<[Box<str>]>,
+    interface: bool,
+}
+
+/// Classes declared by a jar with their direct supertypes
+pub struct ClassDecls(Vec<ClassDecl>);
+
+pub fn gather_class_decls<RS: Read + Seek>(fm: &FileMap, rs: &mut RS) -> anyhow::Result<ClassDecls> {
+    let mut v = vec![];
+    for (_, fe) in fm
+        .iter()
+        .filter(|(k, _)| Extension::Class.matches(k.as_ref()))
+    {
+        let jcr = JClassBytesReader::new(fe.vec_from(rs)?)?;
+        let name = jcr.class_name()?.to_string().into_boxed_str();
+        let super_class = jcr.super_class()?.map(|s| s.to_string().into_boxed_str());
+        let interface = jcr.access_flags() & ACC_INTERFACE != 0;
+        let interfaces = jcr.interfaces()?
+            .map(|i| Ok(i?.to_string().into_boxed_str()))
+            .collect::<anyhow::Result<_>>()?;
+        v.push(ClassDecl { name, super_class, interfaces, interface });
+    }
+    Ok(ClassDecls(v))
+}
+
+#[derive(Default)]
+struct Node {
+    name: Box<str>,
+    interface: bool,
+    /// Mods declaring the class, empty for classes only referenced as supertypes
+    declared: Vec<Id>,
+    super_class: Option<usize>,
+    interfaces: Vec<usize>,
+    subclasses: Vec<usize>,
+    implementors: Vec<usize>,
+}
+
+/// Class hierarchy of the whole workspace, with edges in both directions.
+/// Classes declared by more than one mod keep the supertypes of the first one.
+pub struct Hierarchy {
+    nodes: Vec<Node>,
+    indices: HashMap<Box<str>, usize>,
+    /// Node indices sorted by name
+    sorted: Vec<usize>,
+}
+impl Hierarchy {
+    pub fn new(it: impl Iterator<Item = (Id, Arc<ClassDecls>)>) -> Self {
+        let mut h = Self { nodes: vec![], indices: HashMap::new(), sorted: vec![] };
+        for (id, decls) in it {
+            for d in &decls.0 {
+                let i = h.find_or_insert(&d.name);
+                let first = h.nodes[i].declared.is_empty();
+                h.nodes[i].declared.push(id);
+                if !first {
+                    continue;
+                }
+                h.nodes[i].interface = d.interface;
+                if let Some(s) = &d.super_class {
+                    let si = h.find_or_insert(s);
+                    h.nodes[i].super_class = Some(si);
+                    h.nodes[si].subclasses.push(i);
+                }
+                for iface in &d.interfaces {
+                    let ii = h.find_or_insert(iface);
+                    h.nodes[ii].interface = true;
+                    h.nodes[i].interfaces.push(ii);
+                    h.nodes[ii].implementors.push(i);
+                }
+            }
+        }
+        h.sorted = (0..h.nodes.len()).collect();
+        h.sorted.sort_by(|&a, &b| h.nodes[a].name.cmp(&h.nodes[b].name));
+        h
+    }
+    fn find_or_insert(&mut self, name: &str) -> usize {
+        if let Some(&i) = self.indices.get(name) {
+            return i;
+        }
+        let i = self.nodes.len();
+        self.nodes.push(Node { name: name.into(), ..Node::default() });
+        self.indices.insert(name.into(), i);
+        i
+    }
+    /// Finds a class by the name shown with the mappings of the remapper, or by its original name.
+    fn index_of(&self, name: &str, remapper: Option<&Remapper>) -> Option<usize> {
+        let name = name.replace('.', "/");
+        remapper.and_then(|r| r.original_class(&name))
+            .and_then(|n| self.indices.get(n))
+            .or_else(|| self.indices.get(&*name))
+            .copied()
+    }
+    /// Number of superclasses up to the topmost known one, which is returned with it.
+    /// The depth is exact when that class is `java/lang/Object`.
+    fn depth(&self, index: usize) -> (usize, usize) {
+        let mut chain = vec![index];
+        while let Some(s) = self.nodes[chain[chain.len() - 1]].super_class.filter(|s| !chain.contains(s)) {
+            chain.push(s);
+        }
+        (chain.len() - 1, chain[chain.len() - 1])
+    }
+    /// Breadth-first traversal of related classes with their distance, each class visited once
+    fn related(&self, index: usize, relation: Relation, transitive: bool) -> Vec<(usize, usize)> {
+        let mut visited = vec![false; self.nodes.len()];
+        visited[index] = true;
+        let mut q = VecDeque::from([(index, 0)]);
+        let mut out = vec![];
+        while let Some((i, dist)) = q.pop_front() {
+            if dist > 0 {
+                out.push((i, dist));
+                if !transitive {
+                    continue;
+                }
+            }
+            let n = &self.nodes[i];
+            let next = match relation {
+                Relation::Subtypes => n.subclasses.iter().chain(&n.implementors).copied().collect::<Vec<_>>(),
+                Relation::Supertypes => n.super_class.iter().chain(&n.interfaces).copied().collect(),
+            };
+            for j in next {
+                if !visited[j] {
+                    visited[j] = true;
+                    q.push_back((j, dist + 1));
+                }
+            }
+        }
+        out
+    }
+    fn summary(&self, index: usize) -> ClassSummary {
+        let n = &self.nodes[index];
+        let (depth, root) = self.depth(index);
+        ClassSummary {
+            name: n.name.clone(),
+            interface: n.interface,
+            declared: n.declared.clone(),
+            depth,
+            exact_depth: *self.nodes[root].name == *OBJECT,
+            subtypes: n.subclasses.len() + n.implementors.len(),
+        }
+    }
+    /// Answers a query with one page of classes.
+    /// The class and filter match original names and, if given, names of the remapper.
+    pub fn query(&self, q: &HierarchyQuery, remapper: Option<&Remapper>) -> anyhow::Result<HierarchyPage> {
+        let filter = q.filter.to_lowercase();
+        let matches = |i: usize| {
+            let name = &self.nodes[i].name;
+            filter.is_empty()
+                || name.to_lowercase().contains(&filter)
+                || remapper.is_some_and(|r| r.remap_str(name).to_lowercase().contains(&filter))
+        };
+        let (class, items) = match &q.class {
+            Some(name) => {
+                let c = self.index_of(name, remapper).ok_or_else(|| anyhow::anyhow!("class not found"))?;
+                let mut rel = self.related(c, q.relation, q.transitive);
+                rel.retain(|&(i, _)| matches(i));
+                rel.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| self.nodes[a.0].name.cmp(&self.nodes[b.0].name)));
+                (Some(self.summary(c)), rel)
+            }
+            None => (None, self.sorted.iter().filter(|&&i| matches(i)).map(|&i| (i, 0)).collect()),
+        };
+        let limit = q.limit.clamp(1, MAX_LIMIT);
+        Ok(HierarchyPage {
+            class,
+            total: items.len(),
+            items: items.into_iter()
+                .skip(q.offset)
+                .take(limit)
+                .map(|(i, dist)| (dist, self.summary(i)))
+                .collect(),
+        })
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Relation {
+    #[default]
+    Subtypes,
+    Supertypes,
+}
+
+#[derive(Deserialize)]
+pub struct HierarchyQuery {
+    /// Internal or binary name of the queried class, or `None` to list all classes.
+    /// Indices would point to other classes once the hierarchy is rebuilt, so classes are only queried by name.
+    class: Option<Box<str>>,
+    #[serde(default)]
+    relation: Relation,
+    /// Include indirect subtypes or supertypes
+    #[serde(default)]
+    transitive: bool,
+    #[serde(default)]
+    filter: String,
+    #[serde(default)]
+    offset: usize,
+    limit: usize,
+}
+
+#[derive(Serialize)]
+pub struct ClassSummary {
+    name: Box<str>,
+    interface: bool,
+    declared: Vec<Id>,
+    /// Number of superclasses, counted up to the topmost class known in the workspace
+    depth: usize,
+    /// Whether the superclass chain reaches `java/lang/Object`
+    exact_depth: bool,
+    /// Number of direct subclasses and implementors
+    subtypes: usize,
+}
+
+#[derive(Serialize)]
+pub struct HierarchyPage {
+    class: Option<ClassSummary>,
+    total: usize,
+    /// Classes with their distance from the queried class
+    items: Vec<(usize, ClassSummary)>,
+}
+impl Remap for HierarchyPage {
+    fn remap(&mut self, r: &Remapper) {
+        for c in self.class.iter_mut().chain(self.items.iter_mut().map(|(_, c)| c)) {
+            r.class(&mut c.name);
+        }
+    }
+}
@@ -54,6 +54,62 @@ pub fn get_mods_dir(mcpath: &Path) -> Option<PathBuf> {
     if meta.is_dir() { Some(mods) } else { None }
 }
 
+/// Minecraft and Java versions configured in a launcher instance
+#[derive(Serialize, Default)]
+pub struct InstanceRuntime {
+    pub minecraft: Option<Box<str>>,
+    pub java: Option<u16>,
+}
+
+/// Reads the launcher instance files around the mods directory (Prism Launcher and FTB App).
+pub fn instance_runtime(mods_dir: &Path) -> InstanceRuntime {
+    let mut rt = InstanceRuntime::default();
+    for dir in mods_dir.ancestors().skip(1).take(2) {
+        if let Ok(s) = fs::read_to_string(dir.join("instance.cfg")) {
+            rt.java = rt.java.or_else(|| s.lines().find_map(|l| l.strip_prefix("JavaVersion=")).and_then(java_feature_version));
+        }
+        if rt.minecraft.is_none() {
+            rt.minecraft = minecraft_from_instance(dir);
+        }
+    }
+    rt
+}
+
+fn minecraft_from_instance(dir: &Path) -> Option<Box<str>> {
+    if let Some(v) = read_json(&dir.join("mmc-pack.json")) {
+        return v.get("components")?.as_array()?.iter()
+            .find(|c| c.get("uid").and_then(|u| u.as_str()) == Some("net.minecraft"))?
+            .get("version")?.as_str().map(Box::from);
+    }
+    read_json(&dir.join("instance.json"))?.get("mcVersion")?.as_str().map(Box::from)
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+    serde_json::from_slice(&fs::read(path).ok()?).ok()
+}
+
+/// Converts a Java version string (`1.8.0_382`, `17.0.8`) to its feature release number.
+fn java_feature_version(s: &str) -> Option<u16> {
+    let mut it = s.trim().split(['.', '_', '+', '-']);
+    match it.next()? {
+        "1" => it.next()?.parse().ok(),
+        x => x.parse().ok(),
+    }
+}
+
+/// Java release required by vanilla Minecraft of the given version
+pub fn java_for_minecraft(v: &str) -> Option<u16> {
+    let mut it = v.split(['.', '-', ' ']).map(|x| x.parse::<u32>().ok());
+    let (major, minor, patch) = (it.next()??, it.next().flatten().unwrap_or(0), it.next().flatten().unwrap_or(0));
+    Some(match (major, minor, patch) {
+        (1, ..=16, _) => 8,
+        (1, 17, _) => 16,
+        (1, 18..=19, _) | (1, 20, ..=4) => 17,
+        (1, _, _) | (..=25, _, _) => 21,
+        _ => 25,
+    })
+}
+
 #[derive(Deserialize)]
 #[serde(untagged)]
 pub enum ReqModDirs {
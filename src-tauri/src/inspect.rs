@@ -0,0 +1,404 @@
+use cm_jclass::{
+    attr::{Annotation, Data, ElemValue, JAttr, KeyAnnotations, RecordComponent, TypeAnnotation, TypeTarget},
+    flags::{self, FlagTable},
+    jtype::{self, MemberType},
+    kotlin::KotlinMetadata,
+    pool::{JVal, PoolItem},
+    verify,
+    AttrInfo, JClassReader, MemberInfo,
+};
+use serde::Serialize;
+
+use crate::mappings::{Remap, Remapper};
+
+/// Rendered values longer than this are shortened, mostly for the data of `@kotlin.Metadata`
+const MAX_VALUE_LEN: usize = 120;
+
+#[derive(Serialize)]
+pub struct AnnotationInfo {
+    type_name: Box<str>,
+    visible: bool,
+    /// Elements as `name = value`
+    elements: Vec<Box<str>>,
+    /// Annotated parameter or type use, `None` for the declaration itself
+    target: Option<Box<str>>,
+}
+
+#[derive(Serialize)]
+pub struct MemberDetails {
+    access: Vec<&'static str>,
+    name: Box<str>,
+    descriptor: Box<str>,
+    signature: Option<Box<str>>,
+    /// Java source-style declaration
+    display: Box<str>,
+    /// Kotlin source declaration, from the metadata of the class
+    kotlin: Option<Box<str>>,
+    annotations: Vec<AnnotationInfo>,
+    /// Checked exceptions declared by a method
+    exceptions: Vec<Box<str>>,
+}
+
+#[derive(Serialize)]
+pub struct InnerClassInfo {
+    inner: Box<str>,
+    outer: Option<Box<str>>,
+    name: Option<Box<str>>,
+    access: Vec<&'static str>,
+}
+
+/// Everything stored in a single class file
+#[derive(Serialize)]
+pub struct ClassDetails {
+    version: (u16, u16),
+    access: Vec<&'static str>,
+    name: Box<str>,
+    super_class: Option<Box<str>>,
+    interfaces: Vec<Box<str>>,
+    signature: Option<Box<str>>,
+    source_file: Option<Box<str>>,
+    /// Kind and declaration of a Kotlin class
+    kotlin: Option<Box<str>>,
+    annotations: Vec<AnnotationInfo>,
+    inner_classes: Vec<InnerClassInfo>,
+    /// Class whose private members this class can access, for nested classes since Java 11
+    nest_host: Option<Box<str>>,
+    nest_members: Vec<Box<str>>,
+    /// Direct subclasses allowed by a `sealed` class
+    permitted_subclasses: Vec<Box<str>>,
+    /// Declarations of the components of a record class
+    record_components: Option<Vec<Box<str>>>,
+    fields: Vec<MemberDetails>,
+    methods: Vec<MemberDetails>,
+    /// Constant pool entries as `(index, tag, value)`
+    pool: Vec<(u16, &'static str, Box<str>)>,
+}
+
+impl AnnotationInfo {
+    fn remap(&mut self, r: &Remapper) {
+        r.text(&mut self.type_name);
+        self.elements.iter_mut().for_each(|e| r.text(e));
+    }
+}
+impl MemberDetails {
+    fn remap(&mut self, r: &Remapper, owner: &str) {
+        r.member(owner, &mut self.name, Some(&self.descriptor));
+        r.desc(&mut self.descriptor);
+        if let Some(s) = &mut self.signature {
+            r.sig(s);
+        }
+        if let Some(k) = &mut self.kotlin {
+            r.text(k);
+        }
+        self.display = boxed(render_member(&self.name, &self.descriptor, self.signature.as_deref()));
+        self.annotations.iter_mut().for_each(|a| a.remap(r));
+        self.exceptions.iter_mut().for_each(|e| r.class(e));
+    }
+}
+/// The constant pool is kept in the original names, as stored in the class file.
+impl Remap for ClassDetails {
+    fn remap(&mut self, r: &Remapper) {
+        for m in self.fields.iter_mut().chain(&mut self.methods) {
+            m.remap(r, &self.name);
+        }
+        r.class(&mut self.name);
+        for c in self.super_class.iter_mut().chain(&mut self.interfaces).chain(&mut self.nest_host)
+            .chain(&mut self.nest_members).chain(&mut self.permitted_subclasses) {
+            r.class(c);
+        }
+        if let Some(s) = &mut self.signature {
+            r.sig(s);
+        }
+        for s in self.kotlin.iter_mut().chain(self.record_components.iter_mut().flatten()) {
+            r.text(s);
+        }
+        self.annotations.iter_mut().for_each(|a| a.remap(r));
+        for ic in &mut self.inner_classes {
+            let inner = ic.inner.clone();
+            r.class(&mut ic.inner);
+            if let Some(o) = &mut ic.outer {
+                r.class(o);
+            }
+            // Simple names follow the remapped binary names
+            if let (Some(n), true) = (&mut ic.name, ic.inner != inner) {
+                *n = ic.inner.rsplit(['/', '$']).next().unwrap_or(&ic.inner).into();
+            }
+        }
+    }
+}
+
+fn boxed(s: impl ToString) -> Box<str> {
+    s.to_string().into_boxed_str()
+}
+
+fn flag_names(access: u16, table: FlagTable) -> Vec<&'static str> {
+    flags::names(access, table).collect()
+}
+
+fn type_name(desc: &str) -> String {
+    jtype::render_type(desc).unwrap_or_else(|_| desc.to_string())
+}
+
+/// Java source-style declaration of a field or method
+fn render_member(name: &str, descriptor: &str, sig: Option<&str>) -> String {
+    if descriptor.starts_with('(') {
+        jtype::render_method(name, descriptor, sig)
+    } else {
+        jtype::render_field(name, descriptor, sig)
+    }.unwrap_or_else(|_| format!("{name} {descriptor}"))
+}
+
+fn render_value(v: ElemValue) -> anyhow::Result<String> {
+    Ok(match v {
+        ElemValue::Const(b'Z', JVal::Int(x)) => (x != 0).to_string(),
+        ElemValue::Const(b'C', JVal::Int(x)) => format!("{:?}", char::from_u32(x as u32).unwrap_or(char::REPLACEMENT_CHARACTER)),
+        ElemValue::Const(_, JVal::Int(x)) => x.to_string(),
+        ElemValue::Const(_, JVal::Long(x)) => format!("{x}L"),
+        ElemValue::Const(_, JVal::Float(x)) => format!("{x}f"),
+        ElemValue::Const(_, JVal::Double(x)) => x.to_string(),
+        ElemValue::Const(_, JVal::Str(_)) => anyhow::bail!("Invalid constant element"),
+        ElemValue::Str(s) => format!("{:?}", s.to_string()),
+        ElemValue::Enum(desc, name) => format!("{}.{name}", type_name(&desc.to_string())),
+        ElemValue::Class(desc) => format!("{}.class", type_name(&desc.to_string())),
+        ElemValue::Annotation(an) => {
+            let elems = render_elems(&an)?;
+            format!("@{}({})", type_name(&an.type_name()?.to_string()), elems.join(", "))
+        }
+        ElemValue::Array(it) => {
+            let vals = it.map(|v| render_value(v?.value()?)).collect::<anyhow::Result<Vec<_>>>()?;
+            format!("{{{}}}", vals.join(", "))
+        }
+    })
+}
+
+fn render_elems(an: &Data<Annotation>) -> anyhow::Result<Vec<String>> {
+    an.elems()
+        .map(|e| {
+            let e = e?;
+            let mut v = render_value(e.value()?)?;
+            if let Some((i, _)) = v.char_indices().nth(MAX_VALUE_LEN) {
+                v.truncate(i);
+                v.push('…');
+            }
+            Ok(format!("{} = {v}", e.name()?))
+        })
+        .collect()
+}
+
+fn annotation_info(an: &Data<Annotation>, visible: bool, target: Option<String>) -> anyhow::Result<AnnotationInfo> {
+    Ok(AnnotationInfo {
+        type_name: boxed(type_name(&an.type_name()?.to_string())),
+        visible,
+        elements: render_elems(an)?.into_iter().map(boxed).collect(),
+        target: target.map(boxed),
+    })
+}
+
+fn read_annotations<T>(ai: &AttrInfo<T>, key: &cm_jclass::attr::AttrKey<KeyAnnotations>, visible: bool, out: &mut Vec<AnnotationInfo>) -> anyhow::Result<()>
+where
+    T: cm_jclass::attr::AttrMatch,
+{
+    for an in ai.read(key)? {
+        out.push(annotation_info(&an?, visible, None)?);
+    }
+    Ok(())
+}
+
+/// Describes the annotated type of a type annotation
+fn type_target(ta: &Data<TypeAnnotation>) -> String {
+    match ta.target() {
+        TypeTarget::TypeParameter(i) => format!("type parameter {i}"),
+        TypeTarget::Supertype(u16::MAX) => "superclass".into(),
+        TypeTarget::Supertype(i) => format!("interface {i}"),
+        TypeTarget::TypeParameterBound(i, b) => format!("bound {b} of type parameter {i}"),
+        TypeTarget::Empty => match ta.target_type() {
+            0x13 => "field type".into(),
+            0x14 => "return type".into(),
+            _ => "receiver type".into(),
+        },
+        TypeTarget::FormalParameter(i) => format!("type of parameter {i}"),
+        TypeTarget::Throws(i) => format!("thrown type {i}"),
+        TypeTarget::LocalVar(_) => "local variable type".into(),
+        TypeTarget::Catch(i) => format!("caught type {i}"),
+        TypeTarget::Offset(pc) => format!("type at {pc}"),
+        TypeTarget::TypeArgument(pc, i) => format!("type argument {i} at {pc}"),
+    }
+}
+
+fn read_extra_annotations<T>(ai: &AttrInfo<T>, out: &mut Vec<AnnotationInfo>) -> anyhow::Result<()>
+where
+    T: cm_jclass::attr::AttrMatch,
+{
+    let kind = ai.kind()?;
+    let visible = matches!(kind, JAttr::RuntimeVisibleParameterAnnotations(_) | JAttr::RuntimeVisibleTypeAnnotations(_));
+    match &kind {
+        JAttr::RuntimeVisibleParameterAnnotations(k) | JAttr::RuntimeInvisibleParameterAnnotations(k) => {
+            for (i, ans) in ai.read(k)?.into_iter().enumerate() {
+                for an in ans {
+                    out.push(annotation_info(&an?, visible, Some(format!("parameter {i}")))?);
+                }
+            }
+        }
+        JAttr::RuntimeVisibleTypeAnnotations(k) | JAttr::RuntimeInvisibleTypeAnnotations(k) => {
+            for ta in ai.read(k)? {
+                let ta = ta?;
+                out.push(annotation_info(&ta.annotation(), visible, Some(type_target(&ta)))?);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn member_details<T>(mut mi: MemberInfo<T>, table: FlagTable) -> anyhow::Result<MemberDetails>
+where
+    T: MemberType + cm_jclass::attr::AttrMatch,
+{
+    let name = mi.name()?.to_string();
+    let descriptor = mi.descriptor()?.to_string();
+    let mut signature = None;
+    let mut annotations = vec![];
+    let mut exceptions = vec![];
+    for ai in mi.attrs() {
+        let ai = ai?;
+        match ai.kind()? {
+            JAttr::Signature(k) => signature = Some(ai.read(&k)?.to_string()),
+            JAttr::Exceptions(k) => exceptions = ai.read(&k)?.into_iter().map(boxed).collect(),
+            JAttr::RuntimeVisibleAnnotations(k) => read_annotations(&ai, &k, true, &mut annotations)?,
+            JAttr::RuntimeInvisibleAnnotations(k) => read_annotations(&ai, &k, false, &mut annotations)?,
+            _ => read_extra_annotations(&ai, &mut annotations)?,
+        }
+    }
+    let display = render_member(&name, &descriptor, signature.as_deref());
+    Ok(MemberDetails {
+        access: flag_names(mi.access_flags(), table),
+        name: boxed(name),
+        descriptor: boxed(descriptor),
+        signature: signature.map(boxed),
+        display: boxed(display),
+        kotlin: None,
+        annotations,
+        exceptions,
+    })
+}
+
+/// Java source-style declaration of a record component
+fn record_component(rc: &Data<RecordComponent>) -> anyhow::Result<Box<str>> {
+    let (name, desc) = (rc.name()?.to_string(), rc.descriptor()?.to_string());
+    let mut signature = None;
+    for ai in rc.attrs() {
+        let ai = ai?;
+        if let JAttr::Signature(k) = ai.kind()? {
+            signature = Some(ai.read(&k)?.to_string());
+        }
+    }
+    Ok(boxed(jtype::render_field(&name, &desc, signature.as_deref()).unwrap_or_else(|_| format!("{name} {desc}"))))
+}
+
+/// Decodes `@kotlin.Metadata`, keeping the error as the description of invalid metadata
+fn read_kotlin<T>(ai: &AttrInfo<T>, key: &cm_jclass::attr::AttrKey<KeyAnnotations>) -> anyhow::Result<Option<Result<KotlinMetadata, String>>>
+where
+    T: cm_jclass::attr::AttrMatch,
+{
+    for an in ai.read(key)? {
+        match KotlinMetadata::from_annotation(&an?) {
+            Ok(Some(km)) => return Ok(Some(Ok(km))),
+            Ok(None) => {}
+            Err(e) => return Ok(Some(Err(format!("Invalid Kotlin metadata: {e}")))),
+        }
+    }
+    Ok(None)
+}
+
+pub fn class_details(data: &[u8]) -> anyhow::Result<ClassDetails> {
+    let jcr = JClassReader::new(data)?;
+    let pool = jcr.pool().clone();
+    let version = jcr.version();
+    let access = flag_names(jcr.access_flags(), flags::CLASS);
+    let name = boxed(jcr.class_name()?);
+    let super_class = jcr.super_class()?.map(boxed);
+    let (jcr, ifs) = jcr.interfaces()?;
+    let interfaces = ifs.map(|x| Ok(boxed(x?))).collect::<anyhow::Result<_>>()?;
+    let (jcr, fields) = jcr.fields()?;
+    let mut fields = fields
+        .map(|f| member_details(f?, flags::FIELD))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let (jcr, methods) = jcr.methods()?;
+    let mut methods = methods
+        .map(|m| member_details(m?, flags::METHOD))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let (_, attrs) = jcr.attributes()?;
+    let mut signature = None;
+    let mut source_file = None;
+    let mut annotations = vec![];
+    let mut inner_classes = vec![];
+    let mut nest_host = None;
+    let mut nest_members = vec![];
+    let mut permitted_subclasses = vec![];
+    let mut record_components = None;
+    let mut kotlin = None;
+    for ai in attrs {
+        let ai = ai?;
+        match ai.kind()? {
+            JAttr::Signature(k) => signature = Some(boxed(ai.read(&k)?)),
+            JAttr::SourceFile(k) => source_file = Some(boxed(ai.read(&k)?)),
+            JAttr::RuntimeVisibleAnnotations(k) => {
+                read_annotations(&ai, &k, true, &mut annotations)?;
+                kotlin = read_kotlin(&ai, &k)?;
+            }
+            JAttr::RuntimeInvisibleAnnotations(k) => read_annotations(&ai, &k, false, &mut annotations)?,
+            JAttr::InnerClasses(k) => {
+                for ic in ai.read(&k)? {
+                    let ic = ic?;
+                    inner_classes.push(InnerClassInfo {
+                        inner: boxed(ic.inner_name()?),
+                        outer: ic.outer_name()?.map(boxed),
+                        name: ic.simple_name()?.map(boxed),
+                        access: flag_names(ic.access_flags(), flags::INNER_CLASS),
+                    });
+                }
+            }
+            JAttr::NestHost(k) => nest_host = Some(boxed(ai.read(&k)?)),
+            JAttr::NestMembers(k) => nest_members = ai.read(&k)?.into_iter().map(boxed).collect(),
+            JAttr::PermittedSubclasses(k) => permitted_subclasses = ai.read(&k)?.into_iter().map(boxed).collect(),
+            JAttr::Record(k) => record_components = Some(ai.read(&k)?.map(|rc| record_component(&rc?)).collect::<anyhow::Result<_>>()?),
+            _ => read_extra_annotations(&ai, &mut annotations)?,
+        }
+    }
+    if let Some(Ok(km)) = &kotlin {
+        for m in fields.iter_mut().chain(&mut methods) {
+            m.kotlin = km.declaration_of(&m.name, &m.descriptor).map(boxed);
+        }
+    }
+    let kotlin = kotlin.map(|k| boxed(k.map_or_else(|e| e, |km| km.describe())));
+    let pool = (1..pool.len() as u16)
+        .filter_map(|i| {
+            let tag = pool.item(i).map_or("", PoolItem::tag_name);
+            (!tag.is_empty()).then(|| (i, tag, boxed(pool.display_at(i).unwrap_or_else(|e| e.to_string()))))
+        })
+        .collect();
+    Ok(ClassDetails {
+        version,
+        access,
+        name,
+        super_class,
+        interfaces,
+        signature,
+        source_file,
+        kotlin,
+        annotations,
+        inner_classes,
+        nest_host,
+        nest_members,
+        permitted_subclasses,
+        record_components,
+        fields,
+        methods,
+        pool,
+    })
+}
+
+/// Structural problems of a class file as `(offset, path, message)`
+pub fn class_problems(data: &[u8]) -> Vec<(usize, String, String)> {
+    verify::verify(data).into_iter().map(|p| (p.offset, p.path, p.message)).collect()
+}
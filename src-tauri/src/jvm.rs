@@ -1,149 +1,244 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     io::{Read, Seek},
-    sync::Mutex,
+    sync::{Arc, Mutex},
     time,
 };
 
-use cafebabe::attributes::{AnnotationElement, AnnotationElementValue, AttributeData};
 use cm_zipext::FileMap;
 use serde::Serialize;
 
-use crate::ext::{self, Extension};
-use cm_jclass::{self, pool::PoolIter, JClassReader};
+use crate::{
+    ext::{self, Extension},
+    id::Id,
+    imp::InstanceRuntime,
+    iter_extend,
+    mappings::{Remap, Remapper},
+    slice::ExtendSelf,
+};
+use cm_jclass::{
+    self,
+    attr::{self, Code, JAttr},
+    code::Operand,
+    jtype,
+    kotlin::KotlinMetadata,
+    mutf8,
+    pool::{ClassPool, PoolItem},
+    JClassBytesReader, JClassReader,
+};
 
 pub static PARSE_TIMES: std::sync::LazyLock<Mutex<HashMap<Box<str>, time::Duration>>> =
     std::sync::LazyLock::new(|| Mutex::new(HashMap::new()));
 
-fn parse_class_safe(
-    b: &[u8],
-    bytecode: bool,
-) -> Result<cafebabe::ClassFile<'_>, cafebabe::ParseError> {
-    let now = time::Instant::now();
-    match cafebabe::parse_class_with_options(
-        b,
-        cafebabe::ParseOptions::default().parse_bytecode(bytecode),
-    ) {
-        Ok(x) => {
-            PARSE_TIMES
-                .lock()
-                .unwrap()
-                .insert(x.this_class.to_string().into_boxed_str(), now.elapsed());
-            Ok(x)
-        }
-        e => e,
-    }
-}
-
-pub struct CFOwned(
-    Box<cafebabe::ClassFile<'static>>,
-    #[allow(unused)] Box<[u8]>,
-);
-impl CFOwned {
-    #[inline]
-    pub fn new(x: cafebabe::ClassFile<'static>, buf: Box<[u8]>) -> Self {
-        Self(Box::new(x), buf)
-    }
-
-    pub fn from_vec(v: Vec<u8>, bytecode: bool) -> anyhow::Result<Self> {
-        let bb = unsafe { std::mem::transmute::<&[u8], &[u8]>(v.as_slice()) };
-        Ok(Self::new(
-            parse_class_safe(bb, bytecode)?,
-            v.into_boxed_slice(),
-        ))
-    }
-}
-impl std::ops::Deref for CFOwned {
-    type Target = cafebabe::ClassFile<'static>;
-    #[inline]
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
 pub fn gather_inheritance_v2<RS: Read + Seek>(
     fm: &FileMap,
     rs: &mut RS,
 ) -> anyhow::Result<ext::Inheritance> {
-    use std::str::from_utf8;
     let mut inh = ext::Inheritance::default();
     for (_, fe) in fm
         .iter()
         .filter(|(k, _)| Extension::Class.matches(k.as_ref()))
     {
-        let cr = fe.reader(rs)?;
-        let jcr = JClassReader::new(cr)?;
-        let ajcn = jcr.class_name()?;
-        let cname = from_utf8(ajcn)?;
-        let ci = inh.find(cname);
+        let jcr = JClassBytesReader::new(fe.vec_from(rs)?)?;
+        let ci = inh.find(&jcr.class_name()?.to_str()?);
         if let Some(ajcn) = jcr.super_class()? {
-            let s = from_utf8(ajcn)?;
+            let s = ajcn.to_str()?;
             if s != "java/lang/Object" {
-                inh.add_inherit(ci, s);
+                inh.add_inherit(ci, &s);
             }
         }
-        let (_, av) = jcr.interfaces()?;
-        for ajcn in av {
-            let ajcn = ajcn?;
-            let s = from_utf8(&ajcn)?;
-            inh.add_inherit(ci, s);
+        for ajcn in jcr.interfaces()? {
+            inh.add_inherit(ci, &ajcn?.to_str()?);
         }
     }
     Ok(inh)
 }
 
-#[derive(Serialize)]
+/// HotSpot does not JIT-compile methods with more bytecode than this (`DontCompileHugeMethods`)
+const HUGE_METHOD_SIZE: usize = 8000;
+/// Methods with more bytecode than this are close to the 65535 byte limit of the JVM,
+/// which mixins and other instrumentation adding code can push them over
+const NEAR_LIMIT_METHOD_SIZE: usize = 56 * 1024;
+const LARGEST_METHODS: usize = 10;
+const ACC_BRIDGE: u16 = 0x0040;
+const ACC_SYNTHETIC: u16 = 0x1000;
+
+#[derive(Serialize, Clone)]
 pub struct Complexity(pub HashMap<Box<str>, ClassCounting>);
+/// Method declarations are rendered, so only the names found in their text are remapped.
+impl Remap for Complexity {
+    fn remap(&mut self, r: &Remapper) {
+        self.0 = std::mem::take(&mut self.0).into_iter()
+            .map(|(mut k, mut cc)| {
+                r.class(&mut k);
+                if let Some(kt) = &mut cc.kotlin {
+                    r.text(kt);
+                }
+                cc.code.iter_mut().for_each(|m| r.text(&mut m.name));
+                (k, cc)
+            })
+            .collect();
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MethodMetrics {
+    name: Box<str>,
+    opcodes: usize,
+    /// Bytecode length in bytes (limited to 65535 by the JVM)
+    size: usize,
+    cyclomatic: usize,
+    max_stack: u16,
+    max_locals: u16,
+    handlers: usize,
+    indy: usize,
+    lambdas: usize,
+}
+impl MethodMetrics {
+    fn new(name: Box<str>, code: &Code, pool: &ClassPool, lambda_bsm: &[bool]) -> anyhow::Result<Self> {
+        let mut cyclomatic = 1;
+        let (mut opcodes, mut indy, mut lambdas) = (0, 0, 0);
+        for insn in code.insns() {
+            let insn = insn?;
+            opcodes += 1;
+            match (insn.opcode(), insn.operand()) {
+                // if<cond>, if_icmp<cond>, if_acmp<cond>, ifnull, ifnonnull
+                (0x99..=0xa6 | 0xc6 | 0xc7, _) => cyclomatic += 1,
+                (_, Operand::Switch(s)) => cyclomatic += s.len(),
+                // invokedynamic
+                (0xba, Operand::Pool(idx)) => {
+                    indy += 1;
+                    if let Ok(PoolItem::InvokeDynamic(bsm, _)) = pool.item(idx) {
+                        if lambda_bsm.get(*bsm as usize).copied().unwrap_or(false) {
+                            lambdas += 1;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Self {
+            name,
+            opcodes,
+            size: code.code().len(),
+            cyclomatic,
+            max_stack: code.max_stack(),
+            max_locals: code.max_locals(),
+            handlers: code.handlers().len(),
+            indy,
+            lambdas,
+        })
+    }
+}
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ClassCounting {
     total: usize,
     fields: usize,
     methods: usize,
-    pub code: Vec<(Box<str>, usize)>,
+    cyclomatic: usize,
+    max_stack: u16,
+    max_locals: u16,
+    handlers: usize,
+    indy: usize,
+    lambdas: usize,
+    /// Size of the largest method in bytes
+    largest: usize,
+    /// Kind and declaration of a Kotlin class
+    kotlin: Option<Box<str>>,
+    pub code: Vec<MethodMetrics>,
 }
 impl Complexity {
     pub fn new() -> Self {
         Self(HashMap::new())
     }
-    fn fill_from(&mut self, cf: &cafebabe::ClassFile) {
-        let s = &cf.this_class;
-        if !self.0.contains_key(s.to_string().as_str()) {
-            let mut total = cf.fields.len() + cf.methods.len();
-            let mut v = vec![];
-            for m in &cf.methods {
-                let Some(mcode) = m.attributes.iter().find_map(|a| {
-                    if let cafebabe::attributes::AttributeData::Code(code) = &a.data {
-                        Some(code)
-                    } else {
-                        None
-                    }
-                }) else {
-                    continue;
-                };
-                let Some(bc) = &mcode.bytecode else {
-                    continue;
-                };
-                let mn = m.name.replacen("lambda$", "lambda ", 1);
-                let x = &m.descriptor;
-                total += bc.opcodes.len();
-                v.push(((format!("{mn} {x:?}")).into_boxed_str(), bc.opcodes.len()));
+    fn fill_from(&mut self, r: impl Read) -> anyhow::Result<()> {
+        let now = time::Instant::now();
+        let jcr = JClassReader::new(r)?;
+        let s = jcr.class_name()?.to_string();
+        if self.0.contains_key(s.as_str()) {
+            return Ok(());
+        }
+        let pool = jcr.pool().clone();
+        let (jcr, fields) = jcr.skip_interfaces()?.fields()?;
+        let (jcr, methods) = jcr.methods()?;
+        let (fields, n_methods) = (fields.len(), methods.len());
+        // Flags, name, descriptor, signature and code of each method with a body
+        let mut bodies = vec![];
+        for m in methods {
+            let mut m = m?;
+            let (mut code, mut sig) = (None, None);
+            for ai in m.attrs() {
+                let ai = ai?;
+                match ai.kind()? {
+                    JAttr::Code(k) => code = Some(ai.read(&k)?),
+                    JAttr::Signature(k) => sig = Some(ai.read(&k)?.to_string()),
+                    _ => {}
+                }
             }
-            if (s.to_string().ends_with("package-info") || s.to_string().ends_with("module-info"))
-                && v.is_empty()
-            {
-                return;
+            if let Some(code) = code {
+                bodies.push((m.access_flags(), m.name()?.to_string(), m.descriptor()?.to_string(), sig, code));
             }
-            self.0.insert(
-                s.to_string().into_boxed_str(),
-                ClassCounting {
-                    total,
-                    fields: cf.fields.len(),
-                    methods: cf.methods.len(),
-                    code: v,
-                },
-            );
         }
+        // Bootstrap methods of lambdas and method references
+        let mut lambda_bsm = vec![];
+        let mut kotlin = None;
+        let (_, attrs) = jcr.attributes()?;
+        for ai in attrs {
+            let ai = ai?;
+            match ai.kind()? {
+                JAttr::BootstrapMethods(k) => {
+                    lambda_bsm = ai.read(&k)?
+                        .map(|bsm| Ok(&**bsm?.owner()? == b"java/lang/invoke/LambdaMetafactory"))
+                        .collect::<anyhow::Result<_>>()?;
+                }
+                JAttr::RuntimeVisibleAnnotations(k) | JAttr::RuntimeInvisibleAnnotations(k) => {
+                    for an in ai.read(&k)? {
+                        match KotlinMetadata::from_annotation(&an?) {
+                            Ok(Some(km)) => kotlin = Some(km),
+                            Ok(None) => {}
+                            Err(e) => eprintln!("{s}: invalid Kotlin metadata: {e}"),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        PARSE_TIMES.lock().unwrap().insert(s.as_str().into(), now.elapsed());
+        let mut total = fields + n_methods;
+        let mut v = vec![];
+        for (access, name, desc, sig, code) in bodies {
+            let mn = name.replacen("lambda$", "lambda ", 1);
+            // Bridge and synthetic methods share the Kotlin declaration of the method they call
+            let label = kotlin.as_ref()
+                .filter(|_| access & (ACC_BRIDGE | ACC_SYNTHETIC) == 0)
+                .and_then(|km| km.declaration_of(&name, &desc))
+                .unwrap_or_else(|| jtype::render_method(&mn, &desc, sig.as_deref()).unwrap_or_else(|_| format!("{mn} {desc}")));
+            let mm = MethodMetrics::new(label.into_boxed_str(), &code, &pool, &lambda_bsm)?;
+            total += mm.opcodes;
+            v.push(mm);
+        }
+        if (s.ends_with("package-info") || s.ends_with("module-info")) && v.is_empty() {
+            return Ok(());
+        }
+        self.0.insert(
+            s.into_boxed_str(),
+            ClassCounting {
+                total,
+                fields,
+                methods: n_methods,
+                cyclomatic: v.iter().map(|m| m.cyclomatic).sum(),
+                max_stack: v.iter().map(|m| m.max_stack).max().unwrap_or(0),
+                max_locals: v.iter().map(|m| m.max_locals).max().unwrap_or(0),
+                handlers: v.iter().map(|m| m.handlers).sum(),
+                indy: v.iter().map(|m| m.indy).sum(),
+                lambdas: v.iter().map(|m| m.lambdas).sum(),
+                largest: v.iter().map(|m| m.size).max().unwrap_or(0),
+                kotlin: kotlin.map(|km| km.describe().into_boxed_str()),
+                code: v,
+            },
+        );
+        Ok(())
     }
     pub fn extend(&mut self, other: &Self) {
         self.0.extend(
@@ -153,6 +248,61 @@ impl Complexity {
                 .map(|(k, v)| (k.to_string().into_boxed_str(), v.clone())),
         );
     }
+    pub fn summary(&self) -> ComplexitySummary {
+        let mut cs = ComplexitySummary {
+            classes: self.0.len(),
+            ..ComplexitySummary::default()
+        };
+        let mut largest = vec![];
+        for (k, cc) in &self.0 {
+            cs.methods += cc.code.len();
+            cs.opcodes += cc.total;
+            cs.cyclomatic += cc.cyclomatic;
+            cs.handlers += cc.handlers;
+            cs.indy += cc.indy;
+            cs.lambdas += cc.lambdas;
+            for m in &cc.code {
+                cs.max_cyclomatic = cs.max_cyclomatic.max(m.cyclomatic);
+                if m.size > HUGE_METHOD_SIZE {
+                    cs.huge_methods += 1;
+                }
+                if m.size > NEAR_LIMIT_METHOD_SIZE {
+                    cs.near_limit_methods += 1;
+                }
+                largest.push((k.clone(), m.name.clone(), m.size));
+            }
+        }
+        largest.sort_by_key(|x| std::cmp::Reverse(x.2));
+        largest.truncate(LARGEST_METHODS);
+        cs.largest = largest;
+        cs
+    }
+}
+
+/// Complexity of a jar, used to rank mods by their JIT compilation and startup cost
+#[derive(Serialize, Default)]
+pub struct ComplexitySummary {
+    classes: usize,
+    methods: usize,
+    opcodes: usize,
+    cyclomatic: usize,
+    max_cyclomatic: usize,
+    handlers: usize,
+    indy: usize,
+    lambdas: usize,
+    /// Methods too large to be JIT-compiled
+    huge_methods: usize,
+    /// Huge methods close to the bytecode size limit
+    near_limit_methods: usize,
+    largest: Vec<(Box<str>, Box<str>, usize)>,
+}
+impl Remap for ComplexitySummary {
+    fn remap(&mut self, r: &Remapper) {
+        for (class, method, _) in &mut self.largest {
+            r.class(class);
+            r.text(method);
+        }
+    }
 }
 impl<R> FromIterator<R> for Complexity
 where
@@ -172,28 +322,11 @@ pub fn gather_complexity<RS: Read + Seek>(fm: &FileMap, rs: &mut RS) -> anyhow::
         .iter()
         .filter(|(k, _)| Extension::Class.matches(k.as_ref()))
     {
-        let cf = CFOwned::from_vec(fe.vec_from(rs)?, true)?;
-        cmplx.fill_from(&cf);
+        cmplx.fill_from(fe.reader(rs)?)?;
     }
     Ok(cmplx)
 }
 
-fn find_annotation<'a>(
-    cf: &'a CFOwned,
-    name: &str,
-) -> Option<&'a cafebabe::attributes::Annotation<'a>> {
-    // We compare strings to avoid constructing FieldDescriptor (whcih has private fields)
-    cf.attributes.iter().find_map(|a| {
-        match &a.data {
-            AttributeData::RuntimeVisibleAnnotations(van)
-            | AttributeData::RuntimeInvisibleAnnotations(van) => van,
-            _ => return None,
-        }
-        .iter()
-        .find(|an| an.type_descriptor.to_string() == name)
-    })
-}
-
 pub struct StrIndex {
     pub classes: Vec<Box<str>>,
     pub strings: HashMap<Box<str>, Vec<usize>>,
@@ -206,6 +339,12 @@ pub struct StrIndexMapped {
     pub classes: Slice<Box<str>>,
     pub strings: Slice<(Box<str>, Slice<usize>)>,
 }
+/// String constants are kept as they are.
+impl Remap for StrIndexMapped {
+    fn remap(&mut self, r: &Remapper) {
+        self.classes.iter_mut().for_each(|c| r.class(c));
+    }
+}
 impl From<StrIndex> for StrIndexMapped {
     fn from(x: StrIndex) -> Self {
         let classes = x.classes.into_boxed_slice();
@@ -231,11 +370,11 @@ pub fn gather_str_index_v2<RS: Read + Seek>(
         .iter()
         .filter(|(k, _)| Extension::Class.matches(k.as_ref()))
     {
-        let jcr = JClassReader::new(fe.reader(rs)?)?;
+        let jcr = JClassBytesReader::new(fe.vec_from(rs)?)?;
         let name = jcr.class_name()?.to_string().into_boxed_str();
         let sz = sidx.classes.len();
         sidx.classes.push(name);
-        for x in jcr.iter_pool().by_type::<cm_jclass::idx::Utf8>() {
+        for x in jcr.pool().utf8s() {
             sidx.strings
                 .entry(x.to_string().into_boxed_str())
                 .or_default()
@@ -245,6 +384,8 @@ pub fn gather_str_index_v2<RS: Read + Seek>(
     Ok(sidx.into())
 }
 
+const FORGE_MOD: &str = "Lnet/minecraftforge/fml/common/Mod;";
+
 #[derive(Serialize)]
 pub struct ModEntries {
     pub classes: Box<[Box<str>]>,
@@ -260,23 +401,19 @@ pub fn scan_forge_mod_entries<RS: Read + Seek>(
         .iter()
         .filter(|(k, _)| Extension::Class.matches(k.as_ref()))
     {
-        let cf = CFOwned::from_vec(fe.vec_from(rs)?, false)?;
-        let Some(a) = find_annotation(&cf, "Lnet/minecraftforge/fml/common/Mod;") else {
+        let jcr = JClassBytesReader::new(fe.vec_from(rs)?)?;
+        // Only classes referencing the annotation type need their attributes read
+        if jcr.pool().str_to_index(FORGE_MOD).is_none() {
+            continue;
+        }
+        let class = jcr.class_name()?.to_string().into_boxed_str();
+        let (_, attrs) = jcr.reader().skip_interfaces()?.skip_fields()?.skip_methods()?.attributes()?;
+        let Some(an) = attr::find_annotation(attrs, FORGE_MOD)? else {
             continue;
         };
-        for e in &a.elements {
-            if let AnnotationElement {
-                name: x,
-                value: AnnotationElementValue::StringConstant(s),
-            } = e
-            {
-                if x != "value" {
-                    continue;
-                }
-                if let Some(i) = names.iter().position(|n| *n == *s) {
-                    found[i] = Some(cf.this_class.to_string().into_boxed_str());
-                    break;
-                }
+        if let Some(e) = an.elem("value")? {
+            if let Some(i) = e.str_value()?.and_then(|s| names.iter().position(|n| *mutf8::encode(n) == **s)) {
+                found[i] = Some(class);
             }
         }
     }
@@ -294,7 +431,113 @@ pub fn scan_fabric_mod_entry<RS: Read + Seek>(
     let fe = fm
         .get(&classfile)
         .ok_or_else(|| anyhow::anyhow!("Classfile not found: {}", classfile))?;
-    let buf = fe.vec_from(rs)?;
-    let jcr = JClassReader::new(buf.as_slice())?;
+    let jcr = JClassBytesReader::new(fe.vec_from(rs)?)?;
     jcr.class_name().map(|x| x.to_string().into_boxed_str())
 }
+
+
+/// Class file major version of the first release of Java 1.1
+const JAVA_MAJOR_OFFSET: u16 = 44;
+/// Minor version marking classes compiled with preview features
+const PREVIEW_MINOR: u16 = 0xFFFF;
+
+#[derive(Serialize, Default)]
+pub struct ClassVersions {
+    /// Number of classes for each major version
+    majors: BTreeMap<u16, usize>,
+    /// Java release required by classes outside of `META-INF/versions`
+    required: Option<u16>,
+    multi_release: bool,
+    /// Java releases with their own class versions in `META-INF/versions`
+    versioned: Vec<u16>,
+    preview: Vec<Box<str>>,
+}
+impl ExtendSelf for ClassVersions {
+    fn extend(&mut self, other: &Self) {
+        for (k, v) in &other.majors {
+            *self.majors.entry(*k).or_default() += v;
+        }
+        self.required = self.required.max(other.required);
+        self.multi_release |= other.multi_release;
+        self.versioned.extend_from_slice(&other.versioned);
+        self.versioned.sort_unstable();
+        self.versioned.dedup();
+        self.preview.extend_from_slice(&other.preview);
+    }
+}
+iter_extend!(ClassVersions);
+
+pub fn gather_class_versions<RS: Read + Seek>(
+    fm: &FileMap,
+    rs: &mut RS,
+) -> anyhow::Result<ClassVersions> {
+    let mut cv = ClassVersions::default();
+    for (name, fe) in fm
+        .iter()
+        .filter(|(k, _)| Extension::Class.matches(k.as_ref()))
+    {
+        let (major, minor) = cm_jclass::read_version(&mut fe.reader(rs)?)?;
+        *cv.majors.entry(major).or_default() += 1;
+        if minor == PREVIEW_MINOR {
+            cv.preview.push(name.clone());
+        }
+        if let Some(rest) = name.strip_prefix("META-INF/versions/") {
+            if let Some(v) = rest.split_once('/').and_then(|(v, _)| v.parse().ok()) {
+                if !cv.versioned.contains(&v) {
+                    cv.versioned.push(v);
+                }
+            }
+        } else if !name.ends_with("module-info.class") {
+            cv.required = cv.required.max(Some(major.saturating_sub(JAVA_MAJOR_OFFSET)));
+        }
+    }
+    cv.versioned.sort_unstable();
+    cv.multi_release = fm.get("META-INF/MANIFEST.MF").is_some_and(|fe| {
+        fe.string_from(rs).is_ok_and(|s| {
+            s.lines()
+                .any(|l| l.trim().eq_ignore_ascii_case("Multi-Release: true"))
+        })
+    });
+    Ok(cv)
+}
+
+/// A mod requiring a newer Java release than one of the limits
+#[derive(Serialize)]
+pub struct JavaViolation {
+    id: Id,
+    required: u16,
+    /// Newer than the Java release of the instance
+    instance: bool,
+    /// Newer than the Java release implied by the Minecraft version
+    minecraft: bool,
+}
+
+#[derive(Serialize)]
+pub struct JavaCheck {
+    runtime: InstanceRuntime,
+    /// Java release implied by the Minecraft version
+    implied: Option<u16>,
+    mods: Vec<JavaViolation>,
+}
+
+/// Flags mods requiring a newer Java release than the instance provides
+/// or than the Minecraft version implies, checking each limit that is known.
+pub fn check_java_requirements(
+    runtime: InstanceRuntime,
+    it: impl Iterator<Item = (Id, Arc<ClassVersions>)>,
+) -> JavaCheck {
+    let implied = runtime
+        .minecraft
+        .as_deref()
+        .and_then(crate::imp::java_for_minecraft);
+    let exceeds = |limit: Option<u16>, req: u16| limit.is_some_and(|l| req > l);
+    let mut mods = it
+        .filter_map(|(id, cv)| {
+            let required = cv.required?;
+            let (instance, minecraft) = (exceeds(runtime.java, required), exceeds(implied, required));
+            (instance || minecraft).then_some(JavaViolation { id, required, instance, minecraft })
+        })
+        .collect::<Vec<_>>();
+    mods.sort_by_key(|m| std::cmp::Reverse(m.required));
+    JavaCheck { runtime, implied, mods }
+}
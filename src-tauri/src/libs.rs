@@ -0,0 +1,228 @@
+use std::{collections::HashMap, io::{Read, Seek}, sync::Arc};
+
+use cm_zipext::FileMap;
+use serde::Serialize;
+
+use crate::{id::Id, iter_extend, slice::ExtendSelf};
+
+struct KnownLib {
+    name: &'static str,
+    /// Package root of an unrelocated copy
+    root: &'static str,
+    /// Maven group and artifact prefix used in `META-INF/maven/<group>/<artifact>/pom.properties`
+    maven: (&'static str, &'static str),
+    /// Class path (relative to the root) used to find relocated copies, which keep the root under another package
+    marker: &'static str,
+}
+
+const fn lib(name: &'static str, root: &'static str, maven: (&'static str, &'static str), marker: &'static str) -> KnownLib {
+    KnownLib { name, root, maven, marker }
+}
+
+const KNOWN_LIBS: &[KnownLib] = &[
+    lib("Gson", "com/google/gson/", ("com.google.code.gson", "gson"), "internal/bind/TypeAdapters.class"),
+    lib("Guava", "com/google/common/", ("com.google.guava", "guava"), "collect/ImmutableList.class"),
+    lib("Kotlin", "kotlin/", ("org.jetbrains.kotlin", "kotlin-stdlib"), "jvm/internal/Intrinsics.class"),
+    lib("kotlinx.coroutines", "kotlinx/coroutines/", ("org.jetbrains.kotlinx", "kotlinx-coroutines"), "CoroutineScope.class"),
+    lib("kotlinx.serialization", "kotlinx/serialization/", ("org.jetbrains.kotlinx", "kotlinx-serialization"), "KSerializer.class"),
+    lib("Apache Commons Lang", "org/apache/commons/lang3/", ("org.apache.commons", "commons-lang3"), "builder/ToStringBuilder.class"),
+    lib("Apache Commons IO", "org/apache/commons/io/", ("commons-io", "commons-io"), "filefilter/IOFileFilter.class"),
+    lib("Apache Commons Text", "org/apache/commons/text/", ("org.apache.commons", "commons-text"), "similarity/LevenshteinDistance.class"),
+    lib("Apache Commons Math", "org/apache/commons/math3/", ("org.apache.commons", "commons-math3"), "util/FastMath.class"),
+    lib("Apache Commons Compress", "org/apache/commons/compress/", ("org.apache.commons", "commons-compress"), "archivers/ArchiveEntry.class"),
+    lib("Apache HttpClient", "org/apache/http/", ("org.apache.httpcomponents", "httpclient"), "client/HttpClient.class"),
+    lib("fastutil", "it/unimi/dsi/fastutil/", ("it.unimi.dsi", "fastutil"), "ints/IntArrayList.class"),
+    lib("Jackson", "com/fasterxml/jackson/", ("com.fasterxml.jackson.core", "jackson"), "core/JsonFactory.class"),
+    lib("SnakeYAML", "org/yaml/snakeyaml/", ("org.yaml", "snakeyaml"), "constructor/SafeConstructor.class"),
+    lib("Netty", "io/netty/", ("io.netty", "netty"), "channel/ChannelPipeline.class"),
+    lib("SLF4J", "org/slf4j/", ("org.slf4j", "slf4j-api"), "helpers/MessageFormatter.class"),
+    lib("Log4j", "org/apache/logging/log4j/", ("org.apache.logging.log4j", "log4j"), "spi/LoggerContext.class"),
+    lib("JOML", "org/joml/", ("org.joml", "joml"), "Matrix4fc.class"),
+    lib("ASM", "org/objectweb/asm/", ("org.ow2.asm", "asm"), "signature/SignatureVisitor.class"),
+    lib("MixinExtras", "com/llamalad7/mixinextras/", ("io.github.llamalad7", "mixinextras"), "MixinExtrasBootstrap.class"),
+    lib("Night Config", "com/electronwill/nightconfig/", ("com.electronwill.night-config", "core"), "core/UnmodifiableConfig.class"),
+    lib("Caffeine", "com/github/benmanes/caffeine/", ("com.github.ben-manes.caffeine", "caffeine"), "cache/Caffeine.class"),
+    lib("Jankson", "blue/endless/jankson/", ("blue.endless", "jankson"), "JsonGrammar.class"),
+    lib("toml4j", "com/moandjiezana/toml/", ("com.moandjiezana.toml", "toml4j"), "TomlWriter.class"),
+    lib("OkHttp", "okhttp3/", ("com.squareup.okhttp3", "okhttp"), "OkHttpClient.class"),
+    lib("Okio", "okio/", ("com.squareup.okio", "okio"), "BufferedSource.class"),
+    lib("JSON-java", "org/json/", ("org.json", "json"), "JSONTokener.class"),
+    lib("Javassist", "javassist/", ("org.javassist", "javassist"), "bytecode/ConstPool.class"),
+    lib("JetBrains Annotations", "org/jetbrains/annotations/", ("org.jetbrains", "annotations"), "ApiStatus.class"),
+    lib("JSR 305", "javax/annotation/", ("com.google.code.findbugs", "jsr305"), "meta/TypeQualifier.class"),
+    lib("Reflections", "org/reflections/", ("org.reflections", "reflections"), "Reflections.class"),
+    lib("Objenesis", "org/objenesis/", ("org.objenesis", "objenesis"), "ObjenesisStd.class"),
+    lib("LZ4", "net/jpountz/lz4/", ("org.lz4", "lz4"), "LZ4Factory.class"),
+    lib("zstd-jni", "com/github/luben/zstd/", ("com.github.luben", "zstd-jni"), "ZstdInputStream.class"),
+];
+
+/// Directory names commonly used by shading plugins for relocated packages
+const RELOCATION_DIRS: &[&str] = &["shadow", "shadowed", "shaded", "repack", "repackaged", "relocated", "third_party"];
+/// Top-level package names that need one more segment to name a library
+const TLD_PACKAGES: &[&str] = &["com", "org", "net", "io", "dev", "me", "de", "uk", "fr", "ca", "edu", "info", "xyz", "javax", "jakarta"];
+
+#[derive(Clone, PartialEq, Eq)]
+enum LibName {
+    Known(usize),
+    Unknown(Box<str>),
+}
+impl LibName {
+    fn name(&self) -> &str {
+        match self {
+            Self::Known(i) => KNOWN_LIBS[*i].name,
+            Self::Unknown(n) => n,
+        }
+    }
+}
+
+pub struct LibRoot {
+    lib: LibName,
+    root: Box<str>,
+    relocated: bool,
+}
+
+fn pom_coords(name: &str) -> Option<(&str, &str)> {
+    let rest = name.strip_prefix("META-INF/maven/")?.strip_suffix("/pom.properties")?;
+    rest.split_once('/')
+}
+
+/// Finds package roots of bundled libraries from entry names, leaving out packages holding the mod's entry classes.
+/// Roots are sorted from the longest, so the first matching root is the most specific one.
+pub fn find_library_roots(fm: &FileMap, entries: &[Box<str>]) -> Vec<LibRoot> {
+    let mut roots = HashMap::<Box<str>, (LibName, bool)>::new();
+    for name in fm.keys().filter(|k| k.ends_with(".class")) {
+        for (i, kl) in KNOWN_LIBS.iter().enumerate() {
+            if name.starts_with(kl.root) {
+                roots.entry(kl.root.into()).or_insert((LibName::Known(i), false));
+            } else if let Some(prefix) = name.strip_suffix(kl.marker).and_then(|r| r.strip_suffix(kl.root)).filter(|p| p.ends_with('/')) {
+                // Matching the whole path, as marker names alone like `Reflections.class` are common
+                roots.entry(format!("{prefix}{}", kl.root).into_boxed_str()).or_insert((LibName::Known(i), true));
+            }
+        }
+        let segs = name.split('/').collect::<Vec<_>>();
+        let Some(pos) = segs.iter().skip(1).position(|s| RELOCATION_DIRS.contains(s)).map(|p| p + 2) else {
+            continue;
+        };
+        let prefix_len = segs[..pos].iter().map(|s| s.len() + 1).sum::<usize>();
+        let (prefix, rest) = name.split_at(prefix_len);
+        if let Some((i, kl)) = KNOWN_LIBS.iter().enumerate().find(|(_, kl)| rest.starts_with(kl.root)) {
+            roots.entry(format!("{prefix}{}", kl.root).into_boxed_str()).or_insert((LibName::Known(i), true));
+            continue;
+        }
+        let dirs = &segs[pos..segs.len() - 1];
+        if dirs.is_empty() {
+            continue;
+        }
+        let dirs = &dirs[..dirs.len().min(if TLD_PACKAGES.contains(&dirs[0]) { 2 } else { 1 })];
+        let root = format!("{prefix}{}/", dirs.join("/")).into_boxed_str();
+        roots.entry(root).or_insert_with(|| (LibName::Unknown(dirs.join(".").into_boxed_str()), true));
+    }
+    for (group, artifact) in fm.keys().filter_map(|k| pom_coords(k)) {
+        if KNOWN_LIBS.iter().any(|kl| kl.maven.0 == group) {
+            continue;
+        }
+        let root = format!("{}/", group.replace('.', "/")).into_boxed_str();
+        // Mods built with Maven ship their own pom, which must not turn the mod into a library
+        if entries.iter().any(|e| e.starts_with(&*root)) {
+            continue;
+        }
+        if roots.keys().any(|r| root.starts_with(&**r)) || !fm.keys().any(|k| k.starts_with(&*root) && k.ends_with(".class")) {
+            continue;
+        }
+        roots.insert(root, (LibName::Unknown(format!("{group}:{artifact}").into_boxed_str()), false));
+    }
+    let mut v = roots.into_iter().map(|(root, (lib, relocated))| LibRoot { lib, root, relocated }).collect::<Vec<_>>();
+    v.sort_by(|a, b| b.root.len().cmp(&a.root.len()).then_with(|| a.root.cmp(&b.root)));
+    v
+}
+
+#[inline]
+pub fn library_of<'a>(roots: &'a [LibRoot], name: &str) -> Option<&'a LibRoot> {
+    roots.iter().find(|r| name.starts_with(&*r.root))
+}
+
+#[derive(Serialize, Clone)]
+pub struct BundledLib {
+    name: Box<str>,
+    root: Box<str>,
+    relocated: bool,
+    version: Option<Box<str>>,
+    sizes: [usize; 3],
+}
+
+#[derive(Serialize, Default)]
+pub struct ModLibraries(Vec<BundledLib>);
+impl ExtendSelf for ModLibraries {
+    fn extend(&mut self, other: &Self) {
+        self.0.extend(other.0.iter().cloned());
+    }
+}
+iter_extend!(ModLibraries);
+
+fn pom_versions<'a, RS: Read + Seek>(fm: &'a FileMap, rs: &mut RS) -> Vec<(&'a str, &'a str, Box<str>)> {
+    let mut v = vec![];
+    for (name, fe) in fm.iter() {
+        let Some((group, artifact)) = pom_coords(name) else { continue };
+        let Ok(s) = fe.string_from(rs) else { continue };
+        let Some(ver) = s.lines().find_map(|l| l.strip_prefix("version=")) else { continue };
+        v.push((group, artifact, ver.trim().into()));
+    }
+    v
+}
+
+pub fn gather_libraries<RS: Read + Seek>(fm: &FileMap, entries: &[Box<str>], rs: &mut RS) -> anyhow::Result<ModLibraries> {
+    let roots = find_library_roots(fm, entries);
+    if roots.is_empty() {
+        return Ok(ModLibraries::default());
+    }
+    let versions = pom_versions(fm, rs);
+    let mut libs = roots.iter().map(|r| BundledLib {
+        name: r.lib.name().into(),
+        root: r.root.clone(),
+        relocated: r.relocated,
+        version: versions.iter().find(|(g, a, _)| match &r.lib {
+            LibName::Known(i) => KNOWN_LIBS[*i].maven.0 == *g && a.starts_with(KNOWN_LIBS[*i].maven.1),
+            LibName::Unknown(n) => n.split_once(':') == Some((g, a)),
+        }).map(|(_, _, v)| v.clone()),
+        sizes: [0; 3],
+    }).collect::<Vec<_>>();
+    for (name, fe) in fm.iter() {
+        let Some(i) = roots.iter().position(|r| name.starts_with(&*r.root)) else { continue };
+        let op = &mut libs[i].sizes;
+        op[0] += 1;
+        op[1] += fe.size() as usize;
+        op[2] += fe.compressed() as usize;
+    }
+    libs.retain(|l| l.sizes[0] > 0);
+    libs.sort_by(|a, b| b.sizes[1].cmp(&a.sizes[1]));
+    Ok(ModLibraries(libs))
+}
+
+#[derive(Serialize)]
+pub struct SharedLib {
+    name: Box<str>,
+    copies: Vec<(Id, Option<Box<str>>, [usize; 3])>,
+}
+
+/// Lists libraries bundled by more than one mod in the workspace.
+pub fn shared_libraries(it: impl Iterator<Item = (Id, Arc<ModLibraries>)>) -> Vec<SharedLib> {
+    let mut m = HashMap::<Box<str>, Vec<(Id, Option<Box<str>>, [usize; 3])>>::new();
+    for (id, ml) in it {
+        for bl in &ml.0 {
+            let v = m.entry(bl.name.clone()).or_default();
+            if let Some(c) = v.iter_mut().find(|c| c.0 == id) {
+                for i in 0..3 {
+                    c.2[i] += bl.sizes[i];
+                }
+            } else {
+                v.push((id, bl.version.clone(), bl.sizes));
+            }
+        }
+    }
+    let mut v = m.into_iter()
+        .filter(|(_, copies)| copies.len() > 1)
+        .map(|(name, copies)| SharedLib { name, copies })
+        .collect::<Vec<_>>();
+    v.sort_by(|a, b| b.copies.len().cmp(&a.copies.len()).then_with(|| a.name.cmp(&b.name)));
+    v
+}
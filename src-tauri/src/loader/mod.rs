@@ -117,6 +117,20 @@ impl ExtendSelf for DepMap {
     }
 }
 iter_extend!(DepMap);
+impl DepMap {
+    /// Highest lower bound of the Minecraft version required by any mod
+    pub fn minecraft_version(&self) -> Option<semver::Version> {
+        self.0.iter()
+            .filter_map(|(_, _, d)| match d.get("minecraft")? {
+                VersionData(ParsedVersionReq::Correct(req), _) => Some(req),
+                _ => None
+            })
+            .flat_map(|req| &req.comparators)
+            .filter(|c| matches!(c.op, semver::Op::Exact | semver::Op::Greater | semver::Op::GreaterEq | semver::Op::Tilde | semver::Op::Caret | semver::Op::Wildcard))
+            .map(|c| semver::Version::new(c.major, c.minor.unwrap_or(0), c.patch.unwrap_or(0)))
+            .max()
+    }
+}
 
 #[derive(serde::Serialize)]
 pub struct DepMapIndexed(Indexer, Vec<Option<(Option<semver::Version>, HashMap<usize, VersionData>)>>);
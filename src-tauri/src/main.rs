@@ -1,15 +1,28 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod disasm;
 mod err;
+mod events;
 mod ext;
 mod extract;
+mod hierarchy;
 mod id;
 mod imp;
+mod inspect;
 mod jvm;
+mod libs;
 mod loader;
+mod mappings;
 mod mc;
+mod modules;
+mod native;
+mod nested;
+mod refs;
+mod registry;
 mod rt;
+mod scan;
+mod search;
 mod slice;
 mod srv;
 mod workspace;
@@ -19,6 +32,7 @@ use err::SafeResult;
 use id::Id;
 use std::{collections::HashMap, path::PathBuf, sync::Arc};
 use tauri::{command, generate_context, generate_handler, Emitter, Listener, Manager, State};
+use mappings::Remapped;
 use workspace::{AllGather, DirWS, Gatherer, WSMode};
 
 #[command]
@@ -156,10 +170,36 @@ fn ws_mod_data(state: State<'_, DirWS>, id: Id) -> Option<Arc<loader::ModTypeDat
         .ok()
 }
 #[command]
-fn ws_str_index(state: State<'_, DirWS>, id: Id) -> Option<Arc<jvm::StrIndexMapped>> {
-    ws_item(state, id, workspace::gather_str_index)
+fn ws_str_index(state: State<'_, DirWS>, id: Id) -> Option<Remapped<Arc<jvm::StrIndexMapped>>> {
+    ws_item(state.clone(), id, workspace::gather_str_index)
         .inspect_err(|e| eprintln!("Error in ws_str_index: {e}"))
         .ok()
+        .map(|v| state.remapped(v))
+}
+#[command]
+fn ws_class_paths(state: State<'_, DirWS>, id: Id) -> Option<Vec<Box<str>>> {
+    let fm = state.mods_read().get(&id)?.filemap()?;
+    Some(fm.keys()
+        .filter(|k| ext::Extension::Class.matches(k.as_ref()))
+        .cloned()
+        .collect())
+}
+#[command]
+async fn ws_class_info(state: State<'_, DirWS>, id: Id, path: String) -> Result<Remapped<inspect::ClassDetails>, ()> {
+    state.mods_read().get(&id)
+        .ok_or_else(|| anyhow::anyhow!("file not found"))
+        .and_then(|fi| workspace::read_entry(fi, &path))
+        .and_then(|data| inspect::class_details(&data))
+        .map(|v| state.remapped(v))
+        .map_err(|e| eprintln!("Error in ws_class_info: {e}"))
+}
+#[command]
+async fn ws_class_verify(state: State<'_, DirWS>, id: Id, path: String) -> Result<Vec<(usize, String, String)>, ()> {
+    state.mods_read().get(&id)
+        .ok_or_else(|| anyhow::anyhow!("file not found"))
+        .and_then(|fi| workspace::read_entry(fi, &path))
+        .map(|data| inspect::class_problems(&data))
+        .map_err(|e| eprintln!("Error in ws_class_verify: {e}"))
 }
 #[command]
 fn ws_mod_errors(state: State<'_, DirWS>, id: Id) -> Vec<workspace::FileError> {
@@ -199,19 +239,122 @@ fn ws_content_sizes(
         .ok()
 }
 #[command]
+async fn ws_libraries(
+    state: State<'_, DirWS>,
+    mode: WSMode,
+) -> Result<Arc<libs::ModLibraries>, ()> {
+    mode.gather_from_entries(state.mods(), workspace::gather_libraries)
+        .map_err(|e| eprintln!("Error in ws_libraries: {e}"))
+}
+#[command]
+async fn ws_shared_libraries(state: State<'_, DirWS>) -> Result<Vec<libs::SharedLib>, ()> {
+    let mods = state.mods().gather_with(false, workspace::gather_libraries);
+    Ok(libs::shared_libraries(
+        mods.iter()
+            .filter_map(|(id, fi)| Some((*id, fi.get::<libs::ModLibraries>()?))),
+    ))
+}
+#[command]
+async fn ws_class_versions(
+    state: State<'_, DirWS>,
+    mode: WSMode,
+) -> Result<Arc<jvm::ClassVersions>, ()> {
+    mode.gather_from_entries(state.mods(), workspace::gather_class_versions)
+        .map_err(|e| eprintln!("Error in ws_class_versions: {e}"))
+}
+#[command]
+async fn ws_java_check(state: State<'_, DirWS>) -> Result<jvm::JavaCheck, ()> {
+    let mut runtime = imp::instance_runtime(&state.dir_path());
+    if runtime.minecraft.is_none() {
+        let deps = WSMode::Generic(false).gather_from_entries(state.mods(), workspace::gather_dep_map)
+            .map_err(|e| eprintln!("Error in ws_java_check: {e}"))?;
+        runtime.minecraft = deps.minecraft_version().map(|v| v.to_string().into_boxed_str());
+    }
+    let mods = state.mods().gather_with(false, workspace::gather_class_versions);
+    Ok(jvm::check_java_requirements(
+        runtime,
+        mods.iter()
+            .filter_map(|(id, fi)| Some((*id, fi.get::<jvm::ClassVersions>()?))),
+    ))
+}
+#[command]
+async fn ws_module(state: State<'_, DirWS>, id: Id) -> Result<Arc<modules::JavaModule>, ()> {
+    state.mods().gather_by_id(id, workspace::gather_module)
+        .map_err(|e| eprintln!("Error in ws_module: {e}"))
+}
+#[command]
+async fn ws_module_check(state: State<'_, DirWS>) -> Result<modules::ModuleCheck, ()> {
+    let mods = state.mods().gather_with(false, workspace::gather_module);
+    Ok(modules::check_modules(
+        mods.iter()
+            .filter_map(|(id, fi)| Some((*id, fi.get::<modules::JavaModule>()?))),
+    ))
+}
+#[command]
+async fn ws_registries(state: State<'_, DirWS>, mode: WSMode) -> Result<Arc<registry::Registrations>, ()> {
+    mode.gather_from_entries(state.mods(), workspace::gather_registrations)
+        .map_err(|e| eprintln!("Error in ws_registries: {e}"))
+}
+#[command]
+async fn ws_registry_totals(state: State<'_, DirWS>) -> Result<registry::RegistryTotals, ()> {
+    let mut minecraft = imp::instance_runtime(&state.dir_path()).minecraft;
+    if minecraft.is_none() {
+        let deps = WSMode::Generic(false).gather_from_entries(state.mods(), workspace::gather_dep_map)
+            .map_err(|e| eprintln!("Error in ws_registry_totals: {e}"))?;
+        minecraft = deps.minecraft_version().map(|v| v.to_string().into_boxed_str());
+    }
+    let mods = state.mods().gather_with(false, workspace::gather_registrations);
+    Ok(registry::registry_totals(
+        minecraft,
+        mods.iter()
+            .filter_map(|(id, fi)| Some((*id, fi.get::<registry::Registrations>()?))),
+    ))
+}
+#[command]
+async fn ws_event_handlers(state: State<'_, DirWS>, mode: WSMode) -> Result<Remapped<Arc<events::EventHandlers>>, ()> {
+    mode.gather_from_entries(state.mods(), workspace::gather_event_handlers)
+        .map(|v| state.remapped(v))
+        .map_err(|e| eprintln!("Error in ws_event_handlers: {e}"))
+}
+#[command]
+async fn ws_event_listeners(state: State<'_, DirWS>) -> Result<Remapped<Vec<events::EventListeners>>, ()> {
+    let mods = state.mods().gather_with(false, workspace::gather_event_handlers);
+    Ok(state.remapped(events::listeners_by_event(
+        mods.iter()
+            .filter_map(|(id, fi)| Some((*id, fi.get::<events::EventHandlers>()?))),
+    )))
+}
+#[command]
 async fn ws_inheritance(
     state: State<'_, DirWS>,
     mode: WSMode,
-) -> Result<Arc<ext::Inheritance>, ()> {
+) -> Result<Remapped<Arc<ext::Inheritance>>, ()> {
     mode.gather_from_entries(state.mods(), workspace::gather_inheritance)
+        .map(|v| state.remapped(v))
         .map_err(|e| eprintln!("Error in ws_inheritance: {e}"))
 }
 #[command]
-async fn ws_complexity(state: State<'_, DirWS>, mode: WSMode) -> Result<Arc<jvm::Complexity>, ()> {
+async fn ws_hierarchy(state: State<'_, DirWS>, query: hierarchy::HierarchyQuery) -> Result<Remapped<hierarchy::HierarchyPage>, ()> {
+    let h = state.hierarchy();
+    let remapper = state.mappings().read().remapper();
+    h.query(&query, remapper.as_deref())
+        .map(|page| Remapped::new(page, remapper.as_deref()))
+        .map_err(|e| eprintln!("Error in ws_hierarchy: {e}"))
+}
+#[command]
+async fn ws_complexity(state: State<'_, DirWS>, mode: WSMode) -> Result<Remapped<Arc<jvm::Complexity>>, ()> {
     mode.gather_from_entries(state.mods(), workspace::gather_complexity)
+        .map(|v| state.remapped(v))
         .map_err(|e| eprintln!("Error in ws_complexity: {e}"))
 }
 #[command]
+async fn ws_complexity_summary(state: State<'_, DirWS>) -> Result<Remapped<Vec<(Id, jvm::ComplexitySummary)>>, ()> {
+    let mods = state.mods().gather_with(false, workspace::gather_complexity);
+    Ok(state.remapped(mods.iter()
+        .filter_map(|(id, fi)| Some((*id, fi.get::<jvm::Complexity>()?.summary())))
+        .collect()))
+}
+#[command]
 async fn ws_tags(state: State<'_, DirWS>, mode: WSMode) -> Result<Arc<extract::TagsList>, ()> {
     mode.gather_from_entries(state.mods(), workspace::gather_tags)
         .map_err(|e| eprintln!("Error in ws_tags: {e}"))
@@ -225,6 +368,164 @@ async fn ws_recipes(
         .map_err(|e| eprintln!("Error in ws_recipes: {e}"))
 }
 
+#[command]
+async fn ws_api_usage(state: State<'_, DirWS>, id: Id) -> Result<Remapped<Vec<refs::ProviderUsage>>, ()> {
+    let cr = state
+        .mods()
+        .gather_by_id(id, workspace::gather_class_refs)
+        .map_err(|e| eprintln!("Error in ws_api_usage: {e}"))?;
+    let providers = refs::Providers::new(
+        state.mods_read().iter().filter_map(|(id, fi)| Some((*id, fi.filemap()?))),
+    );
+    Ok(state.remapped(refs::usage_by_provider(&cr, &providers)))
+}
+#[command]
+async fn ws_api_search(state: State<'_, DirWS>, query: String) -> Result<Remapped<Vec<refs::ApiUsers>>, ()> {
+    let mods = state.mods().gather_with(false, workspace::gather_class_refs);
+    let providers = refs::Providers::new(
+        mods.iter().filter_map(|(id, fi)| Some((*id, fi.filemap()?))),
+    );
+    Ok(state.remapped(refs::search_users(
+        &query,
+        mods.iter()
+            .filter_map(|(id, fi)| Some((*id, fi.get::<refs::ClassRefs>()?))),
+        &providers,
+    )))
+}
+
+#[command]
+async fn ws_search(state: State<'_, DirWS>, query: String, mode: search::SearchMode) -> Result<Remapped<Vec<search::SearchHit>>, ()> {
+    let m = search::Matcher::new(&query, mode).map_err(|e| eprintln!("Error in ws_search: {e}"))?;
+    let mods = state.mods().gather_with(false, workspace::gather_str_index);
+    let strings = mods.iter()
+        .filter_map(|(id, fi)| Some((*id, fi.get::<jvm::StrIndexMapped>()?)))
+        .collect();
+    drop(mods);
+    let mods = state.mods().gather_with(false, workspace::gather_texts);
+    let texts = mods.iter()
+        .filter_map(|(id, fi)| Some((*id, fi.get::<search::ModTexts>()?)))
+        .collect();
+    drop(mods);
+    Ok(state.remapped(search::search(&m, strings, texts)))
+}
+
+#[command]
+async fn ws_native(state: State<'_, DirWS>, id: Id) -> Result<Arc<native::NativeUsage>, ()> {
+    state
+        .mods()
+        .gather_by_id(id, workspace::gather_native_usage)
+        .map_err(|e| eprintln!("Error in ws_native: {e}"))
+}
+#[command]
+async fn ws_native_summary(state: State<'_, DirWS>) -> Result<Vec<(Id, native::NativeSummary)>, ()> {
+    let mods = state.mods().gather_with(false, workspace::gather_native_usage);
+    Ok(native::summaries(
+        mods.iter()
+            .filter_map(|(id, fi)| Some((*id, fi.get::<native::NativeUsage>()?))),
+    ))
+}
+#[command]
+async fn ws_scan(state: State<'_, DirWS>, id: Id) -> Result<scan::ScanReport, ()> {
+    let data = state
+        .mods()
+        .gather_by_id(id, workspace::gather_scan_data)
+        .map_err(|e| eprintln!("Error in ws_scan: {e}"))?;
+    Ok(scan::report(&data, &state.scan_rules().read()))
+}
+#[command]
+async fn ws_scan_summary(state: State<'_, DirWS>) -> Result<Vec<(Id, scan::ScanSummary)>, ()> {
+    let mods = state.mods().gather_with(false, workspace::gather_scan_data);
+    Ok(scan::summaries(
+        mods.iter()
+            .filter_map(|(id, fi)| Some((*id, fi.get::<scan::ScanData>()?))),
+        &state.scan_rules().read(),
+    ))
+}
+#[command]
+fn ws_scan_rules(state: State<'_, DirWS>) -> scan::RulesInfo {
+    state.scan_rules().read().info()
+}
+#[command]
+async fn ws_scan_rules_load(state: State<'_, DirWS>, path: Option<PathBuf>) -> Result<scan::RulesInfo, String> {
+    let path = match path {
+        Some(p) => p,
+        None => match rfd::AsyncFileDialog::new().pick_file().await {
+            Some(f) => PathBuf::from(f),
+            None => return Ok(state.scan_rules().read().info()),
+        },
+    };
+    let rules = scan::ScanRules::load(path.clone()).map_err(|e| {
+        eprintln!("Error in ws_scan_rules_load: {e}");
+        format!("{}: {e}", path.display())
+    })?;
+    let mut sr = state.scan_rules().write();
+    *sr = rules;
+    Ok(sr.info())
+}
+#[command]
+fn ws_scan_rules_clear(state: State<'_, DirWS>) -> scan::RulesInfo {
+    let mut sr = state.scan_rules().write();
+    *sr = scan::ScanRules::default();
+    sr.info()
+}
+
+fn save_mappings(state: &DirWS, ms: &mappings::MappingState) {
+    if let Err(e) = ms.save(&state.dir_path()) {
+        eprintln!("Saving mappings error: {e}");
+    }
+}
+#[command]
+fn ws_mappings(state: State<'_, DirWS>) -> mappings::MappingsInfo {
+    state.mappings().read().info()
+}
+#[command]
+async fn ws_mappings_add(state: State<'_, DirWS>, path: Option<PathBuf>) -> Result<mappings::MappingsInfo, String> {
+    let paths = match path {
+        Some(p) => vec![p],
+        None => rfd::AsyncFileDialog::new()
+            .pick_files()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(PathBuf::from)
+            .collect(),
+    };
+    // Parsing takes a while, so the mappings are only locked to merge them
+    let loaded = paths.into_iter()
+        .map(|p| match mappings::Mappings::load(&p) {
+            Ok((m, format)) => Ok((p, m, format)),
+            Err(e) => {
+                eprintln!("Error in ws_mappings_add: {e}");
+                Err(format!("{}: {e}", p.display()))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut ms = state.mappings().write();
+    for (p, m, format) in loaded {
+        let display = p.display().to_string();
+        ms.add(p, m, format).map_err(|e| {
+            eprintln!("Error in ws_mappings_add: {e}");
+            format!("{display}: {e}")
+        })?;
+    }
+    save_mappings(&state, &ms);
+    Ok(ms.info())
+}
+#[command]
+fn ws_mappings_select(state: State<'_, DirWS>, namespace: Option<Box<str>>) -> mappings::MappingsInfo {
+    let mut ms = state.mappings().write();
+    ms.select(namespace);
+    save_mappings(&state, &ms);
+    ms.info()
+}
+#[command]
+fn ws_mappings_clear(state: State<'_, DirWS>) -> mappings::MappingsInfo {
+    let mut ms = state.mappings().write();
+    *ms = mappings::MappingState::default();
+    save_mappings(&state, &ms);
+    ms.info()
+}
+
 #[command]
 async fn ws_mod_entries(state: State<'_, DirWS>, id: Id) -> Result<Arc<jvm::ModEntries>, ()> {
     state
@@ -286,13 +587,42 @@ fn main() {
             ws_mod_data,
             ws_dep_map,
             ws_str_index,
+            ws_class_paths,
+            ws_class_info,
+            ws_class_verify,
             ws_mod_errors,
             ws_file_type_sizes,
             ws_content_sizes,
+            ws_libraries,
+            ws_shared_libraries,
+            ws_class_versions,
+            ws_java_check,
+            ws_module,
+            ws_module_check,
+            ws_registries,
+            ws_registry_totals,
+            ws_event_handlers,
+            ws_event_listeners,
             ws_inheritance,
+            ws_hierarchy,
             ws_complexity,
+            ws_complexity_summary,
             ws_tags,
             ws_mod_entries,
+            ws_api_usage,
+            ws_api_search,
+            ws_search,
+            ws_native,
+            ws_native_summary,
+            ws_scan,
+            ws_scan_summary,
+            ws_scan_rules,
+            ws_scan_rules_load,
+            ws_scan_rules_clear,
+            ws_mappings,
+            ws_mappings_add,
+            ws_mappings_select,
+            ws_mappings_clear,
             ws_recipes,
             ws_mod_playable,
             dbg_parse_times,
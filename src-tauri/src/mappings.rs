@@ -0,0 +1,773 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use cm_jclass::jtype::{BaseType, ClassSig, ClassType, JavaType, MethodSig};
+use serde::{Deserialize, Serialize};
+
+use crate::id::Id;
+
+/// Obfuscated names of Mojang, which are too short and ambiguous to be remapped without their owner class
+const OFFICIAL: &str = "official";
+/// Namespaces whose names are unique enough to be remapped without their owner class
+const UNIQUE_NAMESPACES: &[&str] = &["intermediary", "srg"];
+/// Namespaces never used as remapping sources (TSRG2 numeric IDs)
+const SKIPPED_NAMESPACES: &[&str] = &["id"];
+/// Identifier of the app, naming its config directory
+const APP_IDENTIFIER: &str = "xyz.szeweq.craftmatch";
+/// Path of Tiny mappings inside Yarn and intermediary jars
+const JAR_MAPPINGS_PATH: &str = "mappings/mappings.tiny";
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingFormat {
+    TinyV1,
+    TinyV2,
+    Tsrg,
+    Tsrg2,
+    #[serde(rename = "proguard")]
+    ProGuard,
+    Srg,
+    McpCsv,
+}
+impl MappingFormat {
+    fn detect(path: &Path, head: &str) -> Self {
+        let first = head.lines().find(|l| !l.trim().is_empty()).unwrap_or_default();
+        if first.starts_with("v1\t") {
+            Self::TinyV1
+        } else if first.starts_with("tiny\t2\t") {
+            Self::TinyV2
+        } else if first.starts_with("tsrg2 ") {
+            Self::Tsrg2
+        } else if ["PK: ", "CL: ", "FD: ", "MD: "].iter().any(|p| first.starts_with(p)) {
+            Self::Srg
+        } else if first.starts_with("searge,") || path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv")) {
+            Self::McpCsv
+        } else if first.starts_with('#') || first.ends_with(':') && first.contains(" -> ") {
+            Self::ProGuard
+        } else {
+            Self::Tsrg
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Member {
+    names: Vec<Option<Box<str>>>,
+    /// Descriptor in the first namespace of the mappings
+    desc: Option<Box<str>>,
+}
+
+#[derive(Clone)]
+struct Class {
+    names: Vec<Option<Box<str>>>,
+    fields: Vec<Member>,
+    methods: Vec<Member>,
+}
+
+/// Class, field and method names in several namespaces
+#[derive(Clone, Default)]
+pub struct Mappings {
+    namespaces: Vec<Box<str>>,
+    classes: Vec<Class>,
+}
+
+fn name(s: &str) -> Option<Box<str>> {
+    (!s.is_empty()).then(|| s.into())
+}
+
+/// Replaces class names in a field or method descriptor, keeping invalid descriptors as they are.
+fn remap_desc(desc: &str, classes: &HashMap<Box<str>, Box<str>>) -> Box<str> {
+    let mut f = |c: &str| classes.get(c).cloned();
+    let out = if desc.starts_with('(') {
+        MethodSig::from_desc(desc).map(|mut m| {
+            m.map_classes(&mut f);
+            m.to_sig()
+        })
+    } else {
+        JavaType::from_desc(desc).map(|mut t| {
+            t.map_classes(&mut f);
+            t.to_sig()
+        })
+    };
+    out.map_or_else(|_| desc.into(), String::into_boxed_str)
+}
+
+/// Replaces class names in a field, method or class signature, keeping invalid signatures as they are.
+fn remap_sig(sig: &str, classes: &HashMap<Box<str>, Box<str>>) -> Box<str> {
+    let mut f = |c: &str| classes.get(c).cloned();
+    let out = if sig.contains('(') {
+        MethodSig::from_sig(sig).map(|mut m| {
+            m.map_classes(&mut f);
+            m.to_sig()
+        })
+    } else if sig.starts_with('<') {
+        ClassSig::from_sig(sig).map(|mut c| {
+            c.map_classes(&mut f);
+            c.to_sig()
+        })
+    } else {
+        JavaType::from_sig(sig).map(|mut t| {
+            t.map_classes(&mut f);
+            t.to_sig()
+        })
+    };
+    out.map_or_else(|_| sig.into(), String::into_boxed_str)
+}
+
+/// Converts a Java source type (`int[]`, `java.lang.String`) to a descriptor.
+fn java_type_desc(t: &str) -> String {
+    let (elem, dims) = t.split_once('[').map_or((t, 0), |(e, d)| (e, d.matches(']').count() + 1));
+    let mut ty = BaseType::from_name(elem)
+        .map_or_else(|| JavaType::Class(ClassType::new(&elem.replace('.', "/"))), JavaType::Base);
+    for _ in 0..dims {
+        ty = JavaType::Array(Box::new(ty));
+    }
+    ty.to_sig()
+}
+
+impl Mappings {
+    fn with_namespaces(namespaces: &[&str]) -> Self {
+        Self { namespaces: namespaces.iter().map(|&n| n.into()).collect(), classes: vec![] }
+    }
+    pub const fn class_count(&self) -> usize {
+        self.classes.len()
+    }
+    fn class_map(&self, from: usize, to: usize) -> HashMap<Box<str>, Box<str>> {
+        self.classes.iter()
+            .filter_map(|c| Some((c.names[from].clone()?, c.names[to].clone()?)))
+            .collect()
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<(Self, MappingFormat)> {
+        let data = fs::read(path)?;
+        let text = if data.starts_with(b"PK\x03\x04") {
+            let fm = cm_zipext::FileMap::from_zip_read_seek(Cursor::new(&data))?;
+            let fe = fm.get(JAR_MAPPINGS_PATH)
+                .ok_or_else(|| anyhow::anyhow!("No {JAR_MAPPINGS_PATH} in {}", path.display()))?;
+            fe.string_from(&mut Cursor::new(&data))?
+        } else {
+            String::from_utf8(data)?
+        };
+        let format = MappingFormat::detect(path, &text);
+        let m = match format {
+            MappingFormat::TinyV1 => Self::parse_tiny_v1(&text),
+            MappingFormat::TinyV2 => Self::parse_tiny_v2(&text),
+            MappingFormat::Tsrg => Self::parse_tsrg(&text),
+            MappingFormat::Tsrg2 => Self::parse_tsrg2(&text),
+            MappingFormat::ProGuard => Self::parse_proguard(&text),
+            MappingFormat::Srg => Self::parse_srg(&text),
+            MappingFormat::McpCsv => Self::parse_mcp_csv(&text),
+        }.map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?;
+        Ok((m, format))
+    }
+
+    /// Index of the class named `owner` in the first namespace, added if missing
+    fn class_by_first(&mut self, index: &mut HashMap<Box<str>, usize>, owner: &str) -> &mut Class {
+        let n = self.namespaces.len();
+        let i = *index.entry(owner.into()).or_insert_with(|| {
+            let mut names = vec![None; n];
+            names[0] = name(owner);
+            self.classes.push(Class { names, fields: vec![], methods: vec![] });
+            self.classes.len() - 1
+        });
+        &mut self.classes[i]
+    }
+
+    fn parse_tiny_v1(text: &str) -> anyhow::Result<Self> {
+        let mut lines = text.lines();
+        let header = lines.next().unwrap_or_default().split('\t').skip(1).collect::<Vec<_>>();
+        let mut m = Self::with_namespaces(&header);
+        let n = header.len();
+        let mut index = HashMap::new();
+        for (i, l) in lines.enumerate() {
+            let parts = l.split('\t').collect::<Vec<_>>();
+            match parts.as_slice() {
+                ["CLASS", names @ ..] if names.len() == n => {
+                    let c = m.class_by_first(&mut index, names[0]);
+                    c.names = names.iter().map(|s| name(s)).collect();
+                }
+                [kind @ ("FIELD" | "METHOD"), owner, desc, names @ ..] if names.len() == n => {
+                    let member = Member { names: names.iter().map(|s| name(s)).collect(), desc: name(desc) };
+                    let c = m.class_by_first(&mut index, owner);
+                    if *kind == "FIELD" { c.fields.push(member) } else { c.methods.push(member) }
+                }
+                [] | [""] => {}
+                [x, ..] if x.starts_with('#') => {}
+                _ => anyhow::bail!("Invalid Tiny v1 line {}", i + 2),
+            }
+        }
+        Ok(m)
+    }
+
+    fn parse_tiny_v2(text: &str) -> anyhow::Result<Self> {
+        let mut lines = text.lines();
+        let header = lines.next().unwrap_or_default().split('\t').skip(3).collect::<Vec<_>>();
+        let mut m = Self::with_namespaces(&header);
+        let n = header.len();
+        for (i, l) in lines.enumerate() {
+            let depth = l.len() - l.trim_start_matches('\t').len();
+            let parts = l[depth..].split('\t').collect::<Vec<_>>();
+            match (depth, parts.as_slice()) {
+                (0, ["c", names @ ..]) if names.len() == n => {
+                    m.classes.push(Class { names: names.iter().map(|s| name(s)).collect(), fields: vec![], methods: vec![] });
+                }
+                (1, [kind @ ("f" | "m"), desc, names @ ..]) if names.len() == n => {
+                    let member = Member { names: names.iter().map(|s| name(s)).collect(), desc: name(desc) };
+                    let Some(c) = m.classes.last_mut() else {
+                        anyhow::bail!("Member outside of a class at line {}", i + 2);
+                    };
+                    if *kind == "f" { c.fields.push(member) } else { c.methods.push(member) }
+                }
+                (0, [""]) => {}
+                // Comments, parameters and local variables
+                (1.., _) => {}
+                _ => anyhow::bail!("Invalid Tiny v2 line {}", i + 2),
+            }
+        }
+        Ok(m)
+    }
+
+    fn parse_tsrg(text: &str) -> anyhow::Result<Self> {
+        let mut m = Self::with_namespaces(&[OFFICIAL, "srg"]);
+        for (i, l) in text.lines().enumerate() {
+            let parts = l.trim_start().split(' ').collect::<Vec<_>>();
+            match (l.starts_with('\t') || l.starts_with(' '), parts.as_slice()) {
+                // Packages
+                (false, [a, _]) if a.ends_with('/') => {}
+                (false, [a, b]) => m.classes.push(Class { names: vec![name(a), name(b)], fields: vec![], methods: vec![] }),
+                (true, members) => {
+                    let Some(c) = m.classes.last_mut() else {
+                        anyhow::bail!("Member outside of a class at line {}", i + 1);
+                    };
+                    match members {
+                        [a, b] => c.fields.push(Member { names: vec![name(a), name(b)], desc: None }),
+                        [a, d, b] => c.methods.push(Member { names: vec![name(a), name(b)], desc: name(d) }),
+                        _ => anyhow::bail!("Invalid TSRG line {}", i + 1),
+                    }
+                }
+                (false, [""]) => {}
+                _ => anyhow::bail!("Invalid TSRG line {}", i + 1),
+            }
+        }
+        Ok(m)
+    }
+
+    fn parse_tsrg2(text: &str) -> anyhow::Result<Self> {
+        let mut lines = text.lines();
+        let header = lines.next().unwrap_or_default().split(' ').skip(1)
+            .map(|n| if n == "obf" { OFFICIAL } else { n })
+            .collect::<Vec<_>>();
+        let mut m = Self::with_namespaces(&header);
+        let n = header.len();
+        for (i, l) in lines.enumerate() {
+            let depth = l.len() - l.trim_start_matches('\t').len();
+            let parts = l[depth..].split(' ').collect::<Vec<_>>();
+            match depth {
+                0 if parts.len() == n => m.classes.push(Class { names: parts.iter().map(|s| name(s)).collect(), fields: vec![], methods: vec![] }),
+                1 => {
+                    let Some(c) = m.classes.last_mut() else {
+                        anyhow::bail!("Member outside of a class at line {}", i + 2);
+                    };
+                    match parts.as_slice() {
+                        names if names.len() == n => c.fields.push(Member { names: names.iter().map(|s| name(s)).collect(), desc: None }),
+                        [first, desc, rest @ ..] if rest.len() + 1 == n => {
+                            let names = std::iter::once(first).chain(rest).map(|s| name(s)).collect();
+                            let member = Member { names, desc: name(desc) };
+                            if desc.starts_with('(') { c.methods.push(member) } else { c.fields.push(member) }
+                        }
+                        _ => anyhow::bail!("Invalid TSRG2 line {}", i + 2),
+                    }
+                }
+                // Parameters and the `static` marker
+                2.. => {}
+                _ if l.trim().is_empty() => {}
+                _ => anyhow::bail!("Invalid TSRG2 line {}", i + 2),
+            }
+        }
+        Ok(m)
+    }
+
+    fn parse_proguard(text: &str) -> anyhow::Result<Self> {
+        let mut m = Self::with_namespaces(&["mojang", OFFICIAL]);
+        for (i, l) in text.lines().enumerate() {
+            if l.trim_start().starts_with('#') || l.trim().is_empty() {
+                continue;
+            }
+            let Some((left, obf)) = l.trim().split_once(" -> ") else {
+                anyhow::bail!("Invalid ProGuard line {}", i + 1);
+            };
+            if !l.starts_with(' ') {
+                let Some(obf) = obf.strip_suffix(':') else {
+                    anyhow::bail!("Invalid ProGuard line {}", i + 1);
+                };
+                m.classes.push(Class {
+                    names: vec![name(&left.replace('.', "/")), name(&obf.replace('.', "/"))],
+                    fields: vec![],
+                    methods: vec![],
+                });
+                continue;
+            }
+            let Some(c) = m.classes.last_mut() else {
+                anyhow::bail!("Member outside of a class at line {}", i + 1);
+            };
+            // Line numbers of methods: `12:34:void tick() -> a`
+            let left = left.trim_start_matches(|ch: char| ch.is_ascii_digit() || ch == ':');
+            let Some((ty, rest)) = left.split_once(' ') else {
+                anyhow::bail!("Invalid ProGuard line {}", i + 1);
+            };
+            match rest.split_once('(') {
+                Some((mname, args)) => {
+                    if mname.starts_with('<') {
+                        continue;
+                    }
+                    let args = args.split_once(')').map_or(args, |a| a.0);
+                    let desc = format!(
+                        "({}){}",
+                        args.split(',').filter(|a| !a.is_empty()).map(java_type_desc).collect::<String>(),
+                        java_type_desc(ty),
+                    );
+                    c.methods.push(Member { names: vec![name(mname), name(obf)], desc: name(&desc) });
+                }
+                None => c.fields.push(Member { names: vec![name(rest), name(obf)], desc: name(&java_type_desc(ty)) }),
+            }
+        }
+        Ok(m)
+    }
+
+    fn parse_srg(text: &str) -> anyhow::Result<Self> {
+        let mut m = Self::with_namespaces(&[OFFICIAL, "srg"]);
+        let mut index = HashMap::new();
+        let split_member = |s: &'_ str| s.rsplit_once('/').map(|(o, n)| (o.to_string(), n.to_string()));
+        for (i, l) in text.lines().enumerate() {
+            let parts = l.split(' ').collect::<Vec<_>>();
+            match parts.as_slice() {
+                ["CL:", a, b] => m.class_by_first(&mut index, a).names[1] = name(b),
+                ["FD:", a, b] => {
+                    let (Some((owner, a)), Some((_, b))) = (split_member(a), split_member(b)) else {
+                        anyhow::bail!("Invalid SRG line {}", i + 1);
+                    };
+                    m.class_by_first(&mut index, &owner).fields.push(Member { names: vec![name(&a), name(&b)], desc: None });
+                }
+                ["MD:", a, desc, b, _] => {
+                    let (Some((owner, a)), Some((_, b))) = (split_member(a), split_member(b)) else {
+                        anyhow::bail!("Invalid SRG line {}", i + 1);
+                    };
+                    m.class_by_first(&mut index, &owner).methods.push(Member { names: vec![name(&a), name(&b)], desc: name(desc) });
+                }
+                ["PK:", ..] | [""] => {}
+                _ => anyhow::bail!("Invalid SRG line {}", i + 1),
+            }
+        }
+        Ok(m)
+    }
+
+    /// MCP `fields.csv` and `methods.csv` rename SRG members regardless of their owner,
+    /// so they are stored in a single class without names.
+    fn parse_mcp_csv(text: &str) -> anyhow::Result<Self> {
+        let mut m = Self::with_namespaces(&["srg", "mcp"]);
+        let mut c = Class { names: vec![None, None], fields: vec![], methods: vec![] };
+        for l in text.lines().skip(1) {
+            let mut it = l.splitn(3, ',');
+            let (Some(srg), Some(mcp)) = (it.next(), it.next()) else { continue };
+            let member = Member { names: vec![name(srg), name(mcp)], desc: None };
+            if srg.starts_with("func_") {
+                c.methods.push(member);
+            } else if srg.starts_with("field_") {
+                c.fields.push(member);
+            }
+        }
+        m.classes.push(c);
+        Ok(m)
+    }
+
+    /// Adds names of another mappings file, matching classes and members by a namespace
+    /// both files share. Mappings without a shared namespace are rejected before anything changes.
+    pub fn merge(&mut self, other: Self, format: MappingFormat) -> anyhow::Result<()> {
+        if self.namespaces.is_empty() {
+            *self = other;
+            return Ok(());
+        }
+        let Some((xo, xs)) = other.namespaces.iter()
+            .enumerate()
+            .find_map(|(i, n)| Some((i, self.namespaces.iter().position(|s| s == n)?)))
+        else {
+            anyhow::bail!("Mappings do not share a namespace with the loaded ones ({})", self.namespaces.join(", "));
+        };
+        let ns_map = other.namespaces.iter()
+            .map(|n| self.namespaces.iter().position(|s| s == n).unwrap_or_else(|| {
+                self.namespaces.push(n.clone());
+                self.namespaces.len() - 1
+            }))
+            .collect::<Vec<_>>();
+        let n = self.namespaces.len();
+        for c in &mut self.classes {
+            c.names.resize(n, None);
+            for m in c.fields.iter_mut().chain(&mut c.methods) {
+                m.names.resize(n, None);
+            }
+        }
+        let names = |from: &[Option<Box<str>>]| {
+            let mut v = vec![None; n];
+            for (i, x) in from.iter().enumerate() {
+                v[ns_map[i]].clone_from(x);
+            }
+            v
+        };
+        if format == MappingFormat::McpCsv {
+            // Members renamed by name only
+            let renames = other.classes.iter()
+                .flat_map(|c| c.fields.iter().chain(&c.methods))
+                .filter_map(|m| Some((m.names[xo].clone()?, m.names[1 - xo].clone()?)))
+                .collect::<HashMap<_, _>>();
+            let to = ns_map[1 - xo];
+            for m in self.classes.iter_mut().flat_map(|c| c.fields.iter_mut().chain(&mut c.methods)) {
+                if let Some(r) = m.names[xs].as_ref().and_then(|s| renames.get(s)) {
+                    m.names[to] = Some(r.clone());
+                }
+            }
+            return Ok(());
+        }
+        let self_descs = self.class_map(0, xs);
+        let other_descs = other.class_map(0, xo);
+        let mut index = self.classes.iter()
+            .enumerate()
+            .filter_map(|(i, c)| Some((c.names[xs].clone()?, i)))
+            .collect::<HashMap<_, _>>();
+        for oc in &other.classes {
+            let Some(key) = &oc.names[xo] else { continue };
+            let i = *index.entry(key.clone()).or_insert_with(|| {
+                self.classes.push(Class { names: vec![None; n], fields: vec![], methods: vec![] });
+                self.classes.len() - 1
+            });
+            let sc = &mut self.classes[i];
+            for (j, x) in names(&oc.names).into_iter().enumerate() {
+                if sc.names[j].is_none() {
+                    sc.names[j] = x;
+                }
+            }
+            for (smembers, omembers) in [(&mut sc.fields, &oc.fields), (&mut sc.methods, &oc.methods)] {
+                for om in omembers {
+                    let odesc = om.desc.as_deref().map(|d| remap_desc(d, &other_descs));
+                    let found = smembers.iter_mut().find(|sm| {
+                        sm.names[xs].is_some() && sm.names[xs] == om.names[xo]
+                            && match (&sm.desc, &odesc) {
+                                (Some(a), Some(b)) => remap_desc(a, &self_descs) == *b,
+                                _ => true,
+                            }
+                    });
+                    match found {
+                        Some(sm) => {
+                            for (j, x) in names(&om.names).into_iter().enumerate() {
+                                if sm.names[j].is_none() {
+                                    sm.names[j] = x;
+                                }
+                            }
+                        }
+                        // Descriptors of new members are only kept when both files start with the same namespace
+                        None => smembers.push(Member {
+                            names: names(&om.names),
+                            desc: om.desc.clone().filter(|_| xs == 0 && xo == 0),
+                        }),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Member names of a class by their original name, with descriptors in the same namespace
+type MemberNames = HashMap<Box<str>, Vec<(Option<Box<str>>, Box<str>)>>;
+
+/// Rewrites names of other namespaces to the selected one
+pub struct Remapper {
+    /// Full internal class names
+    classes: HashMap<Box<str>, Box<str>>,
+    /// Field and method names by owner class
+    members: HashMap<Box<str>, MemberNames>,
+    /// Simple class names and member names of namespaces with unique names
+    unique: HashMap<Box<str>, Box<str>>,
+}
+impl Remapper {
+    pub fn new(m: &Mappings, target: &str) -> Option<Self> {
+        let t = m.namespaces.iter().position(|n| &**n == target)?;
+        let sources = m.namespaces.iter()
+            .enumerate()
+            .filter(|(i, n)| *i != t && !SKIPPED_NAMESPACES.contains(&&***n))
+            .map(|(i, n)| (i, UNIQUE_NAMESPACES.contains(&&**n), (i > 0).then(|| m.class_map(0, i))))
+            .collect::<Vec<_>>();
+        let mut classes = HashMap::new();
+        let mut members = HashMap::<_, MemberNames>::new();
+        let mut unique = HashMap::new();
+        let simple = |s: &str| -> Box<str> { s.rsplit(['/', '$']).next().unwrap_or(s).into() };
+        for c in &m.classes {
+            for (i, uniq, descs) in &sources {
+                let (i, uniq) = (*i, *uniq);
+                if let (Some(from), Some(to)) = (&c.names[i], &c.names[t]) {
+                    if from != to {
+                        classes.insert(from.clone(), to.clone());
+                        if uniq {
+                            unique.insert(simple(from), simple(to));
+                        }
+                    }
+                }
+                for mem in c.fields.iter().chain(&c.methods) {
+                    let (Some(from), Some(to)) = (&mem.names[i], &mem.names[t]) else { continue };
+                    if uniq && from != to {
+                        unique.insert(from.clone(), to.clone());
+                    }
+                    // Members of the class without names (MCP CSV) are only renamed as unique names
+                    let Some(owner) = &c.names[i] else { continue };
+                    let desc = descs.as_ref().map_or_else(
+                        || mem.desc.clone(),
+                        |d| mem.desc.as_deref().map(|x| remap_desc(x, d)),
+                    );
+                    // Members keeping their names are stored too, so that overloads renamed differently stay ambiguous
+                    members.entry(owner.clone()).or_default()
+                        .entry(from.clone()).or_default()
+                        .push((desc, to.clone()));
+                }
+            }
+        }
+        Some(Self { classes, members, unique })
+    }
+
+    /// Remaps an internal class name.
+    pub fn class(&self, name: &mut Box<str>) {
+        if let Some(c) = self.classes.get(name) {
+            name.clone_from(c);
+        }
+    }
+
+    /// Original internal name of a remapped class name, if a class is renamed to it.
+    pub fn original_class(&self, name: &str) -> Option<&str> {
+        self.classes.iter().find(|(_, to)| ***to == *name).map(|(from, _)| &**from)
+    }
+
+    /// Remaps a field or method name of a class, both in the original names. Members are matched
+    /// by their descriptor if both it and the mappings have one, otherwise only if all members
+    /// of that name are remapped the same way.
+    pub fn member(&self, owner: &str, name: &mut Box<str>, desc: Option<&str>) {
+        let found = self.members.get(owner).and_then(|m| m.get(&**name)).and_then(|cands| {
+            if let Some((_, to)) = desc.and_then(|d| cands.iter().find(|(cd, _)| cd.as_deref() == Some(d))) {
+                return Some(to);
+            }
+            let mut rest = cands.iter().filter(|(cd, _)| desc.is_none() || cd.is_none()).map(|(_, to)| to);
+            let first = rest.next()?;
+            rest.all(|to| to == first).then_some(first)
+        });
+        if let Some(to) = found.or_else(|| self.unique.get(name)) {
+            name.clone_from(to);
+        }
+    }
+
+    /// Remaps class names in a field or method descriptor.
+    pub fn desc(&self, desc: &mut Box<str>) {
+        *desc = remap_desc(desc, &self.classes);
+    }
+
+    /// Remaps class names in a generic signature.
+    pub fn sig(&self, sig: &mut Box<str>) {
+        *sig = remap_sig(sig, &self.classes);
+    }
+
+    /// Remaps names in rendered text, like [`Self::remap_str`].
+    pub fn text(&self, s: &mut Box<str>) {
+        if let Cow::Owned(r) = self.remap_str(s) {
+            *s = r.into_boxed_str();
+        }
+    }
+
+    fn remap_word(&self, w: &str) -> Option<String> {
+        // Unqualified class names (of the official namespace) are too ambiguous to be found in text
+        let class = |x: &str| self.classes.get(x).filter(|_| x.contains('/'));
+        if let Some(c) = class(w).or_else(|| self.unique.get(w)) {
+            return Some(c.to_string());
+        }
+        // Class names in field descriptors (`Lnet/minecraft/class_1297`)
+        if let Some(c) = w.strip_prefix('L').and_then(class) {
+            return Some(format!("L{c}"));
+        }
+        if w.contains('.') {
+            if let Some(c) = class(w.replace('.', "/").as_str()) {
+                return Some(c.replace('/', "."));
+            }
+        }
+        // Members of classes (`Owner.member`) and simple names of inner classes (`Outer$Inner`)
+        if !w.contains(['.', '$']) {
+            return None;
+        }
+        let mut out = String::with_capacity(w.len());
+        let mut changed = false;
+        for (i, p) in w.split_inclusive(['.', '$']).enumerate() {
+            let (p, sep) = p.strip_suffix(['.', '$']).map_or((p, ""), |x| (x, &p[x.len()..]));
+            match self.remap_word(p).filter(|_| i > 0 || !p.is_empty()) {
+                Some(r) => {
+                    changed = true;
+                    out.push_str(&r);
+                }
+                None => out.push_str(p),
+            }
+            out.push_str(sep);
+        }
+        changed.then_some(out)
+    }
+
+    /// Remaps all class and member names found in a string.
+    pub fn remap_str<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        let is_word = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '$' | '/' | '.');
+        let mut out = String::new();
+        let mut last = 0;
+        let mut start = None;
+        for (i, c) in s.char_indices().chain(std::iter::once((s.len(), ' '))) {
+            match (is_word(c), start) {
+                (true, None) => start = Some(i),
+                (false, Some(st)) => {
+                    start = None;
+                    if let Some(r) = self.remap_word(&s[st..i]) {
+                        out.push_str(&s[last..st]);
+                        out.push_str(&r);
+                        last = i;
+                    }
+                }
+                _ => {}
+            }
+        }
+        if last == 0 {
+            return Cow::Borrowed(s);
+        }
+        out.push_str(&s[last..]);
+        Cow::Owned(out)
+    }
+}
+
+/// Values with class and member names which can be remapped
+pub trait Remap {
+    fn remap(&mut self, r: &Remapper);
+}
+impl<T: Remap + Clone> Remap for Arc<T> {
+    fn remap(&mut self, r: &Remapper) {
+        Self::make_mut(self).remap(r);
+    }
+}
+impl<T: Remap> Remap for Vec<T> {
+    fn remap(&mut self, r: &Remapper) {
+        self.iter_mut().for_each(|x| x.remap(r));
+    }
+}
+impl<T: Remap> Remap for (Id, T) {
+    fn remap(&mut self, r: &Remapper) {
+        self.1.remap(r);
+    }
+}
+
+/// A value with its class and member names remapped, if mappings are selected
+#[derive(Serialize)]
+#[serde(transparent)]
+pub struct Remapped<T>(T);
+impl<T: Remap> Remapped<T> {
+    pub fn new(mut v: T, remapper: Option<&Remapper>) -> Self {
+        if let Some(r) = remapper {
+            v.remap(r);
+        }
+        Self(v)
+    }
+}
+
+#[derive(Serialize)]
+pub struct MappingsInfo {
+    files: Vec<(Box<Path>, MappingFormat, usize)>,
+    namespaces: Vec<Box<str>>,
+    selected: Option<Box<str>>,
+}
+
+/// Mappings files and the selected namespace of a workspace, kept between sessions
+#[derive(Serialize, Deserialize, Default)]
+struct SavedMappings {
+    files: Vec<PathBuf>,
+    selected: Option<Box<str>>,
+}
+
+/// File with the saved mappings of all workspaces, in the app config directory of Tauri
+fn saved_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join(APP_IDENTIFIER).join("mappings.json"))
+}
+
+fn read_saved(path: &Path) -> HashMap<PathBuf, SavedMappings> {
+    fs::read(path).ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Mappings loaded for a workspace and the namespace selected for display
+#[derive(Default)]
+pub struct MappingState {
+    files: Vec<(Box<Path>, MappingFormat, usize)>,
+    mappings: Mappings,
+    selected: Option<Box<str>>,
+    remapper: Option<Arc<Remapper>>,
+}
+impl MappingState {
+    /// Loads the mappings saved for a workspace, skipping files which cannot be loaded anymore.
+    pub fn restore(workspace: &Path) -> Self {
+        let mut ms = Self::default();
+        let Some(mut saved) = saved_path().and_then(|p| read_saved(&p).remove(workspace)) else {
+            return ms;
+        };
+        for path in saved.files {
+            if let Err(e) = Mappings::load(&path).and_then(|(m, format)| ms.add(path, m, format)) {
+                eprintln!("Restoring mappings error: {e}");
+            }
+        }
+        ms.select(saved.selected.take());
+        ms
+    }
+    /// Saves the mappings files and the selected namespace of a workspace.
+    pub fn save(&self, workspace: &Path) -> anyhow::Result<()> {
+        let path = saved_path().ok_or_else(|| anyhow::anyhow!("No config directory"))?;
+        let mut saved = read_saved(&path);
+        if self.files.is_empty() {
+            saved.remove(workspace);
+        } else {
+            saved.insert(workspace.to_path_buf(), SavedMappings {
+                files: self.files.iter().map(|(p, _, _)| p.to_path_buf()).collect(),
+                selected: self.selected.clone(),
+            });
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_vec(&saved)?)?;
+        Ok(())
+    }
+    pub fn info(&self) -> MappingsInfo {
+        MappingsInfo {
+            files: self.files.clone(),
+            namespaces: self.mappings.namespaces.clone(),
+            selected: self.selected.clone(),
+        }
+    }
+    /// Merges mappings loaded from `path`, keeping the current ones if they do not share a namespace.
+    pub fn add(&mut self, path: PathBuf, m: Mappings, format: MappingFormat) -> anyhow::Result<()> {
+        let count = m.class_count();
+        self.mappings.merge(m, format)?;
+        self.files.push((path.into_boxed_path(), format, count));
+        self.select(self.selected.clone());
+        Ok(())
+    }
+    pub fn select(&mut self, namespace: Option<Box<str>>) {
+        self.remapper = namespace.as_deref()
+            .and_then(|ns| Remapper::new(&self.mappings, ns))
+            .map(Arc::new);
+        self.selected = namespace.filter(|_| self.remapper.is_some());
+    }
+    pub fn remapper(&self) -> Option<Arc<Remapper>> {
+        self.remapper.clone()
+    }
+}
@@ -0,0 +1,212 @@
+//! JPMS modules of mods. Forge and NeoForge load every jar as a module of one layer,
+//! which fails at boot when two modules contain the same package or share a name.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{Read, Seek},
+    sync::Arc,
+};
+
+use cm_jclass::{
+    attr::{self, JAttr},
+    flags, JClassReader, JStr,
+};
+use cm_zipext::FileMap;
+use serde::Serialize;
+
+use crate::{id::Id, loader::ModTypeData};
+
+const MODULE_INFO: &str = "module-info.class";
+const AUTOMATIC_MODULE_NAME: &str = "Automatic-Module-Name";
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum NameSource {
+    /// `module-info.class`
+    Descriptor,
+    /// `Automatic-Module-Name` of the manifest
+    Manifest,
+    /// First mod id of the mods.toml
+    ModId,
+    /// Derived from the jar file name
+    FileName,
+}
+
+#[derive(Serialize)]
+pub struct Requires {
+    module: Box<str>,
+    flags: Vec<&'static str>,
+    version: Option<Box<str>>,
+}
+
+/// Package exported or opened by a module, to all modules if `to` is empty
+#[derive(Serialize)]
+pub struct Exports {
+    package: Box<str>,
+    to: Vec<Box<str>>,
+}
+
+/// Declarations of `module-info.class`, with dotted package and class names
+#[derive(Serialize)]
+pub struct ModuleDescriptor {
+    name: Box<str>,
+    flags: Vec<&'static str>,
+    version: Option<Box<str>>,
+    main_class: Option<Box<str>>,
+    requires: Vec<Requires>,
+    exports: Vec<Exports>,
+    opens: Vec<Exports>,
+    uses: Vec<Box<str>>,
+    provides: Vec<(Box<str>, Vec<Box<str>>)>,
+}
+
+#[derive(Serialize)]
+pub struct JavaModule {
+    name: Box<str>,
+    source: NameSource,
+    /// Loaded in the module layer, false for Fabric mods which share the class path
+    layered: bool,
+    descriptor: Option<ModuleDescriptor>,
+    /// Packages containing classes, which the module layer requires to be unique
+    packages: Vec<Box<str>>,
+}
+
+fn dotted(s: &JStr) -> Box<str> {
+    s.to_str_lossy().replace('/', ".").into_boxed_str()
+}
+
+fn read_descriptor<R: Read>(r: R) -> anyhow::Result<ModuleDescriptor> {
+    let jcr = JClassReader::new(r)?;
+    let (_, attrs) = jcr.skip_interfaces()?.skip_fields()?.skip_methods()?.attributes()?;
+    let mut module = None;
+    let mut main_class = None;
+    for ai in attrs {
+        let ai = ai?;
+        match ai.kind()? {
+            JAttr::Module(k) => module = Some(ai.read(&k)?),
+            JAttr::ModuleMainClass(k) => main_class = Some(dotted(&ai.read(&k)?)),
+            _ => {}
+        }
+    }
+    let Some(m) = module else { anyhow::bail!("No Module attribute in {MODULE_INFO}") };
+    let exports = |v: Vec<attr::Exports>| v.into_iter()
+        .map(|e| Exports { package: dotted(&e.package), to: e.to.iter().map(dotted).collect() })
+        .collect();
+    Ok(ModuleDescriptor {
+        name: dotted(&m.name),
+        flags: flags::names(m.flags, flags::MODULE).collect(),
+        version: m.version.map(|v| v.to_str_lossy().into()),
+        main_class,
+        requires: m.requires.into_iter().map(|r| Requires {
+            module: dotted(&r.module),
+            flags: flags::names(r.flags, flags::REQUIRES).collect(),
+            version: r.version.map(|v| v.to_str_lossy().into()),
+        }).collect(),
+        exports: exports(m.exports),
+        opens: exports(m.opens),
+        uses: m.uses.iter().map(dotted).collect(),
+        provides: m.provides.iter().map(|p| (dotted(&p.service), p.with.iter().map(dotted).collect())).collect(),
+    })
+}
+
+/// Value of a main attribute of a manifest, joining continuation lines
+fn manifest_value(mf: &str, key: &str) -> Option<Box<str>> {
+    let mut lines = mf.lines();
+    let first = lines.by_ref().find_map(|l| {
+        l.split_once(':').filter(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v.trim_start())
+    })?;
+    let mut value = first.to_string();
+    value.extend(lines.map_while(|l| l.strip_prefix(' ')));
+    Some(value.trim_end().into())
+}
+
+/// Module name the JDK derives from a jar name, `foo_bar-1.2.jar` becoming `foo.bar`
+fn name_from_file(file: &str) -> Box<str> {
+    let base = file.strip_suffix(".jar").unwrap_or(file);
+    // The version starts at a `-` followed by digits and a dot or the end
+    let version = base.match_indices('-').map(|(i, _)| i).find(|&i| {
+        let rest = &base[i + 1..];
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        digits > 0 && matches!(rest.as_bytes().get(digits), None | Some(b'.'))
+    });
+    base[..version.unwrap_or(base.len())]
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(".")
+        .into_boxed_str()
+}
+
+/// Finds the module of a jar as the loader names it. FML turns mods into automatic modules,
+/// ignoring their `module-info.class`, while other jars are named by it like on the JDK module path.
+pub fn gather_module<RS: Read + Seek>(fm: &FileMap, mtd: Option<&ModTypeData>, file: &str, rs: &mut RS) -> anyhow::Result<JavaModule> {
+    let info = fm.get(MODULE_INFO).or_else(|| {
+        fm.iter()
+            .filter_map(|(k, fe)| {
+                let v = k.strip_prefix("META-INF/versions/")?.strip_suffix(MODULE_INFO)?.strip_suffix('/')?;
+                Some((v.parse::<u16>().ok()?, fe))
+            })
+            .max_by_key(|(v, _)| *v)
+            .map(|(_, fe)| fe)
+    });
+    let descriptor = info.map(|fe| read_descriptor(fe.reader(rs)?)).transpose()?;
+    let automatic = fm.get("META-INF/MANIFEST.MF")
+        .and_then(|fe| fe.string_from(rs).ok())
+        .and_then(|mf| manifest_value(&mf, AUTOMATIC_MODULE_NAME));
+    let mod_id = match mtd {
+        Some(ModTypeData::Forge(md) | ModTypeData::Neoforge(md)) => Some(md.first().map(|m| m.slug().into())),
+        _ => None,
+    };
+    let (name, source) = match (mod_id, &descriptor, automatic) {
+        (Some(_), _, Some(n)) => (n, NameSource::Manifest),
+        (Some(Some(id)), _, None) => (id, NameSource::ModId),
+        (None, Some(d), _) => (d.name.clone(), NameSource::Descriptor),
+        (None, None, Some(n)) => (n, NameSource::Manifest),
+        (_, _, None) => (name_from_file(file), NameSource::FileName),
+    };
+    let packages = fm.keys()
+        .filter(|k| k.ends_with(".class") && !k.starts_with("META-INF/"))
+        .filter_map(|k| k.rsplit_once('/').map(|(p, _)| p.replace('/', ".").into_boxed_str()))
+        .collect::<BTreeSet<_>>();
+    Ok(JavaModule {
+        name,
+        source,
+        layered: !matches!(mtd, Some(ModTypeData::Fabric(_))),
+        descriptor,
+        packages: packages.into_iter().collect(),
+    })
+}
+
+#[derive(Serialize)]
+pub struct LayerModule {
+    id: Id,
+    name: Box<str>,
+    source: NameSource,
+    requires: Vec<Box<str>>,
+}
+
+#[derive(Serialize)]
+pub struct ModuleCheck {
+    modules: Vec<LayerModule>,
+    /// Packages contained in more than one module
+    split_packages: Vec<(Box<str>, Vec<Id>)>,
+    /// Module names used by more than one jar
+    collisions: Vec<(Box<str>, Vec<Id>)>,
+}
+
+/// Finds the split packages and module name collisions that make the module layer fail.
+pub fn check_modules(it: impl Iterator<Item = (Id, Arc<JavaModule>)>) -> ModuleCheck {
+    let mut modules = vec![];
+    let mut packages = BTreeMap::<Box<str>, Vec<Id>>::new();
+    let mut names = BTreeMap::<Box<str>, Vec<Id>>::new();
+    for (id, jm) in it.filter(|(_, jm)| jm.layered) {
+        for p in &jm.packages {
+            packages.entry(p.clone()).or_default().push(id);
+        }
+        names.entry(jm.name.clone()).or_default().push(id);
+        let requires = jm.descriptor.iter().flat_map(|d| &d.requires).map(|r| r.module.clone()).collect();
+        modules.push(LayerModule { id, name: jm.name.clone(), source: jm.source, requires });
+    }
+    let shared = |m: BTreeMap<Box<str>, Vec<Id>>| m.into_iter().filter(|(_, ids)| ids.len() > 1).collect();
+    ModuleCheck { modules, split_packages: shared(packages), collisions: shared(names) }
+}
@@ -0,0 +1,316 @@
+use std::{io::{Read, Seek}, path::Path, sync::Arc};
+
+use cm_jclass::{attr::{Code, JAttr}, pool::{ClassPool, PoolRef}, JClassReader};
+use cm_zipext::FileMap;
+use serde::Serialize;
+
+use crate::{ext::Extension, id::Id, nested};
+
+const NATIVE_EXTENSIONS: &[&str] = &["so", "dll", "dylib", "jnilib"];
+const ACC_NATIVE: u16 = 0x0100;
+/// Bytes read from each library, enough for the headers
+const HEADER_SIZE: u64 = 4096;
+/// Fat Mach-O files with more architectures than this are rejected
+const MAX_FAT_ARCHS: u32 = 32;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BinaryFormat {
+    Elf,
+    Pe,
+    MachO,
+    Unknown,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Os {
+    Linux,
+    FreeBsd,
+    Windows,
+    Macos,
+    Unknown,
+}
+
+/// A native library bundled in a jar
+#[derive(Serialize)]
+pub struct NativeLib {
+    /// Entry path, through nested archives as in `META-INF/jars/lwjgl.jar!/linux/x64/liblwjgl.so`
+    path: Box<str>,
+    size: u64,
+    format: BinaryFormat,
+    os: Os,
+    /// Architectures of the library, more than one for universal Mach-O files
+    archs: Vec<Box<str>>,
+    bits: Option<u8>,
+}
+
+/// A `System.load` or `System.loadLibrary` call
+#[derive(Serialize)]
+pub struct LibraryLoad {
+    class: Box<str>,
+    method: Box<str>,
+    call: Box<str>,
+    /// Last string constant loaded before the call, usually the library name
+    library: Option<Box<str>>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Portability {
+    /// No native code
+    Portable,
+    /// Bundled libraries include Linux ARM builds, which ARM servers load
+    Arm,
+    /// Bundled libraries have no Linux ARM builds, even if they have some for other systems
+    NoArm,
+    /// Native code without bundled libraries, provided by the system or another jar
+    External,
+}
+
+/// Native libraries and JNI usage of a jar
+#[derive(Serialize)]
+pub struct NativeUsage {
+    libs: Vec<NativeLib>,
+    /// Classes and signatures of `native` methods
+    methods: Vec<(Box<str>, Box<str>)>,
+    loads: Vec<LibraryLoad>,
+    /// Paths of the classes which could not be read, with the error
+    unreadable: Vec<(Box<str>, Box<str>)>,
+}
+impl NativeUsage {
+    pub fn portability(&self) -> Portability {
+        if self.libs.is_empty() {
+            if self.methods.is_empty() && self.loads.is_empty() { Portability::Portable } else { Portability::External }
+        } else if self.libs.iter().any(|l| l.os == Os::Linux && l.archs.iter().any(|a| a.starts_with("arm") || **a == *"aarch64")) {
+            Portability::Arm
+        } else {
+            Portability::NoArm
+        }
+    }
+}
+
+fn u16_at(b: &[u8], i: usize, le: bool) -> Option<u16> {
+    let x = b.get(i..i + 2)?.try_into().ok()?;
+    Some(if le { u16::from_le_bytes(x) } else { u16::from_be_bytes(x) })
+}
+fn u32_at(b: &[u8], i: usize, le: bool) -> Option<u32> {
+    let x = b.get(i..i + 4)?.try_into().ok()?;
+    Some(if le { u32::from_le_bytes(x) } else { u32::from_be_bytes(x) })
+}
+
+fn elf_arch(machine: u16, bits: u8) -> Box<str> {
+    match (machine, bits) {
+        (0x03, _) => "x86".into(),
+        (0x3e, _) => "x86_64".into(),
+        (0x28, _) => "arm".into(),
+        (0xb7, _) => "aarch64".into(),
+        (0xf3, 64) => "riscv64".into(),
+        (0xf3, _) => "riscv32".into(),
+        (0x08, _) => "mips".into(),
+        (0x14, _) => "ppc".into(),
+        (0x15, _) => "ppc64".into(),
+        (0x16, _) => "s390x".into(),
+        (0x2b, _) => "sparc64".into(),
+        (0x102, _) => "loongarch64".into(),
+        _ => format!("elf:{machine:#x}").into_boxed_str(),
+    }
+}
+
+fn pe_arch(machine: u16) -> Box<str> {
+    match machine {
+        0x14c => "x86".into(),
+        0x8664 => "x86_64".into(),
+        0x1c0 | 0x1c4 => "arm".into(),
+        0xaa64 => "aarch64".into(),
+        _ => format!("pe:{machine:#x}").into_boxed_str(),
+    }
+}
+
+fn macho_arch(cpu: u32) -> Box<str> {
+    match cpu {
+        7 => "x86".into(),
+        0x0100_0007 => "x86_64".into(),
+        12 => "arm".into(),
+        0x0100_000c => "aarch64".into(),
+        0x0200_000c => "arm64_32".into(),
+        18 => "ppc".into(),
+        0x0100_0012 => "ppc64".into(),
+        _ => format!("macho:{cpu:#x}").into_boxed_str(),
+    }
+}
+
+/// Reads the format, OS, architectures and word size from the headers of a library.
+fn read_header(b: &[u8]) -> (BinaryFormat, Os, Vec<Box<str>>, Option<u8>) {
+    const UNKNOWN: (BinaryFormat, Os, Vec<Box<str>>, Option<u8>) = (BinaryFormat::Unknown, Os::Unknown, vec![], None);
+    match b.get(..4) {
+        Some(b"\x7fELF") => {
+            let bits = match b.get(4) { Some(1) => 32, Some(2) => 64, _ => return UNKNOWN };
+            let le = b.get(5) == Some(&1);
+            let os = match b.get(7) {
+                Some(9) => Os::FreeBsd,
+                _ => Os::Linux,
+            };
+            let archs = u16_at(b, 18, le).map(|m| elf_arch(m, bits)).into_iter().collect();
+            (BinaryFormat::Elf, os, archs, Some(bits))
+        }
+        Some([b'M', b'Z', ..]) => {
+            let Some(pe) = u32_at(b, 0x3c, true).map(|x| x as usize) else { return UNKNOWN };
+            if b.get(pe..pe + 4) != Some(b"PE\0\0") {
+                return UNKNOWN;
+            }
+            let archs = u16_at(b, pe + 4, true).map(pe_arch).into_iter().collect();
+            // The optional header follows the 20-byte file header
+            let bits = match u16_at(b, pe + 24, true) { Some(0x10b) => Some(32), Some(0x20b) => Some(64), _ => None };
+            (BinaryFormat::Pe, Os::Windows, archs, bits)
+        }
+        Some(m) => {
+            let magic = u32::from_be_bytes([m[0], m[1], m[2], m[3]]);
+            match magic {
+                0xfeed_face | 0xfeed_facf | 0xcefa_edfe | 0xcffa_edfe => {
+                    let le = magic & 0xff == 0xfe;
+                    let bits = if magic == 0xfeed_facf || magic == 0xcffa_edfe { 64 } else { 32 };
+                    let archs = u32_at(b, 4, le).map(macho_arch).into_iter().collect();
+                    (BinaryFormat::MachO, Os::Macos, archs, Some(bits))
+                }
+                // Universal binaries list the architecture of each slice
+                0xcafe_babe | 0xcafe_babf => {
+                    let n = u32_at(b, 4, false).unwrap_or(0);
+                    if n == 0 || n > MAX_FAT_ARCHS {
+                        return UNKNOWN;
+                    }
+                    let stride = if magic == 0xcafe_babe { 20 } else { 32 };
+                    let archs = (0..n as usize)
+                        .filter_map(|i| u32_at(b, 8 + i * stride, false).map(macho_arch))
+                        .collect();
+                    (BinaryFormat::MachO, Os::Macos, archs, None)
+                }
+                _ => UNKNOWN,
+            }
+        }
+        None => UNKNOWN,
+    }
+}
+
+fn is_native_lib(path: &str) -> bool {
+    // Versioned shared objects are named like `libfoo.so.1` or `libfoo.so.1.2.3`, unlike checksums such as `libfoo.so.sha1`
+    let versioned = path.rsplit_once(".so.").is_some_and(|(_, v)| v.split('.').all(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit())));
+    versioned || Path::new(path).extension().is_some_and(|x| NATIVE_EXTENSIONS.iter().any(|n| x.eq_ignore_ascii_case(n)))
+}
+
+/// Finds library loading calls of a method, with the last string constant before each call.
+fn scan_loads(pool: &ClassPool, code: &Code, class: &str, method: &str, out: &mut Vec<LibraryLoad>) -> anyhow::Result<()> {
+    let mut last = None;
+    for insn in code.insns() {
+        let insn = insn?;
+        match (insn.opcode(), insn.resolve(pool)) {
+            // ldc, ldc_w
+            (0x12 | 0x13, Ok(Some(PoolRef::Str(s)))) => last = Some(s.to_string().into_boxed_str()),
+            // invokevirtual, invokestatic
+            (0xb6 | 0xb8, Ok(Some(PoolRef::Method(m) | PoolRef::InterfaceMethod(m)))) => {
+                let (owner, name) = (m.owner.to_string(), m.name.to_string());
+                if matches!(owner.as_str(), "java/lang/System" | "java/lang/Runtime") && matches!(name.as_str(), "load" | "loadLibrary") {
+                    let simple = owner.rsplit('/').next().unwrap_or(&owner);
+                    out.push(LibraryLoad {
+                        class: class.into(),
+                        method: method.into(),
+                        call: format!("{simple}.{name}").into_boxed_str(),
+                        library: last.take(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Finds native libraries, `native` methods and library loading calls of a jar,
+/// including those of the archives nested in it.
+pub fn gather_native_usage<RS: Read + Seek>(fm: &FileMap, rs: &mut RS) -> anyhow::Result<NativeUsage> {
+    let mut nu = NativeUsage { libs: vec![], methods: vec![], loads: vec![], unreadable: vec![] };
+    collect_native_usage(fm, rs, None, 0, &mut nu)?;
+    Ok(nu)
+}
+
+fn collect_native_usage<RS: Read + Seek>(fm: &FileMap, rs: &mut RS, archive: Option<&str>, depth: usize, nu: &mut NativeUsage) -> anyhow::Result<()> {
+    for (k, fe) in fm.iter() {
+        let path = || archive.map_or_else(|| k.clone(), |a| nested::entry_path(a, k));
+        if is_native_lib(k) {
+            let mut b = vec![];
+            fe.reader(rs)?.take(HEADER_SIZE).read_to_end(&mut b)?;
+            let (format, os, archs, bits) = read_header(&b);
+            nu.libs.push(NativeLib { path: path(), size: fe.size(), format, os, archs, bits });
+            continue;
+        }
+        if nested::is_archive(k) && depth < nested::MAX_DEPTH {
+            // Nested archives which are too large or cannot be read are skipped
+            if let Ok((nfm, mut data)) = nested::open(fe, rs) {
+                collect_native_usage(&nfm, &mut data, Some(&path()), depth + 1, nu)?;
+            }
+            continue;
+        }
+        if !Extension::Class.matches(k.as_ref()) {
+            continue;
+        }
+        // A malformed class does not hide the rest of the jar
+        if let Err(e) = collect_class(fe.reader(rs)?, nu) {
+            nu.unreadable.push((path(), e.to_string().into_boxed_str()));
+        }
+    }
+    Ok(())
+}
+
+fn collect_class(r: impl Read, nu: &mut NativeUsage) -> anyhow::Result<()> {
+    let jcr = JClassReader::new(r)?;
+    let pool = jcr.pool().clone();
+    let class = jcr.class_name()?.to_string();
+    let (_, ms) = jcr.skip_interfaces()?.skip_fields()?.methods()?;
+    for m in ms {
+        let mut m = m?;
+        let name = m.name()?.to_string();
+        if m.access_flags() & ACC_NATIVE != 0 {
+            nu.methods.push((class.as_str().into(), format!("{name}{}", m.descriptor()?).into_boxed_str()));
+            continue;
+        }
+        for ai in m.attrs() {
+            let ai = ai?;
+            if let JAttr::Code(k) = ai.kind()? {
+                scan_loads(&pool, &ai.read(&k)?, &class, &name, &mut nu.loads)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct NativeSummary {
+    portability: Portability,
+    libs: usize,
+    methods: usize,
+    loads: usize,
+    /// Distinct OS and architecture pairs of the bundled libraries
+    platforms: Vec<(Os, Box<str>)>,
+}
+
+/// Summarizes the jars using native code.
+pub fn summaries(it: impl Iterator<Item = (Id, Arc<NativeUsage>)>) -> Vec<(Id, NativeSummary)> {
+    it.filter_map(|(id, nu)| {
+        let portability = nu.portability();
+        if portability == Portability::Portable {
+            return None;
+        }
+        let mut platforms = nu.libs.iter()
+            .flat_map(|l| l.archs.iter().map(|a| (l.os, a.clone())))
+            .collect::<Vec<_>>();
+        platforms.sort();
+        platforms.dedup();
+        Some((id, NativeSummary {
+            portability,
+            libs: nu.libs.len(),
+            methods: nu.methods.len(),
+            loads: nu.loads.len(),
+            platforms,
+        }))
+    }).collect()
+}
@@ -0,0 +1,35 @@
+//! Archives nested in jars, like the jar-in-jar dependencies of Fabric (`META-INF/jars`)
+//! and Forge (`META-INF/jarjar`) mods, which bundle libraries such as LWJGL.
+
+use std::{io::{Cursor, Read, Seek}, path::Path};
+
+use cm_zipext::{FileEntry, FileMap};
+
+/// Archives nested deeper than this are not opened
+pub const MAX_DEPTH: usize = 3;
+/// Entries larger than this once decompressed are not read into memory
+pub const MAX_ENTRY_SIZE: u64 = 64 << 20;
+
+pub fn is_archive(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|x| x.eq_ignore_ascii_case("jar") || x.eq_ignore_ascii_case("zip"))
+}
+
+/// Path of an entry of a nested archive, as in `META-INF/jars/lwjgl.jar!/linux/x64/liblwjgl.so`
+pub fn entry_path(archive: &str, entry: &str) -> Box<str> {
+    format!("{archive}!/{entry}").into_boxed_str()
+}
+
+/// Reads an entry, failing if it is larger than [`MAX_ENTRY_SIZE`] once decompressed.
+pub fn read_capped<RS: Read + Seek>(fe: &FileEntry, rs: &mut RS) -> anyhow::Result<Vec<u8>> {
+    if fe.size() > MAX_ENTRY_SIZE {
+        anyhow::bail!("Entry of {} bytes exceeds the limit of {MAX_ENTRY_SIZE} bytes", fe.size());
+    }
+    fe.vec_from(rs)
+}
+
+/// Opens an archive entry, returning its file map and a reader of its data.
+pub fn open<RS: Read + Seek>(fe: &FileEntry, rs: &mut RS) -> anyhow::Result<(FileMap, Cursor<Vec<u8>>)> {
+    let mut data = Cursor::new(read_capped(fe, rs)?);
+    let fm = FileMap::from_zip_read_seek(&mut data)?;
+    Ok((fm, data))
+}
@@ -0,0 +1,210 @@
+use std::{cmp::Reverse, collections::{HashMap, HashSet}, io::{Read, Seek}, sync::Arc};
+
+use cm_jclass::{idx::{AnyMethodRef, ClassInfo, FieldRef, Index, NameAndType}, jtype::JavaType, pool::{ClassPool, PoolIter}, JClassReader, JStr};
+use cm_zipext::FileMap;
+use serde::Serialize;
+
+use crate::{ext::Extension, id::Id, mappings::{Remap, Remapper}};
+
+/// Package prefixes of the Java platform, which are not indexed
+const PLATFORM_PACKAGES: &[&str] = &["java/", "javax/", "jdk/", "sun/", "com/sun/"];
+const MINECRAFT_PACKAGES: &[&str] = &["net/minecraft/", "com/mojang/"];
+const LOADER_PACKAGES: &[&str] = &["net/minecraftforge/", "net/neoforged/", "net/fabricmc/", "cpw/mods/", "org/spongepowered/asm/"];
+
+const MAX_SEARCH_RESULTS: usize = 500;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum RefKind {
+    Class,
+    Field,
+    Method,
+}
+
+#[derive(Serialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ApiRef {
+    owner: Box<str>,
+    kind: RefKind,
+    name: Box<str>,
+    desc: Box<str>,
+}
+impl Remap for ApiRef {
+    fn remap(&mut self, r: &Remapper) {
+        if self.kind != RefKind::Class {
+            r.member(&self.owner, &mut self.name, Some(&self.desc));
+            r.desc(&mut self.desc);
+        }
+        r.class(&mut self.owner);
+    }
+}
+impl ApiRef {
+    fn matches(&self, q: &str) -> bool {
+        let hay = format!("{}.{}{}", self.owner, self.name, self.desc).to_lowercase();
+        hay.contains(q) || hay.replace('/', ".").contains(q)
+    }
+}
+
+/// External classes, fields and methods referenced by the classes of a jar
+#[derive(Serialize)]
+pub struct ClassRefs {
+    pub classes: Box<[Box<str>]>,
+    pub refs: Box<[(ApiRef, Box<[usize]>)]>,
+}
+
+fn class_of_ref(s: &JStr) -> Option<Box<str>> {
+    let s = s.to_string();
+    let name = if s.starts_with('[') {
+        // Array classes use field descriptors
+        match JavaType::from_desc(&s).ok()?.element() {
+            JavaType::Class(c) => c.name.clone(),
+            _ => return None,
+        }
+    } else {
+        s.into_boxed_str()
+    };
+    (!PLATFORM_PACKAGES.iter().any(|p| name.starts_with(p))).then_some(name)
+}
+
+fn member_ref(pool: &ClassPool, kind: RefKind, (ci, nt): (Index<ClassInfo>, Index<NameAndType>)) -> anyhow::Result<Option<ApiRef>> {
+    let Some(owner) = class_of_ref(pool.get(pool.get(ci)?)?) else {
+        return Ok(None);
+    };
+    let (n, d) = pool.get(nt)?;
+    Ok(Some(ApiRef {
+        owner,
+        kind,
+        name: pool.get(n)?.to_string().into_boxed_str(),
+        desc: pool.get(d)?.to_string().into_boxed_str(),
+    }))
+}
+
+pub fn gather_class_refs<RS: Read + Seek>(fm: &FileMap, rs: &mut RS) -> anyhow::Result<ClassRefs> {
+    let local = fm.keys()
+        .filter_map(|k| k.strip_suffix(".class"))
+        .collect::<HashSet<_>>();
+    let mut classes = vec![];
+    let mut refs = HashMap::<ApiRef, Vec<usize>>::new();
+    for (_, fe) in fm
+        .iter()
+        .filter(|(k, _)| Extension::Class.matches(k.as_ref()))
+    {
+        let jcr = JClassReader::new(fe.reader(rs)?)?;
+        let pool = jcr.pool();
+        let ci = classes.len();
+        classes.push(jcr.class_name()?.to_string().into_boxed_str());
+        let mut found = HashSet::new();
+        for x in pool.iter().by_type::<ClassInfo>() {
+            if let Some(owner) = class_of_ref(pool.get(x)?) {
+                found.insert(ApiRef { owner, kind: RefKind::Class, name: "".into(), desc: "".into() });
+            }
+        }
+        for x in pool.iter().by_type::<FieldRef>() {
+            found.extend(member_ref(pool, RefKind::Field, x)?);
+        }
+        for (_, c, nt) in pool.iter().by_type::<AnyMethodRef>() {
+            found.extend(member_ref(pool, RefKind::Method, (c, nt))?);
+        }
+        for r in found {
+            if !local.contains(&*r.owner) {
+                refs.entry(r).or_default().push(ci);
+            }
+        }
+    }
+    let mut refs = refs.into_iter()
+        .map(|(k, v)| (k, v.into_boxed_slice()))
+        .collect::<Box<_>>();
+    refs.sort();
+    Ok(ClassRefs { classes: classes.into_boxed_slice(), refs })
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(tag = "type", content = "id", rename_all = "lowercase")]
+pub enum Provider {
+    Minecraft,
+    Loader,
+    Mod(Id),
+    Unknown,
+}
+
+/// Maps class names to the workspace files defining them
+pub struct Providers(HashMap<Box<str>, Id>);
+impl Providers {
+    pub fn new(it: impl Iterator<Item = (Id, Arc<FileMap>)>) -> Self {
+        let mut m = HashMap::new();
+        for (id, fm) in it {
+            for k in fm.keys().filter_map(|k| k.strip_suffix(".class")) {
+                m.entry(k.into()).or_insert(id);
+            }
+        }
+        Self(m)
+    }
+    pub fn of(&self, owner: &str) -> Provider {
+        self.0.get(owner).map_or_else(|| {
+            if MINECRAFT_PACKAGES.iter().any(|p| owner.starts_with(p)) {
+                Provider::Minecraft
+            } else if LOADER_PACKAGES.iter().any(|p| owner.starts_with(p)) {
+                Provider::Loader
+            } else {
+                Provider::Unknown
+            }
+        }, |id| Provider::Mod(*id))
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProviderUsage {
+    provider: Provider,
+    apis: Vec<(ApiRef, usize)>,
+}
+impl Remap for ProviderUsage {
+    fn remap(&mut self, r: &Remapper) {
+        self.apis.iter_mut().for_each(|(api, _)| api.remap(r));
+    }
+}
+
+/// Groups APIs used by a single jar by the mod (or Minecraft) providing them.
+pub fn usage_by_provider(cr: &ClassRefs, providers: &Providers) -> Vec<ProviderUsage> {
+    let mut m = HashMap::<Provider, Vec<(ApiRef, usize)>>::new();
+    for (r, users) in &cr.refs {
+        m.entry(providers.of(&r.owner)).or_default().push((r.clone(), users.len()));
+    }
+    let mut v = m.into_iter()
+        .map(|(provider, apis)| ProviderUsage { provider, apis })
+        .collect::<Vec<_>>();
+    v.sort_by_key(|pu| Reverse(pu.apis.len()));
+    v
+}
+
+#[derive(Serialize)]
+pub struct ApiUsers {
+    api: ApiRef,
+    provider: Provider,
+    users: Vec<(Id, Box<[Box<str>]>)>,
+}
+impl Remap for ApiUsers {
+    fn remap(&mut self, r: &Remapper) {
+        self.api.remap(r);
+        self.users.iter_mut().flat_map(|(_, classes)| classes.iter_mut()).for_each(|c| r.class(c));
+    }
+}
+
+/// Finds mods referencing APIs which match the query.
+pub fn search_users(query: &str, it: impl Iterator<Item = (Id, Arc<ClassRefs>)>, providers: &Providers) -> Vec<ApiUsers> {
+    let q = query.trim().to_lowercase();
+    if q.is_empty() {
+        return vec![];
+    }
+    let mut m = HashMap::<ApiRef, Vec<(Id, Box<[Box<str>]>)>>::new();
+    for (id, cr) in it {
+        for (r, users) in cr.refs.iter().filter(|(r, _)| r.matches(&q)) {
+            let classes = users.iter().map(|&i| cr.classes[i].clone()).collect();
+            m.entry(r.clone()).or_default().push((id, classes));
+        }
+    }
+    let mut v = m.into_iter()
+        .map(|(api, users)| ApiUsers { provider: providers.of(&api.owner), api, users })
+        .collect::<Vec<_>>();
+    v.sort_by(|a, b| b.users.len().cmp(&a.users.len()).then_with(|| a.api.cmp(&b.api)));
+    v.truncate(MAX_SEARCH_RESULTS);
+    v
+}
@@ -0,0 +1,321 @@
+use std::{collections::{BTreeMap, HashMap, HashSet}, io::{Read, Seek}, sync::Arc};
+
+use cm_jclass::{attr::{Code, JAttr}, idx::Utf8, pool::{ClassPool, PoolIter, PoolRef}, JClassReader};
+use cm_zipext::FileMap;
+use serde::Serialize;
+
+use crate::{ext::Extension, id::Id, slice::{iter_extend, ExtendSelf}};
+
+/// Namespaces shared by many mods, which do not identify the mod owning a jar
+const SHARED_NAMESPACES: &[&str] = &["minecraft", "c", "forge", "neoforge", "fabric", "common"];
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistryKind {
+    Block,
+    Item,
+    Entity,
+    BlockEntity,
+    Menu,
+    Sound,
+    Particle,
+}
+use RegistryKind::*;
+
+/// Names of registry fields (`Registries.BLOCK`, `ForgeRegistries.BLOCKS`) and of the
+/// `DeferredRegister` fields mods conventionally declare, longest first
+const FIELD_NAMES: &[(&str, RegistryKind)] = &[
+    ("BLOCK_ENTITY_TYPES", BlockEntity), ("BLOCK_ENTITY_TYPE", BlockEntity),
+    ("BLOCK_ENTITIES", BlockEntity), ("BLOCK_ENTITY", BlockEntity),
+    ("TILE_ENTITY_TYPES", BlockEntity), ("TILE_ENTITY_TYPE", BlockEntity), ("TILE_ENTITIES", BlockEntity),
+    ("BLOCKS", Block), ("BLOCK", Block),
+    ("ITEMS", Item), ("ITEM", Item),
+    ("ENTITY_TYPES", Entity), ("ENTITY_TYPE", Entity), ("ENTITIES", Entity), ("ENTITY", Entity),
+    ("MENU_TYPES", Menu), ("MENU_TYPE", Menu), ("MENUS", Menu), ("MENU", Menu),
+    ("CONTAINER_TYPES", Menu), ("CONTAINER_TYPE", Menu), ("CONTAINERS", Menu), ("SCREEN_HANDLER", Menu),
+    ("SOUND_EVENTS", Sound), ("SOUND_EVENT", Sound), ("SOUNDS", Sound),
+    ("PARTICLE_TYPES", Particle), ("PARTICLE_TYPE", Particle), ("PARTICLES", Particle),
+];
+
+/// Intermediary names of the vanilla registry fields, as referenced by Fabric mods
+const INTERMEDIARY_FIELDS: &[(&str, RegistryKind)] = &[
+    // `BuiltInRegistries` (1.19.3 and newer)
+    ("field_41175", Block), ("field_41178", Item), ("field_41177", Entity), ("field_41181", BlockEntity),
+    ("field_41187", Menu), ("field_41172", Sound), ("field_41180", Particle),
+    // `Registry` (older versions)
+    ("field_11146", Block), ("field_11142", Item), ("field_11145", Entity), ("field_11137", BlockEntity),
+    ("field_17429", Menu), ("field_11156", Sound), ("field_11141", Particle),
+];
+const INTERMEDIARY_REGISTRY: &str = "net/minecraft/class_2378";
+/// Intermediary names of the `Registry.register` overloads
+const INTERMEDIARY_REGISTER: &[&str] = &["method_10230", "method_10226", "method_39197"];
+
+impl RegistryKind {
+    /// Guesses the registry from the name of a static field (`BLOCKS`, `blockEntities`).
+    fn from_field(name: &str) -> Option<Self> {
+        if let Some((_, k)) = INTERMEDIARY_FIELDS.iter().find(|(n, _)| *n == name) {
+            return Some(*k);
+        }
+        let mut upper = String::with_capacity(name.len() + 4);
+        for (i, c) in name.char_indices() {
+            if i > 0 && c.is_ascii_uppercase() && name[..i].ends_with(|p: char| p.is_ascii_lowercase()) {
+                upper.push('_');
+            }
+            upper.push(c.to_ascii_uppercase());
+        }
+        FIELD_NAMES.iter()
+            .find(|(n, _)| upper.strip_suffix(n).is_some_and(|p| p.is_empty() || p.ends_with('_')))
+            .map(|(_, k)| *k)
+    }
+    fn from_lang_prefix(s: &str) -> Option<Self> {
+        Some(match s {
+            "block" => Block,
+            "item" => Item,
+            "entity" => Entity,
+            _ => return None,
+        })
+    }
+}
+
+/// Where a registry entry was found
+#[derive(Serialize, Clone, Copy, Default)]
+pub struct Sources {
+    /// A registration call in bytecode
+    bytecode: bool,
+    /// A `namespace:path` string constant
+    strings: bool,
+    /// Blockstates, models, lang keys and other assets
+    assets: bool,
+}
+impl Sources {
+    const fn merge(&mut self, other: Self) {
+        self.bytecode |= other.bytecode;
+        self.strings |= other.strings;
+        self.assets |= other.assets;
+    }
+}
+
+/// Registry entries of a mod, found heuristically
+#[derive(Serialize, Default)]
+pub struct Registrations {
+    entries: BTreeMap<RegistryKind, BTreeMap<Box<str>, Sources>>,
+    /// `namespace:path` constants of the mod namespaces not matched to a registry
+    unclassified: usize,
+}
+impl Registrations {
+    fn add(&mut self, kind: RegistryKind, id: Box<str>, src: Sources) {
+        self.entries.entry(kind).or_default().entry(id).or_default().merge(src);
+    }
+    pub fn counts(&self) -> BTreeMap<RegistryKind, usize> {
+        self.entries.iter().map(|(k, v)| (*k, v.len())).collect()
+    }
+}
+impl ExtendSelf for Registrations {
+    fn extend(&mut self, other: &Self) {
+        for (kind, ids) in &other.entries {
+            for (id, src) in ids {
+                self.add(*kind, id.clone(), *src);
+            }
+        }
+        self.unclassified += other.unclassified;
+    }
+}
+iter_extend!(Registrations);
+
+fn is_id_part(s: &str, path: bool) -> bool {
+    !s.is_empty() && s.bytes().all(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'_' | b'.' | b'-') || (path && b == b'/'))
+}
+
+fn split_id(s: &str) -> Option<(&str, &str)> {
+    s.split_once(':').filter(|(ns, path)| is_id_part(ns, false) && is_id_part(path, true))
+}
+
+/// Returns `Some` for calls registering an entry, with the registry if implied by the call.
+fn register_call(owner: &str, name: &str) -> Option<Option<RegistryKind>> {
+    let simple = owner.rsplit('/').next().unwrap_or(owner);
+    match (simple, name) {
+        ("DeferredRegister$Blocks", "registerBlock" | "registerSimpleBlock") => Some(Some(Block)),
+        ("DeferredRegister$Items", "registerItem" | "registerSimpleItem" | "registerSimpleBlockItem") => Some(Some(Item)),
+        (s, "register") if s.starts_with("DeferredRegister") => Some(None),
+        ("Registry" | "IForgeRegistry" | "Registries", "register") => Some(None),
+        // Forge 1.12 and older
+        (_, "setRegistryName") => Some(None),
+        _ if owner == INTERMEDIARY_REGISTRY && INTERMEDIARY_REGISTER.contains(&name) => Some(None),
+        _ => None,
+    }
+}
+
+/// Scans a method for registration calls, pairing each with the last registry field
+/// and the last identifier string loaded before it.
+fn scan_code(pool: &ClassPool, code: &Code, found: &mut Vec<(RegistryKind, Box<str>)>) -> anyhow::Result<()> {
+    let mut kind = None;
+    let mut last = None;
+    for insn in code.insns() {
+        let insn = insn?;
+        match (insn.opcode(), insn.resolve(pool)) {
+            // ldc, ldc_w
+            (0x12 | 0x13, Ok(Some(PoolRef::Str(s)))) => {
+                last = Some(s.to_string()).filter(|s| is_id_part(s, true) || split_id(s).is_some());
+            }
+            // getstatic
+            (0xb2, Ok(Some(PoolRef::Field(f)))) => {
+                if let Some(k) = RegistryKind::from_field(&f.name.to_string()) {
+                    kind = Some(k);
+                }
+            }
+            // invokevirtual, invokespecial, invokestatic, invokeinterface
+            (0xb6..=0xb9, Ok(Some(PoolRef::Method(m) | PoolRef::InterfaceMethod(m)))) => {
+                if let Some(implied) = register_call(&m.owner.to_string(), &m.name.to_string()) {
+                    if let (Some(k), Some(s)) = (implied.or(kind), last.take()) {
+                        found.push((k, s.into_boxed_str()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Keys of a JSON object file, or none if it cannot be read
+fn json_keys<RS: Read + Seek>(fe: &cm_zipext::FileEntry, rs: &mut RS) -> Vec<Box<str>> {
+    fe.reader(rs).ok()
+        .and_then(|r| serde_json::from_reader::<_, HashMap<Box<str>, serde_json::Value>>(r).ok())
+        .map(|m| m.into_keys().collect())
+        .unwrap_or_default()
+}
+
+fn scan_assets<RS: Read + Seek>(fm: &FileMap, rs: &mut RS, regs: &mut Registrations) {
+    let src = Sources { assets: true, ..Sources::default() };
+    for (k, fe) in fm.iter() {
+        let Some((ns, rest)) = k.strip_prefix("assets/").and_then(|p| p.split_once('/')) else { continue };
+        if !Extension::Json.matches(rest) {
+            continue;
+        }
+        let stem = |dir: &str| rest.strip_prefix(dir).and_then(|p| p.strip_suffix(".json"));
+        let id = |path: &str| format!("{ns}:{path}").into_boxed_str();
+        if let Some(p) = stem("blockstates/") {
+            regs.add(Block, id(p), src);
+        } else if let Some(p) = stem("models/item/").or_else(|| stem("items/")) {
+            regs.add(Item, id(p), src);
+        } else if let Some(p) = stem("particles/") {
+            regs.add(Particle, id(p), src);
+        } else if rest == "sounds.json" {
+            for s in json_keys(fe, rs) {
+                regs.add(Sound, id(&s), src);
+            }
+        } else if rest == "lang/en_us.json" {
+            for key in json_keys(fe, rs) {
+                let mut it = key.splitn(3, '.');
+                let (Some(prefix), Some(kns), Some(path)) = (it.next(), it.next(), it.next()) else { continue };
+                if let Some(kind) = RegistryKind::from_lang_prefix(prefix).filter(|_| kns == ns && is_id_part(path, true) && !path.contains('.')) {
+                    regs.add(kind, format!("{kns}:{path}").into_boxed_str(), src);
+                }
+            }
+        }
+    }
+}
+
+/// Namespaces of the assets and data of a jar, most used first
+fn mod_namespaces(fm: &FileMap) -> Vec<Box<str>> {
+    let mut m = HashMap::<&str, usize>::new();
+    for k in fm.keys() {
+        if let Some((ns, _)) = k.strip_prefix("assets/").or_else(|| k.strip_prefix("data/")).and_then(|p| p.split_once('/')) {
+            if !SHARED_NAMESPACES.contains(&ns) {
+                *m.entry(ns).or_default() += 1;
+            }
+        }
+    }
+    let mut v = m.into_iter().collect::<Vec<_>>();
+    v.sort_by_key(|(ns, n)| (std::cmp::Reverse(*n), *ns));
+    v.into_iter().map(|(ns, _)| ns.into()).collect()
+}
+
+/// Finds the blocks, items, entities, block entities, menus, sounds and particles a mod
+/// registers, from registration calls, identifier constants and assets.
+pub fn gather_registrations<RS: Read + Seek>(fm: &FileMap, rs: &mut RS) -> anyhow::Result<Registrations> {
+    let namespaces = mod_namespaces(fm);
+    let mut found = vec![];
+    let mut ids = HashSet::<Box<str>>::new();
+    for (_, fe) in fm
+        .iter()
+        .filter(|(k, _)| Extension::Class.matches(k.as_ref()))
+    {
+        let jcr = JClassReader::new(fe.reader(rs)?)?;
+        let pool = jcr.pool().clone();
+        for s in pool.iter().by_type::<Utf8>() {
+            let s = s.to_string();
+            if split_id(&s).is_some_and(|(ns, _)| namespaces.iter().any(|n| &**n == ns)) {
+                ids.insert(s.into_boxed_str());
+            }
+        }
+        let (_, methods) = jcr.skip_interfaces()?.skip_fields()?.methods()?;
+        for m in methods {
+            for ai in m?.attrs() {
+                let ai = ai?;
+                if let JAttr::Code(k) = ai.kind()? {
+                    scan_code(&pool, &ai.read(&k)?, &mut found)?;
+                }
+            }
+        }
+    }
+    let mut regs = Registrations::default();
+    scan_assets(fm, rs, &mut regs);
+    let bytecode = Sources { bytecode: true, ..Sources::default() };
+    for (kind, s) in found {
+        let id = match (split_id(&s), namespaces.first()) {
+            (Some(_), _) => s,
+            (None, Some(ns)) => format!("{ns}:{s}").into_boxed_str(),
+            (None, None) => continue,
+        };
+        regs.add(kind, id, bytecode);
+    }
+    // An ID registered in several registries, like a block and its item, is matched once
+    let mut matched = HashSet::new();
+    for (id, src) in regs.entries.values_mut().flat_map(|m| m.iter_mut()) {
+        if ids.contains(id) {
+            src.strings = true;
+            matched.insert(id.clone());
+        }
+    }
+    regs.unclassified = ids.len() - matched.len();
+    Ok(regs)
+}
+
+/// Numeric registry ID limits of Minecraft 1.12 and older. The flattening of 1.13 removed them,
+/// and the other kinds counted here had no fixed limit in any version, so no other entries apply.
+const LEGACY_LIMITS: &[(RegistryKind, usize)] = &[(Block, 4096), (Item, 32000)];
+
+#[derive(Serialize)]
+pub struct RegistryTotals {
+    minecraft: Option<Box<str>>,
+    totals: BTreeMap<RegistryKind, usize>,
+    /// ID limits applying to the instance's Minecraft version
+    limits: BTreeMap<RegistryKind, usize>,
+    mods: Vec<(Id, BTreeMap<RegistryKind, usize>)>,
+}
+
+fn is_legacy(minecraft: &str) -> bool {
+    let mut it = minecraft.split(['.', '-', ' ']).map(|x| x.parse::<u32>().ok());
+    matches!((it.next().flatten(), it.next().flatten()), (Some(1), Some(..=12)))
+}
+
+/// Counts registry entries per mod and across the workspace.
+pub fn registry_totals(
+    minecraft: Option<Box<str>>,
+    it: impl Iterator<Item = (Id, Arc<Registrations>)>,
+) -> RegistryTotals {
+    let mut all = Registrations::default();
+    let mut mods = vec![];
+    for (id, regs) in it {
+        mods.push((id, regs.counts()));
+        all.extend(&regs);
+    }
+    mods.sort_by_key(|(_, c)| std::cmp::Reverse(c.values().sum::<usize>()));
+    let limits = if minecraft.as_deref().is_some_and(is_legacy) {
+        LEGACY_LIMITS.iter().copied().collect()
+    } else {
+        BTreeMap::new()
+    };
+    RegistryTotals { minecraft, totals: all.counts(), limits, mods }
+}
@@ -0,0 +1,552 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::{Read, Seek},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use base64::{
+    engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD},
+    Engine,
+};
+use cm_jclass::{attr::JAttr, pool::{ClassPool, PoolItem}, JClassReader};
+use cm_zipext::FileMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{ext::Extension, id::Id, nested};
+
+/// String constants shorter than this are not checked for blobs
+const MIN_BLOB_CHARS: usize = 1024;
+/// String constants shorter than this are not checked for entropy
+const MIN_ENTROPY_CHARS: usize = 512;
+/// Bits per character above which a long string is considered random
+const STRING_ENTROPY: f64 = 5.5;
+/// Files smaller than this are not checked for entropy
+const MIN_ENTROPY_BYTES: u64 = 4096;
+/// Bits per byte above which a file is considered encrypted or compressed
+const FILE_ENTROPY: f64 = 7.9;
+/// Extensions of media, compressed and native files, which are compressed or checked elsewhere.
+/// Jar and zip archives are opened and scanned like the jar containing them.
+const SKIPPED_EXTENSIONS: &[&str] = &[
+    "png", "ogg", "gz", "nbt", "jpg", "jpeg", "gif", "webp", "ttf", "otf", "woff", "woff2",
+    "mp3", "wav", "fsb", "bank", "dll", "so", "dylib", "jnilib",
+];
+/// Headers of compressed formats, whose high entropy is expected
+const COMPRESSED_MAGIC: &[&[u8]] = &[
+    b"PK\x03\x04", b"\x1f\x8b", b"\x89PNG", b"OggS", b"\xff\xd8\xff", b"RIFF", b"GIF8", b"OTTO", b"wOFF",
+    b"wOF2", b"\0\x01\0\0", b"BZh", b"\xfd7zXZ", b"7z\xbc\xaf", b"\x28\xb5\x2f\xfd",
+];
+/// Longest matched text kept in a finding
+const MAX_DETAIL: usize = 200;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+impl Severity {
+    /// Points added to the risk score by each rule with findings
+    const fn weight(self) -> u32 {
+        match self {
+            Self::Info => 0,
+            Self::Low => 2,
+            Self::Medium => 10,
+            Self::High => 25,
+            Self::Critical => 100,
+        }
+    }
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "info" => Self::Info,
+            "low" => Self::Low,
+            "medium" => Self::Medium,
+            "high" => Self::High,
+            "critical" => Self::Critical,
+            _ => return None,
+        })
+    }
+}
+
+/// A built-in check
+struct Check {
+    id: &'static str,
+    severity: Severity,
+    title: &'static str,
+}
+const PROCESS: Check = Check { id: "process", severity: Severity::High, title: "Starts external processes" };
+const CLASS_LOADER: Check = Check { id: "class-loader", severity: Severity::High, title: "Loads classes from URLs" };
+const DEFINE_CLASS: Check = Check { id: "define-class", severity: Severity::High, title: "Defines classes from bytes" };
+const UNSAFE: Check = Check { id: "unsafe", severity: Severity::Medium, title: "Uses Unsafe" };
+const NATIVE: Check = Check { id: "native", severity: Severity::Medium, title: "Loads native libraries" };
+const NETWORK: Check = Check { id: "network", severity: Severity::Low, title: "Opens network connections" };
+const REFLECT_INTERNAL: Check = Check { id: "reflect-internal", severity: Severity::Medium, title: "Names internal JDK classes" };
+const EMBEDDED_CLASS: Check = Check { id: "embedded-class", severity: Severity::Critical, title: "Embeds an encoded class file" };
+const EMBEDDED_ARCHIVE: Check = Check { id: "embedded-archive", severity: Severity::High, title: "Embeds an encoded archive" };
+const BASE64_BLOB: Check = Check { id: "base64-blob", severity: Severity::Medium, title: "Contains a large base64 blob" };
+const RANDOM_STRING: Check = Check { id: "random-string", severity: Severity::Low, title: "Contains a long high-entropy string" };
+const RANDOM_FILE: Check = Check { id: "random-file", severity: Severity::Medium, title: "Contains an unrecognized high-entropy file" };
+const BAD_CLASS: Check = Check { id: "bad-class", severity: Severity::High, title: "Contains a class file without a class header" };
+const MALFORMED_CLASS: Check = Check { id: "malformed-class", severity: Severity::Medium, title: "Contains a malformed class file" };
+const OVERSIZED_ENTRY: Check = Check { id: "oversized-entry", severity: Severity::Medium, title: "Contains an entry too large to scan" };
+const DEEP_ARCHIVE: Check = Check { id: "deep-archive", severity: Severity::Medium, title: "Contains archives nested too deep to scan" };
+
+/// Owners and members of checked APIs, an empty member matching any member and the class itself
+const API_CHECKS: &[(&str, &str, &Check)] = &[
+    ("java/lang/Runtime", "exec", &PROCESS),
+    ("java/lang/ProcessBuilder", "", &PROCESS),
+    ("java/net/URLClassLoader", "", &CLASS_LOADER),
+    ("sun/misc/Unsafe", "", &UNSAFE),
+    ("jdk/internal/misc/Unsafe", "", &UNSAFE),
+    ("java/lang/System", "load", &NATIVE),
+    ("java/lang/System", "loadLibrary", &NATIVE),
+    ("java/lang/Runtime", "load", &NATIVE),
+    ("java/lang/Runtime", "loadLibrary", &NATIVE),
+    ("java/net/Socket", "", &NETWORK),
+    ("java/net/ServerSocket", "", &NETWORK),
+    ("java/net/DatagramSocket", "", &NETWORK),
+    ("java/nio/channels/SocketChannel", "", &NETWORK),
+    ("java/net/HttpURLConnection", "", &NETWORK),
+    ("java/net/http/HttpClient", "", &NETWORK),
+    ("java/net/URL", "openConnection", &NETWORK),
+    ("java/net/URL", "openStream", &NETWORK),
+];
+/// Methods defining classes, checked on any owner since class loaders override them
+const DEFINE_METHODS: &[&str] = &["defineClass", "defineClass0", "defineClass1", "defineHiddenClass", "defineAnonymousClass"];
+
+fn api_check(owner: &str, member: Option<&str>) -> Option<&'static Check> {
+    if member.is_some_and(|m| DEFINE_METHODS.contains(&m)) {
+        return Some(&DEFINE_CLASS);
+    }
+    API_CHECKS.iter()
+        .find(|(o, m, _)| *o == owner && (m.is_empty() || Some(*m) == member))
+        .map(|(_, _, c)| *c)
+}
+
+/// Where a finding was made: a class file or another entry of the jar
+#[derive(Serialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    /// Entry path, through nested archives as in `META-INF/jars/lib.jar!/com/example/Lib.class`
+    entry: Box<str>,
+    /// Name and descriptor of the method
+    member: Option<Box<str>>,
+    /// Bytecode offset in the method
+    offset: Option<usize>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Finding {
+    /// Identifier of a built-in check, or `rule:<line>` of a rules file
+    rule: Box<str>,
+    severity: Severity,
+    title: Box<str>,
+    detail: Box<str>,
+    location: Location,
+}
+
+fn truncate(s: &str) -> Box<str> {
+    match s.char_indices().nth(MAX_DETAIL) {
+        Some((i, _)) => format!("{}…", &s[..i]).into_boxed_str(),
+        None => s.into(),
+    }
+}
+
+/// Shannon entropy in bits per symbol
+fn entropy<T: Eq + std::hash::Hash>(it: impl Iterator<Item = T>) -> f64 {
+    let mut counts = HashMap::new();
+    let mut total = 0usize;
+    for x in it {
+        *counts.entry(x).or_insert(0usize) += 1;
+        total += 1;
+    }
+    counts.values()
+        .map(|&n| {
+            let p = n as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Checks a long string constant for encoded payloads.
+fn blob_check(s: &str) -> Option<(&'static Check, Box<str>)> {
+    if s.len() < MIN_ENTROPY_CHARS {
+        return None;
+    }
+    let t = s.trim();
+    if t.len() >= MIN_BLOB_CHARS && t.bytes().all(|b| b.is_ascii_alphanumeric() || b"+/=-_".contains(&b)) {
+        if let Some(data) = [STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD].iter().find_map(|e| e.decode(t).ok()) {
+            let detail = format!("{} bytes", data.len()).into_boxed_str();
+            return Some(if data.starts_with(b"\xca\xfe\xba\xbe") {
+                (&EMBEDDED_CLASS, detail)
+            } else if data.starts_with(b"PK\x03\x04") {
+                (&EMBEDDED_ARCHIVE, detail)
+            } else {
+                (&BASE64_BLOB, detail)
+            });
+        }
+    }
+    let e = entropy(s.chars());
+    (e >= STRING_ENTROPY).then(|| (&RANDOM_STRING, format!("{} chars, {e:.2} bits/char", s.chars().count()).into_boxed_str()))
+}
+
+enum Operand<'a> {
+    Member(&'a str, &'a str),
+    Class(&'a str),
+    Str(&'a str),
+}
+
+/// Resolved pool strings of a class, for checks which compare them many times
+struct Names(Vec<Option<Box<str>>>);
+impl Names {
+    fn new(pool: &ClassPool) -> Self {
+        Self(pool.iter().map(|pi| match pi {
+            PoolItem::Utf8(s) => Some(s.to_string().into_boxed_str()),
+            _ => None,
+        }).collect())
+    }
+    fn utf(&self, i: u16) -> Option<&str> {
+        self.0.get(i as usize)?.as_deref()
+    }
+    fn operand<'a>(&'a self, pool: &ClassPool, idx: u16) -> Option<Operand<'a>> {
+        let class = |c: u16| match pool.item(c).ok()? {
+            PoolItem::Class(n) => self.utf(n.get()),
+            _ => None,
+        };
+        Some(match pool.item(idx).ok()? {
+            PoolItem::RefField(c, nt) | PoolItem::RefMethod(c, nt) | PoolItem::RefInterfaceMethod(c, nt) => {
+                let PoolItem::NameAndType(n, _) = pool.item(nt.get()).ok()? else { return None };
+                Operand::Member(class(c.get())?, self.utf(n.get())?)
+            }
+            PoolItem::Class(n) => Operand::Class(self.utf(n.get())?),
+            PoolItem::String(s) => Operand::Str(self.utf(s.get())?),
+            _ => return None,
+        })
+    }
+}
+
+/// Findings of the built-in checks and the names matched by rules for a single jar
+pub struct ScanData {
+    findings: Vec<Finding>,
+    /// Paths of the entries, including those of nested archives
+    entries: Vec<Box<str>>,
+    /// Referenced classes (`owner`) and members (`owner.name`) with indices of the class entries
+    refs: HashMap<Box<str>, Vec<usize>>,
+    /// String constants with indices of the class entries
+    strings: HashMap<Box<str>, Vec<usize>>,
+}
+
+struct ClassScan<'a> {
+    entry: &'a str,
+    findings: &'a mut Vec<Finding>,
+    seen: HashSet<(&'static str, Box<str>)>,
+}
+impl ClassScan<'_> {
+    fn push(&mut self, check: &'static Check, detail: Box<str>, member: Option<&str>, offset: Option<usize>) {
+        // Each check reports a detail once per method, and once per class outside of code
+        let key = (check.id, format!("{detail} {}", member.unwrap_or_default()).into_boxed_str());
+        if !self.seen.insert(key) {
+            return;
+        }
+        self.findings.push(Finding {
+            rule: check.id.into(),
+            severity: check.severity,
+            title: check.title.into(),
+            detail,
+            location: Location { entry: self.entry.into(), member: member.map(Into::into), offset },
+        });
+    }
+    /// Pushes the finding of a constant operand, if any.
+    fn operand(&mut self, op: &Operand, member: Option<&str>, offset: Option<usize>) {
+        match *op {
+            Operand::Member(owner, name) => if let Some(c) = api_check(owner, Some(name)) {
+                self.push(c, format!("{owner}.{name}").into_boxed_str(), member, offset);
+            },
+            Operand::Class(owner) => if let Some(c) = api_check(owner, None) {
+                self.push(c, owner.into(), member, offset);
+            },
+            Operand::Str(s) => {
+                if s.starts_with("sun.") || s.starts_with("sun/") || s.starts_with("jdk.internal.") || s.starts_with("jdk/internal/") {
+                    self.push(&REFLECT_INTERNAL, truncate(s), member, offset);
+                }
+                if let Some((c, detail)) = blob_check(s) {
+                    self.push(c, detail, member, offset);
+                }
+            }
+        }
+    }
+}
+
+fn scan_class(data: &[u8], entry: &str, ci: usize, out: &mut ScanData) -> anyhow::Result<()> {
+    let jcr = JClassReader::new(data)?;
+    let pool = jcr.pool().clone();
+    let names = Names::new(&pool);
+    let mut cs = ClassScan { entry, findings: &mut out.findings, seen: HashSet::new() };
+    // Pool indices of instruction operands
+    let mut in_code = HashSet::new();
+    let (_, methods) = jcr.skip_interfaces()?.skip_fields()?.methods()?;
+    for m in methods {
+        let mut m = m?;
+        let member = format!("{}{}", m.name()?, m.descriptor()?);
+        for ai in m.attrs() {
+            let ai = ai?;
+            if let JAttr::Code(k) = ai.kind()? {
+                for insn in ai.read(&k)?.insns() {
+                    let insn = insn?;
+                    let Some(idx) = insn.pool_index() else { continue };
+                    let pc = insn.pc();
+                    in_code.insert(idx);
+                    if let Some(op) = names.operand(&pool, idx) {
+                        cs.operand(&op, Some(&member), Some(pc));
+                    }
+                }
+            }
+        }
+    }
+    // Constants outside of code, such as method handles, field values and annotations
+    for i in 1..pool.len() as u16 {
+        let Some(op) = names.operand(&pool, i) else { continue };
+        if !in_code.contains(&i) {
+            cs.operand(&op, None, None);
+        }
+        let (m, key) = match op {
+            Operand::Member(owner, name) => (&mut out.refs, format!("{owner}.{name}").into_boxed_str()),
+            Operand::Class(owner) => (&mut out.refs, owner.into()),
+            Operand::Str(s) => (&mut out.strings, s.into()),
+        };
+        let v = m.entry(key).or_default();
+        if v.last() != Some(&ci) {
+            v.push(ci);
+        }
+    }
+    Ok(())
+}
+
+fn is_skipped(path: &str) -> bool {
+    let ext = Path::new(path).extension().map(|x| x.to_ascii_lowercase());
+    ext.is_some_and(|x| SKIPPED_EXTENSIONS.iter().any(|s| x == *s))
+}
+
+/// Runs the built-in checks on the classes and files of a jar, and of the archives nested in it.
+pub fn gather_scan_data<RS: Read + Seek>(fm: &FileMap, rs: &mut RS) -> anyhow::Result<ScanData> {
+    let mut out = ScanData {
+        findings: vec![],
+        entries: vec![],
+        refs: HashMap::new(),
+        strings: HashMap::new(),
+    };
+    scan_archive(fm, rs, None, 0, &mut out)?;
+    Ok(out)
+}
+
+fn scan_archive<RS: Read + Seek>(fm: &FileMap, rs: &mut RS, archive: Option<&str>, depth: usize, out: &mut ScanData) -> anyhow::Result<()> {
+    for (k, fe) in fm.iter() {
+        let ci = out.entries.len();
+        let entry = archive.map_or_else(|| k.clone(), |a| nested::entry_path(a, k));
+        out.entries.push(entry.clone());
+        let location = || Location { entry: entry.clone(), member: None, offset: None };
+        let finding = |c: &Check, detail: String| Finding {
+            rule: c.id.into(),
+            severity: c.severity,
+            title: c.title.into(),
+            detail: detail.into_boxed_str(),
+            location: location(),
+        };
+        let is_class = Extension::Class.matches(k.as_ref());
+        let is_archive = nested::is_archive(k);
+        if !(is_class || is_archive || (fe.size() >= MIN_ENTROPY_BYTES && !is_skipped(k))) {
+            continue;
+        }
+        if fe.size() > nested::MAX_ENTRY_SIZE {
+            let f = finding(&OVERSIZED_ENTRY, format!("{} bytes, more than {}", fe.size(), nested::MAX_ENTRY_SIZE));
+            out.findings.push(f);
+            continue;
+        }
+        if is_archive {
+            if depth >= nested::MAX_DEPTH {
+                let f = finding(&DEEP_ARCHIVE, format!("nested more than {} levels deep", nested::MAX_DEPTH));
+                out.findings.push(f);
+                continue;
+            }
+            // Other files named like archives are checked for entropy below
+            if let Ok((nfm, mut data)) = nested::open(fe, rs) {
+                scan_archive(&nfm, &mut data, Some(&entry), depth + 1, out)?;
+                continue;
+            }
+        }
+        let data = fe.vec_from(rs)?;
+        if is_class {
+            if !data.starts_with(b"\xca\xfe\xba\xbe") {
+                let e = entropy(data.iter());
+                out.findings.push(finding(&BAD_CLASS, format!("{} bytes, {e:.2} bits/byte", data.len())));
+            } else if let Err(e) = scan_class(&data, &entry, ci, out) {
+                out.findings.push(finding(&MALFORMED_CLASS, e.to_string()));
+            }
+        } else if fe.size() >= MIN_ENTROPY_BYTES && !COMPRESSED_MAGIC.iter().any(|m| data.starts_with(m)) {
+            let e = entropy(data.iter());
+            if e >= FILE_ENTROPY {
+                out.findings.push(finding(&RANDOM_FILE, format!("{} bytes, {e:.2} bits/byte", data.len())));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RuleTarget {
+    /// Names of classes declared by the jar
+    Class,
+    /// Paths of entries
+    File,
+    /// Referenced classes (`owner`) and members (`owner.name`)
+    Ref,
+    /// String constants
+    String,
+}
+
+struct Rule {
+    line: usize,
+    severity: Severity,
+    target: RuleTarget,
+    pattern: regex::Regex,
+    title: Box<str>,
+}
+
+/// Known-malicious signatures from a local rules file.
+///
+/// Each line has a severity, a target (`class`, `file`, `ref` or `string`),
+/// a regular expression without spaces and a title:
+/// `critical class ^dev/neko/nekoclient/ fractureiser client`.
+/// Empty lines and lines starting with `#` are ignored.
+#[derive(Default)]
+pub struct ScanRules {
+    path: Option<Box<Path>>,
+    rules: Vec<Rule>,
+}
+impl ScanRules {
+    pub fn load(path: PathBuf) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(&path)?;
+        let mut rules = vec![];
+        for (i, l) in text.lines().enumerate() {
+            let l = l.trim();
+            if l.is_empty() || l.starts_with('#') {
+                continue;
+            }
+            let line = i + 1;
+            let mut parts = l.split_whitespace();
+            let (Some(severity), Some(target), Some(pattern)) = (parts.next(), parts.next(), parts.next()) else {
+                anyhow::bail!("line {line}: expected a severity, a target and a pattern");
+            };
+            let severity = Severity::parse(severity).ok_or_else(|| anyhow::anyhow!("line {line}: unknown severity {severity}"))?;
+            let target = match target {
+                "class" => RuleTarget::Class,
+                "file" => RuleTarget::File,
+                "ref" => RuleTarget::Ref,
+                "string" => RuleTarget::String,
+                _ => anyhow::bail!("line {line}: unknown target {target}"),
+            };
+            let pattern = regex::Regex::new(pattern).map_err(|e| anyhow::anyhow!("line {line}: {e}"))?;
+            let title = parts.collect::<Vec<_>>().join(" ");
+            rules.push(Rule {
+                line,
+                severity,
+                target,
+                pattern,
+                title: if title.is_empty() { format!("Matches rule {line}") } else { title }.into_boxed_str(),
+            });
+        }
+        Ok(Self { path: Some(path.into_boxed_path()), rules })
+    }
+    pub fn info(&self) -> RulesInfo {
+        RulesInfo { path: self.path.clone(), rules: self.rules.len() }
+    }
+    fn apply(&self, data: &ScanData, out: &mut Vec<Finding>) {
+        for r in &self.rules {
+            let mut push = |detail: &str, entry: &str| out.push(Finding {
+                rule: format!("rule:{}", r.line).into_boxed_str(),
+                severity: r.severity,
+                title: r.title.clone(),
+                detail: truncate(detail),
+                location: Location { entry: entry.into(), member: None, offset: None },
+            });
+            match r.target {
+                RuleTarget::Class => for e in &data.entries {
+                    // Classes of nested archives are named without the archive path
+                    let class = e.rsplit_once("!/").map_or(&**e, |(_, c)| c);
+                    if let Some(name) = class.strip_suffix(".class").filter(|n| r.pattern.is_match(n)) {
+                        push(name, e);
+                    }
+                },
+                RuleTarget::File => for e in data.entries.iter().filter(|e| r.pattern.is_match(e)) {
+                    push(e, e);
+                },
+                RuleTarget::Ref | RuleTarget::String => {
+                    let m = if r.target == RuleTarget::Ref { &data.refs } else { &data.strings };
+                    for (k, classes) in m.iter().filter(|(k, _)| r.pattern.is_match(k)) {
+                        for &ci in classes {
+                            push(k, &data.entries[ci]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct RulesInfo {
+    path: Option<Box<Path>>,
+    rules: usize,
+}
+
+/// Sum of the weights of the distinct rules with findings, capped at 100
+fn risk_score(findings: &[Finding]) -> u32 {
+    let mut rules = findings.iter().map(|f| (&*f.rule, f.severity)).collect::<Vec<_>>();
+    rules.sort_unstable();
+    rules.dedup();
+    rules.iter().map(|(_, s)| s.weight()).sum::<u32>().min(100)
+}
+
+#[derive(Serialize)]
+pub struct ScanReport {
+    /// Risk score from 0 (nothing found) to 100
+    score: u32,
+    findings: Vec<Finding>,
+}
+
+/// Combines the built-in findings of a jar with the findings of the rules.
+pub fn report(data: &ScanData, rules: &ScanRules) -> ScanReport {
+    let mut findings = data.findings.clone();
+    rules.apply(data, &mut findings);
+    findings.sort_by(|a, b| b.severity.cmp(&a.severity)
+        .then_with(|| a.rule.cmp(&b.rule))
+        .then_with(|| a.location.cmp(&b.location)));
+    ScanReport { score: risk_score(&findings), findings }
+}
+
+#[derive(Serialize)]
+pub struct ScanSummary {
+    score: u32,
+    /// Number of findings by severity
+    counts: BTreeMap<Severity, usize>,
+    /// Title of the most severe finding
+    top: Option<Box<str>>,
+}
+
+/// Scores all jars, riskiest first.
+pub fn summaries(it: impl Iterator<Item = (Id, Arc<ScanData>)>, rules: &ScanRules) -> Vec<(Id, ScanSummary)> {
+    let mut v = it.map(|(id, data)| {
+        let r = report(&data, rules);
+        let mut counts = BTreeMap::new();
+        for f in &r.findings {
+            *counts.entry(f.severity).or_insert(0) += 1;
+        }
+        (id, ScanSummary { score: r.score, counts, top: r.findings.first().map(|f| f.title.clone()) })
+    }).collect::<Vec<_>>();
+    v.sort_by_key(|(_, s)| Reverse(s.score));
+    v
+}
@@ -0,0 +1,174 @@
+use std::{io::{Read, Seek}, sync::Arc};
+
+use cm_zipext::FileMap;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+
+use crate::{ext::Extension, id::Id, jvm::StrIndexMapped, mappings::{Remap, Remapper}};
+
+const MAX_RESULTS: usize = 1000;
+/// Text files larger than this are not indexed
+const MAX_TEXT_SIZE: u64 = 1 << 20;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    Literal,
+    Regex,
+    Fuzzy,
+}
+
+pub enum Matcher {
+    Literal(Box<str>),
+    Regex(regex::Regex),
+    Fuzzy(Box<[char]>),
+}
+impl Matcher {
+    pub fn new(query: &str, mode: SearchMode) -> anyhow::Result<Self> {
+        if query.is_empty() {
+            anyhow::bail!("Empty query");
+        }
+        Ok(match mode {
+            SearchMode::Literal => Self::Literal(query.to_lowercase().into_boxed_str()),
+            SearchMode::Regex => Self::Regex(regex::Regex::new(query)?),
+            SearchMode::Fuzzy => Self::Fuzzy(query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect()),
+        })
+    }
+    /// Returns the match score (higher is better) if the text matches.
+    pub fn score(&self, text: &str) -> Option<i64> {
+        match self {
+            Self::Literal(q) => text.to_lowercase().contains(&**q).then(|| -(text.len() as i64)),
+            Self::Regex(r) => r.is_match(text).then(|| -(text.len() as i64)),
+            Self::Fuzzy(q) => fuzzy_score(q, text),
+        }
+    }
+}
+
+/// Scores `text` by the best alignment of `query` characters, rewarding consecutive
+/// characters and matches at word starts, and penalizing gaps and long texts.
+fn fuzzy_score(query: &[char], text: &str) -> Option<i64> {
+    const NONE: i64 = i64::MIN / 2;
+    let lower = text.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect::<Vec<_>>();
+    // Most strings do not match at all, so check for a subsequence first
+    let mut it = lower.iter();
+    if !query.iter().all(|q| it.any(|c| c == q)) {
+        return None;
+    }
+    let chars = text.chars().collect::<Vec<_>>();
+    let bonus = (0..chars.len()).map(|i| {
+        let prev = if i == 0 { ' ' } else { chars[i - 1] };
+        if !prev.is_alphanumeric() || (prev.is_lowercase() && chars[i].is_uppercase()) { 3 } else { 0 }
+    }).collect::<Vec<i64>>();
+    let mut prev = vec![0; lower.len()];
+    for (j, &qc) in query.iter().enumerate() {
+        let mut row = vec![NONE; lower.len()];
+        let mut far = NONE;
+        for p in 0..lower.len() {
+            if p >= 4 {
+                far = far.max(prev[p - 4]);
+            }
+            let cand = if j == 0 {
+                0
+            } else {
+                let at = |d: usize| if p >= d { prev[p - d] } else { NONE };
+                (at(1) + 5).max(at(2) - 1).max(at(3) - 2).max(far - 3)
+            };
+            if lower[p] == qc && cand > NONE / 2 {
+                row[p] = cand + 1 + bonus[p];
+            }
+        }
+        prev = row;
+    }
+    prev.into_iter().max().filter(|&s| s > NONE / 2).map(|s| s - lower.len() as i64 / 8)
+}
+
+/// JSON, TOML and lang files of a jar
+pub struct ModTexts(Box<[(Box<str>, Box<str>)]>);
+
+pub fn gather_texts<RS: Read + Seek>(fm: &FileMap, rs: &mut RS) -> anyhow::Result<ModTexts> {
+    let mut v = vec![];
+    for (k, fe) in fm.iter().filter(|(k, fe)| {
+        fe.size() <= MAX_TEXT_SIZE
+            && (Extension::Json.matches(k.as_ref()) || Extension::Toml.matches(k.as_ref()) || k.ends_with(".lang"))
+    }) {
+        if let Ok(s) = fe.string_from(rs) {
+            v.push((k.clone(), s.into_boxed_str()));
+        }
+    }
+    Ok(ModTexts(v.into_boxed_slice()))
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum HitSource {
+    /// Constant pool string used by the listed classes
+    Constant { classes: Box<[Box<str>]> },
+    Class,
+    File { path: Box<str>, line: usize },
+}
+
+#[derive(Serialize)]
+pub struct SearchHit {
+    id: Id,
+    #[serde(flatten)]
+    source: HitSource,
+    text: Box<str>,
+    #[serde(skip)]
+    score: i64,
+}
+/// Constants and file contents are kept as they are.
+impl Remap for SearchHit {
+    fn remap(&mut self, r: &Remapper) {
+        match &mut self.source {
+            HitSource::Class => r.class(&mut self.text),
+            HitSource::Constant { classes } => classes.iter_mut().for_each(|c| r.class(c)),
+            HitSource::File { .. } => {}
+        }
+    }
+}
+
+fn search_strings(m: &Matcher, id: Id, si: &StrIndexMapped) -> Vec<SearchHit> {
+    let classes = si.classes.iter().filter_map(|c| Some(SearchHit {
+        id,
+        source: HitSource::Class,
+        text: c.clone(),
+        score: m.score(c)?,
+    }));
+    let consts = si.strings.iter().filter_map(|(s, users)| Some(SearchHit {
+        id,
+        score: m.score(s)?,
+        source: HitSource::Constant {
+            classes: users.iter().map(|&i| si.classes[i].clone()).collect(),
+        },
+        text: s.clone(),
+    }));
+    classes.chain(consts).collect()
+}
+
+fn search_texts(m: &Matcher, id: Id, mt: &ModTexts) -> Vec<SearchHit> {
+    mt.0.iter()
+        .flat_map(|(path, text)| {
+            text.lines().enumerate().filter_map(move |(i, l)| Some(SearchHit {
+                id,
+                score: m.score(l)?,
+                source: HitSource::File { path: path.clone(), line: i + 1 },
+                text: l.trim().into(),
+            }))
+        })
+        .collect()
+}
+
+/// Searches class names, constant pool strings and text files of all mods.
+pub fn search(
+    m: &Matcher,
+    strings: Vec<(Id, Arc<StrIndexMapped>)>,
+    texts: Vec<(Id, Arc<ModTexts>)>,
+) -> Vec<SearchHit> {
+    let mut hits = strings.into_par_iter()
+        .flat_map_iter(|(id, si)| search_strings(m, id, &si))
+        .chain(texts.into_par_iter().flat_map_iter(|(id, mt)| search_texts(m, id, &mt)))
+        .collect::<Vec<_>>();
+    hits.sort_by_key(|h| std::cmp::Reverse(h.score));
+    hits.truncate(MAX_RESULTS);
+    hits
+}
@@ -1,7 +1,7 @@
 use std::{fs, io, net::{Ipv4Addr, SocketAddrV4}, ops::RangeInclusive, sync::{atomic::AtomicBool, Arc}};
 
-use axum::{body::Body, extract::{Path, State}, http::{header, HeaderMap, HeaderValue, StatusCode}, response::{IntoResponse, Response}, routing, Router};
-use crate::{id::Id, rt, workspace::DirWS};
+use axum::{body::Body, extract::{Path, Query, State}, http::{header, HeaderMap, HeaderValue, StatusCode}, response::{IntoResponse, Response}, routing, Router};
+use crate::{disasm, id::Id, rt, workspace::{self, DirWS}};
 
 #[derive(Clone)]
 pub struct Server {
@@ -70,6 +70,34 @@ async fn get_raw_data(
     (status, hm, body).into_response()
 }
 
+#[derive(serde::Deserialize)]
+struct DisasmQuery {
+    method: Option<String>,
+}
+
+async fn get_disasm(
+    Path((id, path)): Path<(Id, String)>,
+    Query(q): Query<DisasmQuery>,
+    State(ws): State<DirWS>,
+) -> Response {
+    let hm = [(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"), (header::CONTENT_TYPE, "text/plain; charset=utf-8")];
+    let mods = ws.mods_read();
+    let Some(fi) = mods.get(&id) else {
+        return (StatusCode::NOT_FOUND, hm).into_response();
+    };
+    let remapper = ws.mappings().read().remapper();
+    let res = workspace::read_entry(fi, &path)
+        .and_then(|data| disasm::disassemble(&data, q.method.as_deref(), remapper.as_deref()));
+    drop(mods);
+    match res {
+        Ok(text) => (StatusCode::OK, hm, text).into_response(),
+        Err(e) => {
+            eprintln!("Error in disasm: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, hm, e.to_string()).into_response()
+        }
+    }
+}
+
 fn parse_range(hv: &HeaderValue, size: u64) -> anyhow::Result<RangeInclusive<usize>> {
     let range = hv.to_str()?;
     let pr = http_range_header::parse_range_header(range)?;
@@ -82,7 +110,8 @@ pub async fn run_server(port: u16, ws: DirWS) -> anyhow::Result<()> {
     let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, port);
     let listener = tokio::net::TcpListener::bind(addr).await?;
     let app = Router::new()
-        .route("/raw/:id/*path", routing::get(get_raw_data))
+        .route("/raw/{id}/{*path}", routing::get(get_raw_data))
+        .route("/disasm/{id}/{*path}", routing::get(get_disasm))
         .with_state(ws);
     Ok(axum::serve(listener, app).await?)
 }
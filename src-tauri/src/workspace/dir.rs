@@ -8,9 +8,9 @@ use indexmap::IndexMap;
 use parking_lot::{RwLock, RwLockReadGuard};
 use rayon::prelude::*;
 
-use crate::{ext, id::Id, loader::ModTypeData};
+use crate::{ext, hierarchy::{ClassDecls, Hierarchy}, id::Id, loader::ModTypeData, mappings::{MappingState, Remap, Remapped}, scan::ScanRules};
 
-use super::{gather_mod_data, FileInfo, LockMap, Namespaces};
+use super::{gather_class_decls, gather_mod_data, AllGather, FileInfo, LockMap, Namespaces};
 
 fn id_from_time(path: &Path) -> anyhow::Result<Id> {
     let time = fs::metadata(path)?.modified()?;
@@ -24,6 +24,10 @@ pub struct DirWS {
     mod_entries: LockMap<FileInfo>,
     filemaps: LockMap<Arc<cm_zipext::FileMap>>,
     namespaces: Namespaces,
+    mappings: Arc<RwLock<MappingState>>,
+    scan_rules: Arc<RwLock<ScanRules>>,
+    /// Built on the first query, as its indices must stay the same between pages
+    hierarchy: Arc<RwLock<Option<Arc<Hierarchy>>>>,
 }
 impl DirWS {
     pub fn new() -> Self {
@@ -32,6 +36,9 @@ impl DirWS {
             mod_entries: Arc::new(RwLock::new(IndexMap::new())),
             filemaps: Arc::new(RwLock::new(IndexMap::new())),
             namespaces: Arc::new(RwLock::new(IndexMap::new())),
+            mappings: Arc::new(RwLock::new(MappingState::default())),
+            scan_rules: Arc::new(RwLock::new(ScanRules::default())),
+            hierarchy: Arc::new(RwLock::new(None)),
         }
     }
     pub const fn mods(&self) -> &LockMap<FileInfo> {
@@ -45,11 +52,19 @@ impl DirWS {
         *self.mod_entries.write() = IndexMap::new();
         *self.filemaps.write() = IndexMap::new();
         *self.namespaces.write() = IndexMap::new();
+        *self.mappings.write() = MappingState::default();
+        *self.scan_rules.write() = ScanRules::default();
+        *self.hierarchy.write() = None;
+    }
+    pub fn dir_path(&self) -> Box<Path> {
+        self.dir_path.read().clone()
     }
     pub fn is_empty(&self) -> bool {
         &**self.dir_path.read() != Path::new("")
     }
     pub fn prepare(&self, dir_path: PathBuf) -> anyhow::Result<()> {
+        let mappings = MappingState::restore(&dir_path);
+        *self.mappings.write() = mappings;
         *self.dir_path.write() = dir_path.into_boxed_path();
         let rdir = fs::read_dir(&*self.dir_path.read())?;
         let mut jars = rdir
@@ -99,6 +114,7 @@ impl DirWS {
         *self.mod_entries.write() = jars;
         *self.filemaps.write() = fmaps;
         *self.namespaces.write() = ns;
+        *self.hierarchy.write() = None;
         Ok(())
     }
     pub fn entry_path(&self, id: Id) -> anyhow::Result<Box<Path>> {
@@ -113,4 +129,29 @@ impl DirWS {
     pub fn namespace_keys(&self) -> Vec<Box<str>> {
         self.namespaces.read().keys().cloned().collect()
     }
+    pub const fn mappings(&self) -> &Arc<RwLock<MappingState>> {
+        &self.mappings
+    }
+    pub const fn scan_rules(&self) -> &Arc<RwLock<ScanRules>> {
+        &self.scan_rules
+    }
+    /// Class hierarchy of all mods, kept until the mods change
+    pub fn hierarchy(&self) -> Arc<Hierarchy> {
+        // Holding the lock while building keeps concurrent queries from building it again
+        let mut h = self.hierarchy.write();
+        if let Some(h) = &*h {
+            return Arc::clone(h);
+        }
+        let mods = self.mod_entries.gather_with(false, gather_class_decls);
+        let built = Arc::new(Hierarchy::new(
+            mods.iter().filter_map(|(id, fi)| Some((*id, fi.get::<ClassDecls>()?))),
+        ));
+        drop(mods);
+        *h = Some(Arc::clone(&built));
+        built
+    }
+    /// Wraps a value to be serialized with the names of the selected mappings namespace
+    pub fn remapped<T: Remap>(&self, v: T) -> Remapped<T> {
+        Remapped::new(v, self.mappings.read().remapper().as_deref())
+    }
 }
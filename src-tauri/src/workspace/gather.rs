@@ -4,7 +4,7 @@ use indexmap::IndexMap;
 use parking_lot::RwLockReadGuard;
 use rayon::iter::ParallelIterator;
 
-use crate::{ext, extract, id::Id, jvm, loader};
+use crate::{events, ext, extract, hierarchy, id::Id, jvm, libs, loader, modules, native, refs, registry, scan, search};
 
 use super::{FileInfo, LockMap};
 
@@ -35,6 +35,13 @@ fn get_file_map(fi: &FileInfo) -> anyhow::Result<Arc<cm_zipext::FileMap>> {
     fi.filemap.upgrade().ok_or_else(|| anyhow::anyhow!("No file map"))
 }
 
+/// Reads a single entry of a mod file.
+pub fn read_entry(fi: &FileInfo, path: &str) -> anyhow::Result<Vec<u8>> {
+    let fm = get_file_map(fi)?;
+    let fe = fm.get(path).ok_or_else(|| anyhow::anyhow!("Entry not found: {path}"))?;
+    fe.vec_from(&mut fi.file_buf()?)
+}
+
 pub fn gather_mod_data(fi: &FileInfo) -> anyhow::Result<loader::ModTypeData> {
     let fm = get_file_map(fi)?;
     loader::extract_mod_info(&fm, &mut fi.file_buf()?)
@@ -49,12 +56,16 @@ pub fn gather_file_type_sizes(fi: &FileInfo) -> anyhow::Result<extract::ModFileT
 }
 pub fn gather_content_sizes(fi: &FileInfo) -> anyhow::Result<extract::ModContentSizes> {
     let fm = get_file_map(fi)?;
-    extract::compute_mod_content_sizes(&fm)
+    extract::compute_mod_content_sizes(&fm, &mod_entry_classes(fi, &fm))
 }
 pub fn gather_inheritance(fi: &FileInfo) -> anyhow::Result<ext::Inheritance> {
     let fm = get_file_map(fi)?;
     jvm::gather_inheritance_v2(&fm, &mut fi.file_mem()?)
 }
+pub fn gather_class_decls(fi: &FileInfo) -> anyhow::Result<hierarchy::ClassDecls> {
+    let fm = get_file_map(fi)?;
+    hierarchy::gather_class_decls(&fm, &mut fi.file_mem()?)
+}
 pub fn gather_complexity(fi: &FileInfo) -> anyhow::Result<jvm::Complexity> {
     let fm = get_file_map(fi)?;
     jvm::gather_complexity(&fm, &mut fi.file_mem()?)
@@ -67,11 +78,27 @@ pub fn gather_str_index(fi: &FileInfo) -> anyhow::Result<jvm::StrIndexMapped> {
     let fm = get_file_map(fi)?;
     jvm::gather_str_index_v2(&fm, &mut fi.file_mem()?)
 }
+pub fn gather_texts(fi: &FileInfo) -> anyhow::Result<search::ModTexts> {
+    let fm = get_file_map(fi)?;
+    search::gather_texts(&fm, &mut fi.file_buf()?)
+}
+pub fn gather_class_refs(fi: &FileInfo) -> anyhow::Result<refs::ClassRefs> {
+    let fm = get_file_map(fi)?;
+    refs::gather_class_refs(&fm, &mut fi.file_mem()?)
+}
 pub fn gather_mod_entries(fi: &FileInfo) -> anyhow::Result<jvm::ModEntries> {
     let fm = get_file_map(fi)?;
     let Some(moddata) = fi.get::<loader::ModTypeData>() else { return Err(anyhow::anyhow!("No moddata")) };
     loader::extract_mod_entries(&fm, moddata.as_ref(), &mut fi.file_mem()?)
 }
+/// Entry classes of a mod, or none for other files, used to tell the mod's own packages from bundled libraries
+fn mod_entry_classes(fi: &FileInfo, fm: &cm_zipext::FileMap) -> Box<[Box<str>]> {
+    if let Some(me) = fi.get::<jvm::ModEntries>() {
+        return me.classes.clone();
+    }
+    let moddata = fi.get::<loader::ModTypeData>().or_else(|| loader::extract_mod_info(fm, &mut fi.file_buf().ok()?).ok().map(Arc::new));
+    moddata.and_then(|md| loader::extract_mod_entries(fm, &md, &mut fi.file_mem().ok()?).ok()).map_or_else(Box::default, |me| me.classes)
+}
 pub fn gather_recipes(fi: &FileInfo) -> anyhow::Result<extract::RecipeTypeMap> {
     let fm = get_file_map(fi)?;
     extract::gather_recipes(&fm, &mut fi.file_mem()?)
@@ -79,4 +106,33 @@ pub fn gather_recipes(fi: &FileInfo) -> anyhow::Result<extract::RecipeTypeMap> {
 pub fn gather_playable(fi: &FileInfo) -> anyhow::Result<extract::PlayableFiles> {
     let fm = get_file_map(fi)?;
     Ok(extract::gather_playable_files(&fm))
+}
+pub fn gather_class_versions(fi: &FileInfo) -> anyhow::Result<jvm::ClassVersions> {
+    let fm = get_file_map(fi)?;
+    jvm::gather_class_versions(&fm, &mut fi.file_mem()?)
+}
+pub fn gather_libraries(fi: &FileInfo) -> anyhow::Result<libs::ModLibraries> {
+    let fm = get_file_map(fi)?;
+    libs::gather_libraries(&fm, &mod_entry_classes(fi, &fm), &mut fi.file_buf()?)
+}
+pub fn gather_module(fi: &FileInfo) -> anyhow::Result<modules::JavaModule> {
+    let fm = get_file_map(fi)?;
+    let moddata = fi.get::<loader::ModTypeData>();
+    modules::gather_module(&fm, moddata.as_deref(), &fi.name(), &mut fi.file_buf()?)
+}
+pub fn gather_registrations(fi: &FileInfo) -> anyhow::Result<registry::Registrations> {
+    let fm = get_file_map(fi)?;
+    registry::gather_registrations(&fm, &mut fi.file_mem()?)
+}
+pub fn gather_event_handlers(fi: &FileInfo) -> anyhow::Result<events::EventHandlers> {
+    let fm = get_file_map(fi)?;
+    events::gather_event_handlers(&fm, &mut fi.file_mem()?)
+}
+pub fn gather_scan_data(fi: &FileInfo) -> anyhow::Result<scan::ScanData> {
+    let fm = get_file_map(fi)?;
+    scan::gather_scan_data(&fm, &mut fi.file_mem()?)
+}
+pub fn gather_native_usage(fi: &FileInfo) -> anyhow::Result<native::NativeUsage> {
+    let fm = get_file_map(fi)?;
+    native::gather_native_usage(&fm, &mut fi.file_mem()?)
 }
\ No newline at end of file
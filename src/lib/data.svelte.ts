@@ -19,7 +19,7 @@ export function queryable<T>(source: Source<T[]>, by: (x: T) => string) {
 }
 export type Queryable<T> = ReturnType<typeof queryable<T>>
 
-const perPage = 40
+export const perPage = 40
 
 export function paginate<T>(source: Source<T[]>) {
   source = canon(source)
@@ -2,7 +2,15 @@
 export const routes = [
   { n: 'Errors', p: '/errors' },
   { n: 'Strings', p: '/strings' },
+  { n: 'Classes', p: '/classes' },
   { n: 'Sizes', p: '/sizes' },
+  { n: 'API usage', p: '/apis' },
+  { n: 'Java', p: '/java' },
+  { n: 'Module', p: '/module' },
+  { n: 'Registries', p: '/registries' },
+  { n: 'Events', p: '/events' },
+  { n: 'Security', p: '/security' },
+  { n: 'Native code', p: '/native' },
   { n: 'File types', p: '/filetypes' },
   { n: 'Recipes', p: '/recipes' },
   { n: 'Player', p: '/player' },
@@ -14,7 +14,44 @@ export type Inheritance = {
   indices: [string, number][],
   inherits: number[][]
 }
-export type ClassCounting = {total: number, fields: number, methods: number, code: [string, number][]}
+export type MethodMetrics = {
+  name: string,
+  opcodes: number,
+  size: number,
+  cyclomatic: number,
+  max_stack: number,
+  max_locals: number,
+  handlers: number,
+  indy: number,
+  lambdas: number
+}
+export type ClassCounting = {
+  total: number,
+  fields: number,
+  methods: number,
+  cyclomatic: number,
+  max_stack: number,
+  max_locals: number,
+  handlers: number,
+  indy: number,
+  lambdas: number,
+  largest: number,
+  kotlin: string | null,
+  code: MethodMetrics[]
+}
+export type ComplexitySummary = {
+  classes: number,
+  methods: number,
+  opcodes: number,
+  cyclomatic: number,
+  max_cyclomatic: number,
+  handlers: number,
+  indy: number,
+  lambdas: number,
+  huge_methods: number,
+  near_limit_methods: number,
+  largest: [string, string, number][]
+}
 export type Complexity = {[k: string]: ClassCounting}
 export type Tags = {[k: string]: {[k2: string]: {[k3: string]: number}}}
 export type ModData = {
@@ -33,7 +70,23 @@ export type StrIndex = {
   classes: string[],
   strings: [string, number[]][]
 }
-export type ContentTypes = 'meta' | 'classes' | 'assets' | 'data' | 'other'
+export type ContentTypes = 'meta' | 'classes' | 'assets' | 'data' | 'libraries' | 'other'
+export type BundledLib = {
+  name: string,
+  root: string,
+  relocated: boolean,
+  version: string | null,
+  sizes: [number, number, number]
+}
+export type SharedLib = {
+  name: string,
+  copies: [FileID, string | null, [number, number, number]][]
+}
+
+export type ApiRef = {owner: string, kind: 'class' | 'field' | 'method', name: string, desc: string}
+export type ApiProvider = {type: 'minecraft' | 'loader' | 'unknown'} | {type: 'mod', id: FileID}
+export type ProviderUsage = {provider: ApiProvider, apis: [ApiRef, number][]}
+export type ApiUsers = {api: ApiRef, provider: ApiProvider, users: [FileID, string[]][]}
 
 export function invokeWS(cmd: keyof InvokeAPI, args?: InvokeAPI[typeof cmd][0]) {
   return invoke<InvokeAPI[typeof cmd][1]>(cmd, args)
@@ -60,10 +113,231 @@ export async function wsModErrors(id: FileID) {
 export async function wsModEntries(id: FileID) {
   return await invoke<{}>('ws_mod_entries', {id})
 }
+export type AnnotationInfo = {type_name: string, visible: boolean, elements: string[], target: string | null}
+export type MemberDetails = {
+  access: string[],
+  name: string,
+  descriptor: string,
+  signature: string | null,
+  display: string,
+  kotlin: string | null,
+  annotations: AnnotationInfo[],
+  exceptions: string[]
+}
+export type ClassDetails = {
+  version: [number, number],
+  access: string[],
+  name: string,
+  super_class: string | null,
+  interfaces: string[],
+  signature: string | null,
+  source_file: string | null,
+  kotlin: string | null,
+  annotations: AnnotationInfo[],
+  inner_classes: {inner: string, outer: string | null, name: string | null, access: string[]}[],
+  nest_host: string | null,
+  nest_members: string[],
+  permitted_subclasses: string[],
+  record_components: string[] | null,
+  fields: MemberDetails[],
+  methods: MemberDetails[],
+  pool: [number, string, string][]
+}
+export async function wsClassPaths(id: FileID) {
+  return await invoke<string[] | null>('ws_class_paths', {id})
+}
+export async function wsClassInfo(id: FileID, path: string) {
+  return await invoke<ClassDetails>('ws_class_info', {id, path})
+}
+export async function wsClassVerify(id: FileID, path: string) {
+  return await invoke<[number, string, string][]>('ws_class_verify', {id, path})
+}
+export async function wsApiUsage(id: FileID) {
+  return await invoke<ProviderUsage[]>('ws_api_usage', {id})
+}
+export async function wsApiSearch(query: string) {
+  return await invoke<ApiUsers[]>('ws_api_search', {query})
+}
+export type SearchMode = 'literal' | 'regex' | 'fuzzy'
+export type SearchHit = {id: FileID, text: string} & (
+  {type: 'constant', classes: string[]} | {type: 'class'} | {type: 'file', path: string, line: number}
+)
+export async function wsSearch(query: string, mode: SearchMode) {
+  return await invoke<SearchHit[]>('ws_search', {query, mode})
+}
 export const wsDepMap = invokeWithMode<[string[], [string, Record<number, [string, string]>][]]>('ws_dep_map')
 export const wsFileTypeSizes = invokeWithMode<Record<string, [number, number, number]>>('ws_file_type_sizes')
 export const wsContentSizes = invokeWithMode<Record<ContentTypes, [number, number, number]>>('ws_content_sizes')
+export const wsLibraries = invokeWithMode<BundledLib[]>('ws_libraries')
+export async function wsSharedLibraries() {
+  return await invoke<SharedLib[]>('ws_shared_libraries')
+}
+export type ClassVersions = {
+  majors: Record<number, number>,
+  required: number | null,
+  multi_release: boolean,
+  versioned: number[],
+  preview: string[]
+}
+export type JavaCheck = {
+  runtime: { minecraft: string | null, java: number | null },
+  implied: number | null,
+  mods: {id: FileID, required: number, instance: boolean, minecraft: boolean}[]
+}
+export const wsClassVersions = invokeWithMode<ClassVersions>('ws_class_versions')
+export async function wsJavaCheck() {
+  return await invoke<JavaCheck>('ws_java_check')
+}
+export type ModuleNameSource = 'descriptor' | 'manifest' | 'mod_id' | 'file_name'
+export type ModuleExports = {package: string, to: string[]}
+export type ModuleDescriptor = {
+  name: string,
+  flags: string[],
+  version: string | null,
+  main_class: string | null,
+  requires: {module: string, flags: string[], version: string | null}[],
+  exports: ModuleExports[],
+  opens: ModuleExports[],
+  uses: string[],
+  provides: [string, string[]][]
+}
+export type JavaModule = {
+  name: string,
+  source: ModuleNameSource,
+  layered: boolean,
+  descriptor: ModuleDescriptor | null,
+  packages: string[]
+}
+export type ModuleCheck = {
+  modules: {id: FileID, name: string, source: ModuleNameSource, requires: string[]}[],
+  split_packages: [string, FileID[]][],
+  collisions: [string, FileID[]][]
+}
+export async function wsModule(id: FileID) {
+  return await invoke<JavaModule>('ws_module', {id})
+}
+export async function wsModuleCheck() {
+  return await invoke<ModuleCheck>('ws_module_check')
+}
+export type RegistryKind = 'block' | 'item' | 'entity' | 'block_entity' | 'menu' | 'sound' | 'particle'
+export type RegistrySources = {bytecode: boolean, strings: boolean, assets: boolean}
+export type Registrations = {
+  entries: Partial<Record<RegistryKind, Record<string, RegistrySources>>>,
+  unclassified: number
+}
+export type RegistryCounts = Partial<Record<RegistryKind, number>>
+export type RegistryTotals = {
+  minecraft: string | null,
+  totals: RegistryCounts,
+  limits: RegistryCounts,
+  mods: [FileID, RegistryCounts][]
+}
+export const wsRegistries = invokeWithMode<Registrations>('ws_registries')
+export async function wsRegistryTotals() {
+  return await invoke<RegistryTotals>('ws_registry_totals')
+}
+export type EventHandler = {
+  event: string,
+  class: string,
+  method: string,
+  priority: string,
+  receive_canceled: boolean,
+  bus: 'game' | 'mod' | 'manual' | 'fabric'
+}
+export type EventListeners = {event: string, listeners: [FileID, EventHandler][]}
+export const wsEventHandlers = invokeWithMode<EventHandler[]>('ws_event_handlers')
+export async function wsEventListeners() {
+  return await invoke<EventListeners[]>('ws_event_listeners')
+}
+export type MappingFormat = 'tiny_v1' | 'tiny_v2' | 'tsrg' | 'tsrg2' | 'proguard' | 'srg' | 'mcp_csv'
+export type MappingsInfo = {
+  files: [string, MappingFormat, number][],
+  namespaces: string[],
+  selected: string | null
+}
+export async function wsMappings() {
+  return await invoke<MappingsInfo>('ws_mappings')
+}
+export async function wsMappingsAdd(path: string | null) {
+  return await invoke<MappingsInfo>('ws_mappings_add', {path})
+}
+export async function wsMappingsSelect(namespace: string | null) {
+  return await invoke<MappingsInfo>('ws_mappings_select', {namespace})
+}
+export async function wsMappingsClear() {
+  return await invoke<MappingsInfo>('ws_mappings_clear')
+}
+export type NativeOs = 'linux' | 'freebsd' | 'windows' | 'macos' | 'unknown'
+export type Portability = 'portable' | 'arm' | 'no_arm' | 'external'
+export type NativeUsage = {
+  libs: {path: string, size: number, format: 'elf' | 'pe' | 'macho' | 'unknown', os: NativeOs, archs: string[], bits: number | null}[],
+  methods: [string, string][],
+  loads: {class: string, method: string, call: string, library: string | null}[],
+  unreadable: [string, string][]
+}
+export type NativeSummary = {
+  portability: Portability,
+  libs: number,
+  methods: number,
+  loads: number,
+  platforms: [NativeOs, string][]
+}
+export async function wsNative(id: FileID) {
+  return await invoke<NativeUsage>('ws_native', {id})
+}
+export async function wsNativeSummary() {
+  return await invoke<[FileID, NativeSummary][]>('ws_native_summary')
+}
+export type Severity = 'info' | 'low' | 'medium' | 'high' | 'critical'
+export type Finding = {
+  rule: string,
+  severity: Severity,
+  title: string,
+  detail: string,
+  location: {entry: string, member: string | null, offset: number | null}
+}
+export type ScanReport = {score: number, findings: Finding[]}
+export type ScanSummary = {score: number, counts: Partial<Record<Severity, number>>, top: string | null}
+export type RulesInfo = {path: string | null, rules: number}
+export async function wsScan(id: FileID) {
+  return await invoke<ScanReport>('ws_scan', {id})
+}
+export async function wsScanSummary() {
+  return await invoke<[FileID, ScanSummary][]>('ws_scan_summary')
+}
+export async function wsScanRules() {
+  return await invoke<RulesInfo>('ws_scan_rules')
+}
+export async function wsScanRulesLoad(path: string | null) {
+  return await invoke<RulesInfo>('ws_scan_rules_load', {path})
+}
+export async function wsScanRulesClear() {
+  return await invoke<RulesInfo>('ws_scan_rules_clear')
+}
 export const wsInheritance = invokeWithMode<Inheritance>('ws_inheritance')
+export type HierarchyQuery = {
+  class?: string,
+  relation?: 'subtypes' | 'supertypes',
+  transitive?: boolean,
+  filter?: string,
+  offset?: number,
+  limit: number
+}
+export type ClassSummary = {
+  name: string,
+  interface: boolean,
+  declared: FileID[],
+  depth: number,
+  exact_depth: boolean,
+  subtypes: number
+}
+export type HierarchyPage = {class: ClassSummary | null, total: number, items: [number, ClassSummary][]}
+export async function wsHierarchy(query: HierarchyQuery) {
+  return await invoke<HierarchyPage>('ws_hierarchy', {query})
+}
 export const wsComplexity = invokeWithMode<Complexity>('ws_complexity')
+export async function wsComplexitySummary() {
+  return await invoke<[FileID, ComplexitySummary][]>('ws_complexity_summary')
+}
 export const wsTags = invokeWithMode<Tags>('ws_tags')
 export const wsRecipes = invokeWithMode<Record<string, string[]>>('ws_recipes')
@@ -0,0 +1,5 @@
+import { wsComplexitySummary } from '$lib/ws'
+
+export async function load() {
+    return {summary: await wsComplexitySummary()}
+}
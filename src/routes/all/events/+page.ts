@@ -0,0 +1,5 @@
+import { wsEventListeners } from '$lib/ws'
+
+export async function load() {
+    return {events: await wsEventListeners()}
+}
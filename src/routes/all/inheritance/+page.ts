@@ -1,5 +0,0 @@
-import { wsInheritance } from "$lib/ws"
-
-export function load() {
-    return wsInheritance(false)
-}
\ No newline at end of file
@@ -0,0 +1,5 @@
+import { wsJavaCheck } from '$lib/ws'
+
+export async function load() {
+    return {check: await wsJavaCheck()}
+}
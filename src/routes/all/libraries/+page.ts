@@ -0,0 +1,5 @@
+import { wsSharedLibraries } from '$lib/ws'
+
+export async function load() {
+    return {shared: await wsSharedLibraries()}
+}
@@ -0,0 +1,5 @@
+import { wsMappings } from '$lib/ws'
+
+export async function load() {
+    return {info: await wsMappings()}
+}
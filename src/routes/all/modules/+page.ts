@@ -0,0 +1,5 @@
+import { wsModuleCheck } from '$lib/ws'
+
+export async function load() {
+    return {check: await wsModuleCheck()}
+}
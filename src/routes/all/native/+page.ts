@@ -0,0 +1,5 @@
+import { wsNativeSummary } from '$lib/ws'
+
+export async function load() {
+    return {summary: await wsNativeSummary()}
+}
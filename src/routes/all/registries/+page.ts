@@ -0,0 +1,5 @@
+import { wsRegistryTotals } from '$lib/ws'
+
+export async function load() {
+    return {totals: await wsRegistryTotals()}
+}
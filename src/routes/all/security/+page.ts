@@ -0,0 +1,5 @@
+import { wsScanRules, wsScanSummary } from '$lib/ws'
+
+export async function load() {
+    return {rules: await wsScanRules(), summary: await wsScanSummary()}
+}
@@ -0,0 +1,5 @@
+import { wsApiUsage } from '$lib/ws'
+
+export async function load({ params }) {
+    return {usage: await wsApiUsage(params.id as FileID)}
+}
\ No newline at end of file
@@ -0,0 +1,5 @@
+import { wsClassPaths } from '$lib/ws'
+
+export async function load({ params }) {
+    return {paths: await wsClassPaths(params.id as FileID) ?? []}
+}
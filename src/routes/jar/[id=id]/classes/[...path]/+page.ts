@@ -0,0 +1,11 @@
+import { wsClassInfo, wsClassVerify } from '$lib/ws'
+
+export async function load({ params }) {
+    const id = params.id as FileID
+    const [info, problems] = await Promise.all([
+        // Corrupt classes can't be read, but the verifier still says why
+        wsClassInfo(id, params.path).catch(() => null),
+        wsClassVerify(id, params.path),
+    ])
+    return {path: params.path, info, problems}
+}
@@ -0,0 +1,5 @@
+import { wsEventHandlers } from '$lib/ws.js';
+
+export async function load({ params }) {
+    return {handlers: await wsEventHandlers(params.id as FileID) ?? []}
+}
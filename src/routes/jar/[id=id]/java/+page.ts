@@ -0,0 +1,5 @@
+import { wsClassVersions } from '$lib/ws.js';
+
+export async function load({ params }) {
+    return {versions: await wsClassVersions(params.id as FileID)}
+}
@@ -0,0 +1,5 @@
+import { wsModule } from '$lib/ws'
+
+export async function load({ params }) {
+    return {module: await wsModule(params.id as FileID)}
+}
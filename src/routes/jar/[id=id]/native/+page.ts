@@ -0,0 +1,5 @@
+import { wsNative } from '$lib/ws.js';
+
+export async function load({ params }) {
+    return {usage: await wsNative(params.id as FileID)}
+}
@@ -0,0 +1,5 @@
+import { wsRegistries } from '$lib/ws.js';
+
+export async function load({ params }) {
+    return {registries: await wsRegistries(params.id as FileID)}
+}
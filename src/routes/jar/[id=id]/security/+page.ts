@@ -0,0 +1,5 @@
+import { wsScan } from '$lib/ws.js';
+
+export async function load({ params }) {
+    return {report: await wsScan(params.id as FileID)}
+}
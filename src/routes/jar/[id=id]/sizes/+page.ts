@@ -1,5 +1,7 @@
-import { wsContentSizes } from "$lib/ws";
+import { wsContentSizes, wsLibraries } from "$lib/ws";
 
 export async function load({ params }) {
-    return {sizes: await wsContentSizes(params.id as FileID)}
+    const id = params.id as FileID
+    const [sizes, libs] = await Promise.all([wsContentSizes(id), wsLibraries(id)])
+    return {sizes, libs}
 }
\ No newline at end of file